@@ -70,7 +70,7 @@ fn mlir() {
     for l in llvm_libs().iter() {
         if l.contains("libMLIR") && l.contains(".a") {
             let len = l.len();
-            println!("cargo:rustc-link-lib={}", l[3..(len - 2)].to_string());
+            println!("cargo:rustc-link-lib={}", &l[3..(len - 2)]);
         }
     }
     println!("cargo:rustc-link-lib=LLVM");
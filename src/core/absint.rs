@@ -25,6 +25,8 @@ pub enum InterpreterState<L> {
 #[derive(Debug)]
 pub struct InterpreterFrame<L> {
     vs: Vec<Option<L>>,
+    ret: Option<L>,
+    executable: Vec<bool>,
     trace: Option<Operation>,
 }
 
@@ -47,20 +49,81 @@ impl<L> InterpreterFrame<L>
 where
     L: Clone,
 {
+    /// Get the join of the values of the terminators which exit the region
+    /// (terminators without successors, e.g. `base.return`).
     pub fn get_ret(&self) -> Option<L> {
-        self.vs.last().and_then(|v| v.clone())
+        self.ret.clone()
+    }
+
+    pub fn get(&self, v: Var) -> Option<&L> {
+        self.vs.get(v.get_id()).and_then(|l| l.as_ref())
+    }
+
+    /// Whether block `ind` was reached during interpretation.
+    pub fn is_executable(&self, ind: usize) -> bool {
+        self.executable.get(ind).copied().unwrap_or(false)
+    }
+
+    /// Get the specialized operation emitted by partial evaluation
+    /// (see [`Interpreter::with_partial_evaluation`]).
+    pub fn get_trace(&self) -> Option<&Operation> {
+        self.trace.as_ref()
+    }
+
+    pub fn take_trace(&mut self) -> Option<Operation> {
+        self.trace.take()
     }
 }
 
+/// A worklist-driven abstract interpreter for the first region
+/// of an operation.
+///
+/// Starting from the entry block (whose arguments are given by the initial environment),
+/// each step interprets a block from the worklist with the [`LatticeSemantics`] of its
+/// operations. Terminators with successors forward their operands to
+/// the arguments of the successors which may be taken (see [`SuccessorOperands`]
+/// and [`LatticeBranchSemantics`]), where
+/// values from different predecessors are joined with [`LatticeJoin`]. Whenever a value
+/// changes, the blocks which use it are re-queued - interpretation is
+/// finished when the worklist is empty (the environment is stable).
+///
+/// Operations which call a function (see [`CallLike`]) are resolved with the
+/// return value of the callee for the argument values at the call - when a call
+/// hasn't been resolved yet, the interpreter stops in the `Waiting` state
+/// until [`Interpreter::resolve`] is called (see [`interpret_with_calls`]).
+///
+/// Values only grow (they are joined with their previous values), so
+/// interpretation terminates for lattices of finite height. For lattices of
+/// infinite height, [`Interpreter::with_widening`] widens the arguments of loop headers
+/// (the targets of retreating edges) with [`LatticeWiden`], and
+/// [`Interpreter::with_narrowing`] recovers precision lost by widening
+/// with [`LatticeNarrow`] once a fixpoint is reached.
+///
+/// With [`Interpreter::with_partial_evaluation`], the interpreter also emits a copy
+/// of the operation which is specialized to the values it computed.
 #[derive(Debug)]
 pub struct Interpreter<L> {
     state: InterpreterState<L>,
-    active: usize,
     block_queue: VecDeque<usize>,
     env: Vec<Option<L>>,
+    ret: Option<L>,
+    executable: Vec<bool>,
+    users: Vec<Vec<usize>>,
+    widening_points: Vec<bool>,
+    updates: Vec<usize>,
+    widening: Option<(usize, LatticeOp<L>)>,
+    narrowing: Option<(usize, LatticeOp<L>)>,
+    calls: Vec<(Signature<L>, Option<L>)>,
+    materialize: Option<LatticeMaterializer<L>>,
+    fallback: Option<L>,
     trace: Option<OperationBuilder>,
 }
 
+// A binary lattice operation (widening or narrowing).
+type LatticeOp<L> = fn(&L, &L) -> L;
+
+type LatticeMaterializer<L> = fn(&L, LocationInfo) -> Result<Option<OperationBuilder>, Report>;
+
 pub trait LatticeSemantics<L> {
     fn propagate(&self, op: &Operation, vtypes: Vec<&L>) -> Result<L, Report>;
 }
@@ -69,40 +132,187 @@ pub trait LatticeJoin {
     fn join(&self, other: &Self) -> Self;
 }
 
+/// The greatest lower bound of two values (e.g. the intersection of intervals),
+/// which is used to refine values (see [`crate::core::ReducedProduct`]).
+pub trait LatticeMeet {
+    fn meet(&self, other: &Self) -> Self;
+}
+
+/// Widening accelerates the convergence of increasing chains
+/// (e.g. for intervals, unstable bounds are widened to infinity).
+///
+/// `self.widen(other)` must be an upper bound of both `self` and `other`,
+/// and every sequence of widenings must eventually stabilize.
+pub trait LatticeWiden {
+    fn widen(&self, other: &Self) -> Self;
+}
+
+/// Narrowing refines a value which was over-approximated by widening.
+///
+/// If `other` is below `self`, `self.narrow(other)` must be between `other` and `self`,
+/// and every sequence of narrowings must eventually stabilize.
+pub trait LatticeNarrow {
+    fn narrow(&self, other: &Self) -> Self;
+}
+
 pub trait LatticeConvert<L> {
     fn convert(&self) -> L;
 }
 
+/// Propagation rules for terminators, which determine the successors which
+/// may be taken from the values of their operands (e.g. for a conditional branch
+/// on a known condition). Terminators without a rule may take any successor.
+pub trait LatticeBranchSemantics<L> {
+    /// Get the positions (in [`Operation::get_successors`])
+    /// of the successors which may be taken.
+    fn get_feasible_successors(
+        &self,
+        op: &Operation,
+        vtypes: Vec<&L>,
+    ) -> Result<Vec<usize>, Report>;
+}
+
+/// Lattice values which can be materialized as an operation
+/// which produces the value (e.g. a constant).
+pub trait LatticeMaterialize {
+    /// Build an operation which produces the value, if the value is known exactly.
+    fn materialize(&self, loc: LocationInfo) -> Result<Option<OperationBuilder>, Report>;
+}
+
 impl<L> Interpreter<L>
 where
-    L: Clone + LatticeJoin + 'static,
+    L: Clone + PartialEq + LatticeJoin + 'static,
 {
-    pub fn new(_op: &Operation, env: Vec<Option<L>>) -> Interpreter<L> {
-        let vd = VecDeque::<usize>::new();
-        Interpreter {
+    /// Create an interpreter for the first region of `op`, where `env`
+    /// holds the values of the arguments of the entry block.
+    pub fn new(op: &Operation, env: Vec<Option<L>>) -> Interpreter<L> {
+        let mut interp = Interpreter {
             state: InterpreterState::Active,
-            active: 0,
-            block_queue: vd,
-            env,
+            block_queue: VecDeque::new(),
+            env: Vec::new(),
+            ret: None,
+            executable: Vec::new(),
+            users: Vec::new(),
+            widening_points: Vec::new(),
+            updates: Vec::new(),
+            widening: None,
+            narrowing: None,
+            calls: Vec::new(),
+            materialize: None,
+            fallback: None,
             trace: None,
+        };
+        let r = match op.get_regions().first() {
+            Some(r) if !r.get_blocks().is_empty() => r,
+            _ => {
+                interp.state = InterpreterState::Finished;
+                return interp;
+            }
+        };
+        interp.env = vec![None; r.len()];
+        interp.users = vec![Vec::new(); r.len()];
+        interp.executable = vec![false; r.get_blocks().len()];
+        for b in 0..r.get_blocks().len() {
+            for (_, o) in r.get_block_iter(b) {
+                for operand in o.get_operands().iter() {
+                    if let Some(users) = interp.users.get_mut(operand.get_id()) {
+                        if !users.contains(&b) {
+                            users.push(b);
+                        }
+                    }
+                }
+            }
+        }
+        let nblocks = r.get_blocks().len();
+        interp.widening_points = vec![false; nblocks];
+        interp.updates = vec![0; nblocks];
+        let dom = DominanceInfo::new(r);
+        let mut order = vec![usize::MAX; nblocks];
+        for (ind, b) in dom.get_reverse_postorder().iter().enumerate() {
+            order[*b] = ind;
+        }
+        for b in dom.get_reverse_postorder().iter() {
+            for s in r.get_block_successors(*b) {
+                if order[s] <= order[*b] {
+                    interp.widening_points[s] = true;
+                }
+            }
         }
+        for (a, l) in r.get_block(0).get_operands().iter().zip(env) {
+            interp.env[a.get_id()] = l;
+        }
+        interp.block_queue.push_back(0);
+        interp
+    }
+
+    /// Widen the arguments of loop headers (instead of joining them)
+    /// once they have changed `delay` times.
+    pub fn with_widening(mut self, delay: usize) -> Interpreter<L>
+    where
+        L: LatticeWiden,
+    {
+        self.widening = Some((delay, L::widen));
+        self
+    }
+
+    /// Once a fixpoint is reached, emit a copy of the operation which is specialized
+    /// to the values of the interpretation (see [`Interpreter::finish`]):
+    /// values which are known exactly are materialized with [`LatticeMaterialize`],
+    /// and replace their uses, and blocks which are not executable are removed, along
+    /// with branches to them (with [`FoldSuccessor`]).
+    ///
+    /// The operations which produce materialized values are kept (they may have side effects),
+    /// so pure operations which become dead are left for the `DCEPass`.
+    pub fn with_partial_evaluation(mut self) -> Interpreter<L>
+    where
+        L: LatticeMaterialize,
+    {
+        self.materialize = Some(L::materialize);
+        self
+    }
+
+    /// Give operations whose intrinsics don't support [`LatticeSemantics`]
+    /// the value `l` (instead of failing).
+    pub fn with_fallback(mut self, l: L) -> Interpreter<L> {
+        self.fallback = Some(l);
+        self
+    }
+
+    /// Once a fixpoint is reached, run (up to) `iterations`
+    /// rounds of narrowing at loop headers.
+    pub fn with_narrowing(mut self, iterations: usize) -> Interpreter<L>
+    where
+        L: LatticeNarrow,
+    {
+        self.narrowing = Some((iterations, L::narrow));
+        self
     }
 
     pub fn clone_frame(&self) -> Result<InterpreterFrame<L>, Report> {
         let frame = InterpreterFrame {
             vs: self.env.to_vec(),
+            ret: self.ret.clone(),
+            executable: self.executable.to_vec(),
             trace: None,
         };
         Ok(frame)
     }
 
+    /// Get the frame, along with the specialized operation
+    /// (see [`Interpreter::with_partial_evaluation`]).
+    pub fn finish(self) -> Result<InterpreterFrame<L>, Report> {
+        let mut frame = self.clone_frame()?;
+        frame.trace = match self.trace {
+            None => None,
+            Some(b) => Some(b.finish()?),
+        };
+        Ok(frame)
+    }
+
     pub fn get(&self, v: Var) -> Result<&L, Report> {
         match self.env.get(v.get_id()) {
-            Some(l) => match l {
-                Some(l) => Ok(l),
-                None => bail!(format!("No type for SSA variable {}.", v)),
-            },
-            None => bail!(format!("No type for SSA variable {}.", v)),
+            Some(Some(l)) => Ok(l),
+            _ => bail!(format!("No type for SSA variable {}.", v)),
         }
     }
 
@@ -114,26 +324,448 @@ where
             .collect::<Result<Vec<_>, _>>()
     }
 
-    // TODO: Change.
     pub fn insert(&mut self, v: Var, l: L) {
-        if self.env.len() < (v.get_id() - 1) {
-            self.env.push(Some(l));
-        } else {
-            self.env.insert(v.get_id(), Some(l));
+        if self.env.len() <= v.get_id() {
+            self.env.resize(v.get_id() + 1, None);
+        }
+        self.env[v.get_id()] = Some(l);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, InterpreterState::Finished)
+    }
+
+    /// Get the signature of the call which the interpreter is waiting on.
+    pub fn get_waiting(&self) -> Option<&Signature<L>> {
+        match &self.state {
+            InterpreterState::Waiting(sig) => Some(sig),
+            _ => None,
         }
     }
 
+    /// Resolve the call which the interpreter is waiting on
+    /// with the return value of the callee.
+    pub fn resolve(&mut self, ret: Option<L>) -> Result<(), Report> {
+        match std::mem::replace(&mut self.state, InterpreterState::Active) {
+            InterpreterState::Waiting(sig) => {
+                self.calls.push((sig, ret));
+                Ok(())
+            }
+            state => {
+                self.state = state;
+                bail!("The interpreter is not waiting on a call.")
+            }
+        }
+    }
+
+    fn call_signature(&self, call: &dyn CallLike, op: &Operation) -> Result<Signature<L>, Report> {
+        let callee = match call.get_callee(op) {
+            None => bail!(format!("{} has no callee.", op.get_intrinsic())),
+            Some(callee) => callee,
+        };
+        let args = self
+            .resolve_to_lattice(op)?
+            .into_iter()
+            .map(|l| Some(l.clone()))
+            .collect();
+        Ok(Signature::new(&callee, args))
+    }
+
+    fn get_call_result(&self, sig: &Signature<L>) -> Option<&Option<L>> {
+        self.calls
+            .iter()
+            .find(|(s, _)| s == sig)
+            .map(|(_, ret)| ret)
+    }
+
+    fn enqueue(&mut self, ind: usize) {
+        if !self.block_queue.contains(&ind) {
+            self.block_queue.push_back(ind);
+        }
+    }
+
+    // Join (or widen) `l` into the value of `v`, and re-queue the (executable)
+    // blocks which use `v` if the value changed.
+    fn join_value(&mut self, v: Var, l: L, widen: Option<LatticeOp<L>>) -> bool {
+        let new = match (self.env.get(v.get_id()), widen) {
+            (Some(Some(old)), None) => old.join(&l),
+            (Some(Some(old)), Some(widen)) => widen(old, &old.join(&l)),
+            _ => l,
+        };
+        if self.env.get(v.get_id()).and_then(|l| l.as_ref()) == Some(&new) {
+            return false;
+        }
+        self.insert(v, new);
+        let users = self.users.get(v.get_id()).cloned().unwrap_or_default();
+        for b in users.into_iter() {
+            if self.executable[b] {
+                self.enqueue(b);
+            }
+        }
+        true
+    }
+
+    /// Whether each successor of a terminator may be taken,
+    /// given the current values of its operands.
+    pub fn get_feasible_successors(&self, op: &Operation) -> Result<Vec<bool>, Report> {
+        let nsuccs = op.get_successors().len();
+        match op
+            .get_intrinsic()
+            .query_ref::<dyn LatticeBranchSemantics<L>>()
+        {
+            None => Ok(vec![true; nsuccs]),
+            Some(sema) => {
+                let feasible = sema.get_feasible_successors(op, self.resolve_to_lattice(op)?)?;
+                Ok((0..nsuccs).map(|ind| feasible.contains(&ind)).collect())
+            }
+        }
+    }
+
+    // The value of an operation, given the current values of its operands.
+    fn propagate(&self, op: &Operation) -> Result<L, Report> {
+        match (
+            op.get_intrinsic().query_ref::<dyn LatticeSemantics<L>>(),
+            &self.fallback,
+        ) {
+            (Some(lintr), _) => lintr.propagate(op, self.resolve_to_lattice(op)?),
+            (None, Some(l)) => Ok(l.clone()),
+            (None, None) => bail!("Intrinsic fails to support lattice semantics."),
+        }
+    }
+
+    fn propagate_successors(&mut self, r: &Region, op: &Operation) -> Result<(), Report> {
+        let intr = op.get_intrinsic();
+        let operands = match intr.query_ref::<dyn SuccessorOperands>() {
+            Some(v) => v.get_successor_operands(r, op),
+            None => op.get_successors().iter().map(|_| Vec::new()).collect(),
+        };
+        let feasible = self.get_feasible_successors(op)?;
+        for ((s, args), _) in op
+            .get_successors()
+            .iter()
+            .zip(operands)
+            .zip(feasible)
+            .filter(|(_, f)| *f)
+        {
+            let params = r.get_block(*s).get_operands();
+            if params.len() != args.len() {
+                bail!(format!(
+                    "{} forwards {} operands to block {}, which has {} arguments.",
+                    intr,
+                    args.len(),
+                    s,
+                    params.len()
+                ))
+            }
+            let widen = match self.widening {
+                Some((delay, widen)) if self.widening_points[*s] && self.updates[*s] >= delay => {
+                    Some(widen)
+                }
+                _ => None,
+            };
+            let mut changed = false;
+            for (param, arg) in params.iter().zip(args) {
+                let l = self.get(arg)?.clone();
+                changed |= self.join_value(*param, l, widen);
+            }
+            if changed {
+                self.updates[*s] += 1;
+            }
+            if !self.executable[*s] {
+                self.executable[*s] = true;
+                self.enqueue(*s);
+            }
+        }
+        Ok(())
+    }
+
+    /// Interpret the next block in the worklist.
     pub fn step(&mut self, op: &Operation) -> Result<(), Report> {
-        for (v, o) in op.get_regions()[0].get_block_iter(self.active) {
+        match self.state {
+            InterpreterState::Finished => return Ok(()),
+            InterpreterState::Waiting(_) => {
+                bail!("The interpreter is waiting on a call to be resolved.")
+            }
+            _ => (),
+        }
+        let ind = match self.block_queue.pop_front() {
+            None => {
+                self.state = InterpreterState::Finished;
+                return Ok(());
+            }
+            Some(ind) => ind,
+        };
+        self.executable[ind] = true;
+        let r = &op.get_regions()[0];
+        let last = r.get_block_iter(ind).last().map(|(v, _)| v);
+        for (v, o) in r.get_block_iter(ind) {
+            if !o.get_successors().is_empty() {
+                self.propagate_successors(r, o)?;
+                continue;
+            }
             let intr = o.get_intrinsic();
-            match intr.query_ref::<dyn LatticeSemantics<L>>() {
-                None => bail!("Intrinsic fails to support lattice semantics."),
-                Some(lintr) => {
-                    let vtypes = self.resolve_to_lattice(o)?;
-                    let ltype = lintr.propagate(o, vtypes)?;
-                    self.insert(v, ltype);
+            if let Some(call) = intr.query_ref::<dyn CallLike>() {
+                let sig = self.call_signature(call, o)?;
+                match self.get_call_result(&sig) {
+                    // The block is interpreted again once the call is resolved.
+                    None => {
+                        self.state = InterpreterState::Waiting(sig);
+                        self.block_queue.push_front(ind);
+                        return Ok(());
+                    }
+                    Some(Some(ret)) => {
+                        self.join_value(v, ret.clone(), None);
+                        continue;
+                    }
+                    // The callee doesn't return (so far), so the rest of the block is unreachable.
+                    Some(None) => break,
                 }
             }
+            let ltype = self.propagate(o)?;
+            if Some(v) == last {
+                self.ret = Some(match &self.ret {
+                    None => ltype.clone(),
+                    Some(ret) => ret.join(&ltype),
+                });
+            }
+            self.join_value(v, ltype, None);
+        }
+        if self.block_queue.is_empty() {
+            if let Some((iterations, narrow)) = self.narrowing {
+                self.narrow(op, iterations, narrow)?;
+            }
+            if let Some(materialize) = self.materialize {
+                self.trace = Some(self.specialize(op, materialize)?);
+            }
+            self.state = InterpreterState::Finished;
+        }
+        Ok(())
+    }
+
+    // Descending iteration from the fixpoint: blocks are visited in reverse postorder, and
+    // values are recomputed (without joining with their previous values), where the arguments
+    // of loop headers are narrowed.
+    fn narrow(
+        &mut self,
+        op: &Operation,
+        iterations: usize,
+        narrow: LatticeOp<L>,
+    ) -> Result<(), Report> {
+        let r = &op.get_regions()[0];
+        let dom = DominanceInfo::new(r);
+        for _ in 0..iterations {
+            let mut changed = false;
+            let mut ret: Option<L> = None;
+            for b in dom.get_reverse_postorder().iter().copied() {
+                if !self.executable[b] {
+                    continue;
+                }
+                let params = r.get_block(b).get_operands();
+                let mut incoming: Vec<Option<L>> = vec![None; params.len()];
+                for p in dom.get_predecessors(b).iter().copied() {
+                    let term = match r.get_block_iter(p).last() {
+                        Some((_, term)) if self.executable[p] => term,
+                        _ => continue,
+                    };
+                    let operands = match term.get_intrinsic().query_ref::<dyn SuccessorOperands>() {
+                        Some(trt) => trt.get_successor_operands(r, term),
+                        None => continue,
+                    };
+                    let feasible = self.get_feasible_successors(term)?;
+                    for ((s, args), f) in term.get_successors().iter().zip(operands).zip(feasible) {
+                        if *s != b || !f {
+                            continue;
+                        }
+                        for (ind, arg) in args.into_iter().enumerate().take(params.len()) {
+                            let l = self.get(arg)?;
+                            incoming[ind] = Some(match &incoming[ind] {
+                                None => l.clone(),
+                                Some(prev) => prev.join(l),
+                            });
+                        }
+                    }
+                }
+                for (param, l) in params.iter().zip(incoming) {
+                    let l = match l {
+                        None => continue,
+                        Some(l) => l,
+                    };
+                    let new = match (&self.env[param.get_id()], self.widening_points[b]) {
+                        (Some(old), true) => narrow(old, &l),
+                        _ => l,
+                    };
+                    if self.env[param.get_id()].as_ref() != Some(&new) {
+                        changed = true;
+                        self.insert(*param, new);
+                    }
+                }
+
+                let last = r.get_block_iter(b).last().map(|(v, _)| v);
+                for (v, o) in r.get_block_iter(b) {
+                    if !o.get_successors().is_empty() {
+                        continue;
+                    }
+
+                    // Calls with narrowed arguments which haven't been resolved keep their values.
+                    if let Some(call) = o.get_intrinsic().query_ref::<dyn CallLike>() {
+                        let sig = self.call_signature(call, o)?;
+                        if let Some(Some(ret)) = self.get_call_result(&sig).cloned() {
+                            if self.env[v.get_id()].as_ref() != Some(&ret) {
+                                changed = true;
+                                self.insert(v, ret);
+                            }
+                        }
+                        match self.env[v.get_id()] {
+                            None => break,
+                            Some(_) => continue,
+                        }
+                    }
+                    let ltype = self.propagate(o)?;
+                    if Some(v) == last {
+                        ret = Some(match ret {
+                            None => ltype.clone(),
+                            Some(ret) => ret.join(&ltype),
+                        });
+                    }
+                    if self.env[v.get_id()].as_ref() != Some(&ltype) {
+                        changed = true;
+                        self.insert(v, ltype);
+                    }
+                }
+            }
+            self.ret = ret;
+            if !changed {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // Copy `op` with the executable blocks of its region, where known values are materialized.
+    fn specialize(
+        &self,
+        op: &Operation,
+        materialize: LatticeMaterializer<L>,
+    ) -> Result<OperationBuilder, Report> {
+        let r = &op.get_regions()[0];
+        if let Region::Undirected(_) = r {
+            bail!(format!(
+                "Partial evaluation of {} requires an SSACFG region.",
+                op.get_intrinsic()
+            ))
+        }
+        let mut b =
+            OperationBuilder::default(op.get_intrinsic().clone(), op.get_location().clone());
+        for (k, attr) in op.get_attributes().iter() {
+            b.insert_attr(k, attr.clone());
+        }
+        b.push_region(Region::Directed(SSACFG::default()));
+
+        // Executable blocks (and their arguments) are created up front, so
+        // branches can refer to them.
+        let mut blocks = vec![None; r.get_blocks().len()];
+        let mut vars: Vec<Option<Var>> = vec![None; r.len()];
+        for (ind, blk) in blocks.iter_mut().enumerate() {
+            if !self.executable[ind] {
+                continue;
+            }
+            let h = b.new_block(BasicBlock::default())?;
+            for param in r.get_block(ind).get_operands().iter() {
+                vars[param.get_id()] = Some(b.push_arg_to(h)?);
+            }
+            *blk = Some(h);
+        }
+        let known = |v: Var, loc: &LocationInfo| match self.env.get(v.get_id()) {
+            Some(Some(l)) => materialize(l, loc.clone()),
+            _ => Ok(None),
+        };
+        let remap = |vars: &[Option<Var>], operands: Vec<Var>| {
+            operands
+                .into_iter()
+                .map(|v| match vars.get(v.get_id()).copied().flatten() {
+                    None => bail!(format!("No specialized value for {}.", v)),
+                    Some(v) => Ok(v),
+                })
+                .collect::<Result<Vec<_>, Report>>()
+        };
+        let target = |s: usize| match blocks[s] {
+            None => bail!(format!("Can't remove the branch to (dead) block {}.", s)),
+            Some(h) => Ok(h.get_block()),
+        };
+
+        // Definitions dominate their uses, so they are copied first in reverse postorder.
+        let dom = DominanceInfo::new(r);
+        for ind in dom.get_reverse_postorder().iter().copied() {
+            let h = match blocks[ind] {
+                None => continue,
+                Some(h) => h,
+            };
+            b.set_insertion_block(h)?;
+            for param in r.get_block(ind).get_operands().iter() {
+                if let Some(c) = known(*param, op.get_location())? {
+                    vars[param.get_id()] = Some(b.push(c)?);
+                }
+            }
+            let last = r.get_block_iter(ind).last().map(|(v, _)| v);
+            for (v, o) in r.get_block_iter(ind) {
+                if !o.get_regions().is_empty() {
+                    bail!(format!(
+                        "Partial evaluation of operations with regions ({}) is not supported.",
+                        o.get_intrinsic()
+                    ))
+                }
+                let succs = o.get_successors();
+                let feasible = match succs.is_empty() {
+                    true => Vec::new(),
+                    false => self.get_feasible_successors(o)?,
+                };
+                let taken = (0..succs.len())
+                    .filter(|s| feasible[*s])
+                    .collect::<Vec<_>>();
+                let fold = o.get_intrinsic().query_ref::<dyn FoldSuccessor>();
+                let mut new = match (&taken[..], fold) {
+                    ([s], Some(fold)) if succs.len() > 1 => {
+                        let mut new = fold.fold_successor(r, o, *s)?;
+                        new.set_successors(vec![target(succs[*s])?]);
+                        new
+                    }
+                    _ => {
+                        let mut new = OperationBuilder::default(
+                            o.get_intrinsic().clone(),
+                            o.get_location().clone(),
+                        );
+                        for (k, attr) in o.get_attributes().iter() {
+                            new.insert_attr(k, attr.clone());
+                        }
+                        new.set_operands(o.get_operands());
+                        new.set_successors(
+                            succs
+                                .iter()
+                                .map(|s| target(*s))
+                                .collect::<Result<Vec<_>, _>>()?,
+                        );
+                        new
+                    }
+                };
+                new.set_operands(remap(&vars, new.get_operands())?);
+                vars[v.get_id()] = Some(b.push(new)?);
+
+                // Operations without operands (e.g. constants) are already specialized.
+                if Some(v) != last && !o.get_operands().is_empty() {
+                    if let Some(c) = known(v, o.get_location())? {
+                        vars[v.get_id()] = Some(b.push(c)?);
+                    }
+                }
+            }
+        }
+        Ok(b)
+    }
+
+    /// Interpret until a fixpoint is reached, or until the
+    /// interpreter is waiting on a call.
+    pub fn run(&mut self, op: &Operation) -> Result<(), Report> {
+        while let InterpreterState::Active = self.state {
+            self.step(op)?;
         }
         Ok(())
     }
@@ -186,7 +818,7 @@ where
 
 impl<L> AnalysisKey for Signature<L>
 where
-    L: 'static + Clone + LatticeJoin + Display,
+    L: 'static + Clone + Eq + Hash + LatticeJoin + Display,
 {
     fn to_pass(&self, _op: &Operation) -> Box<dyn AnalysisPass> {
         let pass = LatticeInterpreterPass {
@@ -224,7 +856,9 @@ where
 interfaces! {
     <L: 'static + LatticeJoin + Display> Signature<L>: dyn ObjectClone,
     dyn Display,
-    dyn AnalysisKey where L: Clone
+    dyn ObjectEq,
+    dyn ObjectHash,
+    dyn AnalysisKey where L: Clone + Eq + Hash
 }
 
 #[derive(Debug)]
@@ -233,6 +867,12 @@ pub struct LatticeInterpreterPass<L> {
     result: Option<InterpreterFrame<L>>,
 }
 
+impl<L> LatticeInterpreterPass<L> {
+    pub fn get_result(&self) -> Option<&InterpreterFrame<L>> {
+        self.result.as_ref()
+    }
+}
+
 impl<L> Display for LatticeInterpreterPass<L>
 where
     L: Display,
@@ -248,11 +888,17 @@ where
 
 impl<L> AnalysisPass for LatticeInterpreterPass<L>
 where
-    L: 'static + LatticeJoin + Clone + Display,
+    L: 'static + LatticeJoin + Clone + PartialEq + Display,
 {
     fn apply(&mut self, op: &Operation) -> Result<(), Report> {
         let mut interp = Interpreter::new(op, self.key.env.to_vec());
-        interp.step(op)?;
+        interp.run(op)?;
+        if let Some(sig) = interp.get_waiting() {
+            bail!(format!(
+                "The call to {} in {} can't be resolved in isolation (see `interpret_with_calls`).",
+                sig, self.key
+            ))
+        }
         self.result = Some(interp.clone_frame().unwrap());
         Ok(())
     }
@@ -260,5 +906,249 @@ where
 
 interfaces! {
     <L: 'static + LatticeJoin + Display> LatticeInterpreterPass<L>: dyn Display,
-    dyn AnalysisPass where L: Clone
+    dyn AnalysisPass where L: Clone + PartialEq
+}
+
+/////
+///// Interprocedural interpretation.
+/////
+
+// A function which is being interpreted (on the call stack).
+#[derive(Debug)]
+struct CallFrame<L> {
+    key: Signature<L>,
+    // The lowest stack index of a recursive call made (transitively) from this frame.
+    low: usize,
+    // The return value assumed for recursive calls to this frame.
+    assumption: Option<L>,
+    recursive: bool,
+}
+
+/// Interprets functions where calls (see [`CallLike`]) are resolved in the style of
+/// Julia's type inference: `lookup` gets the operation which defines the callee, which is
+/// interpreted with the argument values at the call, and the return value of the callee
+/// becomes the value of the call.
+///
+/// The results for every function are cached in an [`AnalysisManager`] (as a
+/// [`LatticeInterpreterPass`] for each [`Signature`]), so a function is interpreted once
+/// per signature.
+///
+/// Recursive calls (to a signature which is already on the call stack) optimistically
+/// return the value assumed for the signature, starting from bottom (no value - the code
+/// after the call is unreachable). The function which heads the cycle is interpreted again
+/// until its return value agrees with the assumption, and results within the cycle are only
+/// cached once it does. When the arguments of recursive calls keep changing, the argument
+/// values of a call are widened (see [`LatticeWiden`]) with those of the innermost call to
+/// the same function on the stack once more than `depth_limit` calls to it are in flight,
+/// as are the values assumed for recursive calls. So the number of signatures interpreted
+/// is finite, even on lattices of infinite height (e.g. [`crate::core::Interval`]).
+#[derive(Debug)]
+pub struct InterproceduralInterpreter<'a, L> {
+    depth_limit: usize,
+    stack: Vec<CallFrame<L>>,
+    pending: Vec<(Signature<L>, &'a Operation, InterpreterFrame<L>, usize)>,
+}
+
+impl<'a, L> Default for InterproceduralInterpreter<'a, L> {
+    fn default() -> Self {
+        InterproceduralInterpreter {
+            depth_limit: 4,
+            stack: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<'a, L> InterproceduralInterpreter<'a, L>
+where
+    L: 'static + Clone + Eq + Hash + LatticeJoin + LatticeWiden + Display,
+{
+    pub fn new() -> InterproceduralInterpreter<'a, L> {
+        InterproceduralInterpreter::default()
+    }
+
+    /// Limit the number of in-flight calls to a function
+    /// with distinct signatures (by default, 4).
+    pub fn with_depth_limit(mut self, depth_limit: usize) -> InterproceduralInterpreter<'a, L> {
+        self.depth_limit = depth_limit.max(1);
+        self
+    }
+
+    fn merge(&self, old: &Option<L>, new: &Option<L>) -> Option<L> {
+        match (old, new) {
+            (None, v) | (v, None) => v.clone(),
+            (Some(old), Some(new)) => Some(old.widen(&old.join(new))),
+        }
+    }
+
+    /// Interpret `op` (a function) with the argument values of `key`,
+    /// and return its return value.
+    pub fn run<F>(
+        &mut self,
+        am: &mut AnalysisManager,
+        key: Signature<L>,
+        op: &'a Operation,
+        lookup: &F,
+    ) -> Result<Option<L>, Report>
+    where
+        F: Fn(&str) -> Option<&'a Operation>,
+    {
+        self.stack.clear();
+        self.pending.clear();
+        let (ret, _) = self.interpret(am, key, op, lookup)?;
+        Ok(ret)
+    }
+
+    // Get the result for `key` if it's cached, or assumed (for a recursive call),
+    // along with the lowest stack index it depends on.
+    fn get_known(
+        &mut self,
+        am: &mut AnalysisManager,
+        key: &Signature<L>,
+        op: &Operation,
+    ) -> Option<(Option<L>, usize)> {
+        if let Some(pass) = am.get_cached_analysis::<LatticeInterpreterPass<L>>(key, op) {
+            return Some((pass.get_result().and_then(|f| f.get_ret()), usize::MAX));
+        }
+        if let Some((_, _, frame, low)) = self.pending.iter().find(|(k, _, _, _)| k == key) {
+            return Some((frame.get_ret(), *low));
+        }
+        let ind = self.stack.iter().position(|f| f.key == *key)?;
+        self.stack[ind].recursive = true;
+        Some((self.stack[ind].assumption.clone(), ind))
+    }
+
+    fn interpret<F>(
+        &mut self,
+        am: &mut AnalysisManager,
+        key: Signature<L>,
+        op: &'a Operation,
+        lookup: &F,
+    ) -> Result<(Option<L>, usize), Report>
+    where
+        F: Fn(&str) -> Option<&'a Operation>,
+    {
+        if let Some(known) = self.get_known(am, &key, op) {
+            return Ok(known);
+        }
+        let in_flight = self.stack.iter().filter(|f| f.key.symbol == key.symbol);
+        let key = match (
+            in_flight.clone().count() >= self.depth_limit,
+            in_flight.last(),
+        ) {
+            (true, Some(prev)) if prev.key.env.len() == key.env.len() => {
+                let env = prev
+                    .key
+                    .env
+                    .iter()
+                    .zip(key.env.iter())
+                    .map(|(old, new)| self.merge(old, new))
+                    .collect();
+                let key = Signature::new(&key.symbol, env);
+                if let Some(known) = self.get_known(am, &key, op) {
+                    return Ok(known);
+                }
+                key
+            }
+            _ => key,
+        };
+        let nargs = op
+            .get_regions()
+            .first()
+            .and_then(|r| r.get_blocks().first())
+            .map_or(0, |b| b.get_operands().len());
+        if nargs != key.env.len() {
+            bail!(format!(
+                "{} is called with {} arguments, but {} has {}.",
+                key,
+                key.env.len(),
+                op.get_intrinsic(),
+                nargs
+            ))
+        }
+
+        let ind = self.stack.len();
+        self.stack.push(CallFrame {
+            key: key.clone(),
+            low: ind,
+            assumption: None,
+            recursive: false,
+        });
+        let mark = self.pending.len();
+        let frame = loop {
+            let mut interp = Interpreter::new(op, key.env.to_vec());
+            loop {
+                interp.run(op)?;
+                let sig = match interp.get_waiting() {
+                    None => break,
+                    Some(sig) => sig.clone(),
+                };
+                let callee = match lookup(&sig.symbol) {
+                    None => bail!(format!(
+                        "No function named {} is defined.",
+                        Paint::blue(&sig.symbol)
+                    )),
+                    Some(callee) => callee,
+                };
+                let (ret, low) = self.interpret(am, sig, callee, lookup)?;
+                let top = &mut self.stack[ind];
+                top.low = top.low.min(low);
+                interp.resolve(ret)?;
+            }
+            let frame = interp.clone_frame()?;
+
+            // The head of a cycle is interpreted again until the value
+            // assumed for recursive calls is consistent.
+            let top = &self.stack[ind];
+            if !top.recursive || top.low < ind {
+                break frame;
+            }
+            let assumption = self.merge(&top.assumption, &frame.get_ret());
+            if assumption == top.assumption {
+                break frame;
+            }
+            let top = &mut self.stack[ind];
+            top.assumption = assumption;
+            top.recursive = false;
+            self.pending.truncate(mark);
+        };
+        let top = self.stack.pop().unwrap();
+        let ret = frame.get_ret();
+
+        // Results which depend on an assumption further up the stack are cached
+        // once the cycle is resolved.
+        if top.low < ind {
+            self.pending.push((key, op, frame, top.low));
+            return Ok((ret, top.low));
+        }
+        for (key, op, frame, _) in self.pending.drain(mark..) {
+            let pass = LatticeInterpreterPass {
+                key: key.clone(),
+                result: Some(frame),
+            };
+            am.insert(key, op, Box::new(pass));
+        }
+        let pass = LatticeInterpreterPass {
+            key: key.clone(),
+            result: Some(frame),
+        };
+        am.insert(key, op, Box::new(pass));
+        Ok((ret, usize::MAX))
+    }
+}
+
+/// Interpret `op` (a function) with the argument values of `key`, resolving calls
+/// with the results cached in `am` (see [`InterproceduralInterpreter`]).
+/// Returns the return value of `op`.
+pub fn interpret_with_calls<'a, L, F>(
+    am: &mut AnalysisManager,
+    key: Signature<L>,
+    op: &'a Operation,
+    lookup: &F,
+) -> Result<Option<L>, Report>
+where
+    L: 'static + Clone + Eq + Hash + LatticeJoin + LatticeWiden + Display,
+    F: Fn(&str) -> Option<&'a Operation>,
+{
+    InterproceduralInterpreter::new().run(am, key, op, lookup)
 }
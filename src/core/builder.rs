@@ -1,9 +1,30 @@
 use crate::core::diagnostics::LocationInfo;
 use crate::core::ir::{Attribute, BasicBlock, Intrinsic, Operation, SupportsInterfaceTraits, Var};
 use crate::core::region::Region;
-use color_eyre::Report;
+use color_eyre::{eyre::bail, Report};
 use std::collections::HashMap;
 
+/// A handle to a block in an [`OperationBuilder`] under construction.
+///
+/// Handles are returned by the block construction helpers, and
+/// can be used to move the cursor back to a block, push arguments onto it,
+/// or target it as a branch successor (via [`BlockHandle::get_block`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockHandle {
+    region: usize,
+    block: usize,
+}
+
+impl BlockHandle {
+    pub fn get_region(&self) -> usize {
+        self.region
+    }
+
+    pub fn get_block(&self) -> usize {
+        self.block
+    }
+}
+
 #[derive(Debug)]
 pub struct OperationBuilder {
     latest: Vec<Var>,
@@ -56,6 +77,7 @@ impl OperationBuilder {
         self.latest.to_vec()
     }
 
+    #[allow(clippy::borrowed_box)]
     pub fn get_intrinsic(&self) -> &Box<dyn Intrinsic> {
         &self.intrinsic
     }
@@ -88,18 +110,60 @@ impl OperationBuilder {
         self.cursor
     }
 
-    pub fn push_arg(&mut self) -> Result<Var, Report> {
-        let blk = self.cursor.1 - 1;
-        let r = self.get_region_mut();
-        match r.push_arg(blk) {
-            Ok(v) => {
-                if blk == 0 {
-                    self.push_operand(v);
-                }
-                Ok(v)
+    /// Get a handle to the block at the cursor, if there is one.
+    pub fn get_current_block(&self) -> Option<BlockHandle> {
+        match self.cursor {
+            (0, _) | (_, 0) => None,
+            (reg, blk) => Some(BlockHandle {
+                region: reg - 1,
+                block: blk - 1,
+            }),
+        }
+    }
+
+    /// Move the cursor to the block referenced by `h`.
+    pub fn set_insertion_block(&mut self, h: BlockHandle) -> Result<(), Report> {
+        match self.regions.get(h.region) {
+            Some(r) if h.block < r.get_blocks().len() => {
+                self.cursor = (h.region + 1, h.block + 1);
+                Ok(())
             }
-            Err(e) => Err(e),
+            _ => bail!(format!(
+                "No block {} in region {} of {}.",
+                h.block, h.region, self.intrinsic
+            )),
+        }
+    }
+
+    fn current_block(&self) -> Result<BlockHandle, Report> {
+        match self.get_current_block() {
+            None => bail!(format!(
+                "{} builder cursor does not point to a block.",
+                self.intrinsic
+            )),
+            Some(h) => Ok(h),
+        }
+    }
+
+    /// Push an argument onto the block at the cursor.
+    /// Arguments of the entry block of the first region
+    /// are also operands of the operation.
+    pub fn push_arg(&mut self) -> Result<Var, Report> {
+        let h = self.current_block()?;
+        self.push_arg_to(h)
+    }
+
+    /// Push an argument onto the block referenced by `h`.
+    pub fn push_arg_to(&mut self, h: BlockHandle) -> Result<Var, Report> {
+        let r = match self.regions.get_mut(h.region) {
+            None => bail!(format!("No region {} in {}.", h.region, self.intrinsic)),
+            Some(r) => r,
+        };
+        let v = r.push_arg(h.block)?;
+        if h.region == 0 && h.block == 0 {
+            self.push_operand(v);
         }
+        Ok(v)
     }
 
     pub fn insert_attr(&mut self, k: &str, attr: Box<dyn Attribute>) {
@@ -114,9 +178,11 @@ impl OperationBuilder {
         &mut self.attributes
     }
 
+    /// Push a region onto the operation, and move the cursor to it.
+    /// The cursor does not point to a block until one is pushed.
     pub fn push_region(&mut self, r: Region) {
         self.regions.push(r);
-        self.cursor = (self.cursor.0 + 1, self.cursor.1)
+        self.cursor = (self.regions.len(), 0)
     }
 
     pub fn get_region(&self) -> &Region {
@@ -133,11 +199,69 @@ impl OperationBuilder {
         &self.regions
     }
 
+    /// Push a block onto the region at the cursor, and move the cursor to it.
     pub fn push_block(&mut self, b: BasicBlock) -> Result<(), Report> {
+        let h = self.new_block(b)?;
+        self.cursor = (h.region + 1, h.block + 1);
+        Ok(())
+    }
+
+    /// Push a block onto the region at the cursor, without moving the cursor.
+    ///
+    /// This supports forward references to blocks (e.g. as branch targets)
+    /// which are filled in later.
+    pub fn new_block(&mut self, b: BasicBlock) -> Result<BlockHandle, Report> {
+        if self.cursor.0 == 0 {
+            bail!(format!(
+                "{} builder has no region to push a block onto.",
+                self.intrinsic
+            ))
+        }
+        let region = self.cursor.0 - 1;
         let r = self.get_region_mut();
         r.push_block(b)?;
-        self.cursor = (self.cursor.0, self.cursor.1 + 1);
-        Ok(())
+        Ok(BlockHandle {
+            region,
+            block: r.get_blocks().len() - 1,
+        })
+    }
+
+    /// Push a region, and build its contents with `f`.
+    /// The cursor is restored afterwards. Returns the index of the new region.
+    pub fn build_region<F>(&mut self, r: Region, f: F) -> Result<usize, Report>
+    where
+        F: FnOnce(&mut OperationBuilder) -> Result<(), Report>,
+    {
+        let cursor = self.cursor;
+        self.push_region(r);
+        let ind = self.regions.len() - 1;
+        let ret = f(self);
+        self.cursor = cursor;
+        ret.map(|_| ind)
+    }
+
+    /// Push a block onto the region at the cursor, and build its contents with `f`.
+    /// The cursor is restored afterwards. Returns a handle to the new block.
+    pub fn build_block<F>(&mut self, f: F) -> Result<BlockHandle, Report>
+    where
+        F: FnOnce(&mut OperationBuilder) -> Result<(), Report>,
+    {
+        let h = self.new_block(BasicBlock::default())?;
+        self.with_block(h, f)?;
+        Ok(h)
+    }
+
+    /// Build into an existing block referenced by `h` with `f`.
+    /// The cursor is restored afterwards.
+    pub fn with_block<F>(&mut self, h: BlockHandle, f: F) -> Result<(), Report>
+    where
+        F: FnOnce(&mut OperationBuilder) -> Result<(), Report>,
+    {
+        let cursor = self.cursor;
+        self.set_insertion_block(h)?;
+        let ret = f(self);
+        self.cursor = cursor;
+        ret
     }
 
     pub fn get_block(&self) -> &BasicBlock {
@@ -155,6 +279,7 @@ impl OperationBuilder {
     }
 
     pub fn push(&mut self, v: OperationBuilder) -> Result<Var, Report> {
+        self.current_block()?;
         let op = v.finish()?;
         Ok(self.push_op(op))
     }
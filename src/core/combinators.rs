@@ -0,0 +1,572 @@
+//! Lattice combinators, which build lattices from other lattices.
+//!
+//! The propagation rules of the combined lattices are the rules of their
+//! components (which are queried as dynamic interfaces of each intrinsic), so a combined
+//! lattice only requires that the rules of each component are registered. The rules of
+//! the combined lattice itself must be registered too - the standard dialects provide a
+//! generic function for this (e.g. `register_lattice_semantics` in [`crate::dialects::arith`]).
+
+use crate::core::absint::{
+    LatticeBranchSemantics, LatticeConvert, LatticeJoin, LatticeMeet, LatticeNarrow,
+    LatticeSemantics, LatticeWiden,
+};
+use crate::core::ir::{Intrinsic, Operation, SupportsInterfaceTraits};
+use color_eyre::{eyre::bail, Report};
+use std::collections::BTreeMap;
+use std::fmt;
+use yansi::Paint;
+
+// Propagate the values of the operands of `op` with the registered rules of `L`.
+fn propagate<L: 'static>(op: &Operation, vtypes: Vec<&L>) -> Result<L, Report> {
+    match op.get_intrinsic().query_ref::<dyn LatticeSemantics<L>>() {
+        None => bail!(format!(
+            "{} fails to support lattice semantics for a component of a lattice combinator.",
+            op.get_intrinsic()
+        )),
+        Some(lintr) => lintr.propagate(op, vtypes),
+    }
+}
+
+// Get the feasible successors of `op` with the registered rules of `L`
+// (if `L` has no rules, every successor is feasible).
+fn get_feasible_successors<L: 'static>(
+    op: &Operation,
+    vtypes: Vec<&L>,
+) -> Result<Vec<usize>, Report> {
+    match op
+        .get_intrinsic()
+        .query_ref::<dyn LatticeBranchSemantics<L>>()
+    {
+        None => Ok((0..op.get_successors().len()).collect()),
+        Some(lintr) => lintr.get_feasible_successors(op, vtypes),
+    }
+}
+
+/// The product of two lattices, ordered componentwise.
+///
+/// Products of more than two lattices can be built by nesting
+/// (e.g. `Product<A, Product<B, C>>`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Product<A, B>(pub A, pub B);
+
+impl<A, B> LatticeJoin for Product<A, B>
+where
+    A: LatticeJoin,
+    B: LatticeJoin,
+{
+    fn join(&self, other: &Product<A, B>) -> Product<A, B> {
+        Product(self.0.join(&other.0), self.1.join(&other.1))
+    }
+}
+
+impl<A, B> LatticeMeet for Product<A, B>
+where
+    A: LatticeMeet,
+    B: LatticeMeet,
+{
+    fn meet(&self, other: &Product<A, B>) -> Product<A, B> {
+        Product(self.0.meet(&other.0), self.1.meet(&other.1))
+    }
+}
+
+impl<A, B> LatticeWiden for Product<A, B>
+where
+    A: LatticeWiden,
+    B: LatticeWiden,
+{
+    fn widen(&self, other: &Product<A, B>) -> Product<A, B> {
+        Product(self.0.widen(&other.0), self.1.widen(&other.1))
+    }
+}
+
+impl<A, B> LatticeNarrow for Product<A, B>
+where
+    A: LatticeNarrow,
+    B: LatticeNarrow,
+{
+    fn narrow(&self, other: &Product<A, B>) -> Product<A, B> {
+        Product(self.0.narrow(&other.0), self.1.narrow(&other.1))
+    }
+}
+
+impl<I, A, B> LatticeSemantics<Product<A, B>> for I
+where
+    I: Intrinsic,
+    A: 'static,
+    B: 'static,
+{
+    fn propagate(
+        &self,
+        op: &Operation,
+        vtypes: Vec<&Product<A, B>>,
+    ) -> Result<Product<A, B>, Report> {
+        Ok(Product(
+            propagate(op, vtypes.iter().map(|v| &v.0).collect())?,
+            propagate(op, vtypes.iter().map(|v| &v.1).collect())?,
+        ))
+    }
+}
+
+// A successor is feasible if it is feasible for both components.
+impl<I, A, B> LatticeBranchSemantics<Product<A, B>> for I
+where
+    I: Intrinsic,
+    A: 'static,
+    B: 'static,
+{
+    fn get_feasible_successors(
+        &self,
+        op: &Operation,
+        vtypes: Vec<&Product<A, B>>,
+    ) -> Result<Vec<usize>, Report> {
+        let first = get_feasible_successors(op, vtypes.iter().map(|v| &v.0).collect())?;
+        let second = get_feasible_successors(op, vtypes.iter().map(|v| &v.1).collect())?;
+        Ok(first.into_iter().filter(|s| second.contains(s)).collect())
+    }
+}
+
+impl<A, B> fmt::Display for Product<A, B>
+where
+    A: fmt::Display,
+    B: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.0, self.1)
+    }
+}
+
+/// The reduced product of two lattices, where each component
+/// is refined with the other (with [`LatticeConvert`] and [`LatticeMeet`]).
+///
+/// Values are reduced when they are created (and after joins and propagation):
+/// the first component is met with the conversion of the second,
+/// and then the second with the conversion of the (refined) first.
+/// Widening and narrowing don't reduce values (which could prevent termination).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReducedProduct<A, B>(A, B);
+
+impl<A, B> ReducedProduct<A, B>
+where
+    A: LatticeMeet + LatticeConvert<B>,
+    B: LatticeMeet + LatticeConvert<A>,
+{
+    pub fn new(a: A, b: B) -> ReducedProduct<A, B> {
+        let a = a.meet(&b.convert());
+        let b = b.meet(&a.convert());
+        ReducedProduct(a, b)
+    }
+}
+
+impl<A, B> ReducedProduct<A, B> {
+    pub fn get_first(&self) -> &A {
+        &self.0
+    }
+
+    pub fn get_second(&self) -> &B {
+        &self.1
+    }
+}
+
+impl<A, B> LatticeJoin for ReducedProduct<A, B>
+where
+    A: LatticeJoin + LatticeMeet + LatticeConvert<B>,
+    B: LatticeJoin + LatticeMeet + LatticeConvert<A>,
+{
+    fn join(&self, other: &ReducedProduct<A, B>) -> ReducedProduct<A, B> {
+        ReducedProduct::new(self.0.join(&other.0), self.1.join(&other.1))
+    }
+}
+
+impl<A, B> LatticeMeet for ReducedProduct<A, B>
+where
+    A: LatticeMeet + LatticeConvert<B>,
+    B: LatticeMeet + LatticeConvert<A>,
+{
+    fn meet(&self, other: &ReducedProduct<A, B>) -> ReducedProduct<A, B> {
+        ReducedProduct::new(self.0.meet(&other.0), self.1.meet(&other.1))
+    }
+}
+
+impl<A, B> LatticeWiden for ReducedProduct<A, B>
+where
+    A: LatticeWiden,
+    B: LatticeWiden,
+{
+    fn widen(&self, other: &ReducedProduct<A, B>) -> ReducedProduct<A, B> {
+        ReducedProduct(self.0.widen(&other.0), self.1.widen(&other.1))
+    }
+}
+
+impl<A, B> LatticeNarrow for ReducedProduct<A, B>
+where
+    A: LatticeNarrow,
+    B: LatticeNarrow,
+{
+    fn narrow(&self, other: &ReducedProduct<A, B>) -> ReducedProduct<A, B> {
+        ReducedProduct(self.0.narrow(&other.0), self.1.narrow(&other.1))
+    }
+}
+
+impl<I, A, B> LatticeSemantics<ReducedProduct<A, B>> for I
+where
+    I: Intrinsic,
+    A: LatticeMeet + LatticeConvert<B> + 'static,
+    B: LatticeMeet + LatticeConvert<A> + 'static,
+{
+    fn propagate(
+        &self,
+        op: &Operation,
+        vtypes: Vec<&ReducedProduct<A, B>>,
+    ) -> Result<ReducedProduct<A, B>, Report> {
+        Ok(ReducedProduct::new(
+            propagate(op, vtypes.iter().map(|v| &v.0).collect())?,
+            propagate(op, vtypes.iter().map(|v| &v.1).collect())?,
+        ))
+    }
+}
+
+impl<I, A, B> LatticeBranchSemantics<ReducedProduct<A, B>> for I
+where
+    I: Intrinsic,
+    A: 'static,
+    B: 'static,
+{
+    fn get_feasible_successors(
+        &self,
+        op: &Operation,
+        vtypes: Vec<&ReducedProduct<A, B>>,
+    ) -> Result<Vec<usize>, Report> {
+        let first = get_feasible_successors(op, vtypes.iter().map(|v| &v.0).collect())?;
+        let second = get_feasible_successors(op, vtypes.iter().map(|v| &v.1).collect())?;
+        Ok(first.into_iter().filter(|s| second.contains(s)).collect())
+    }
+}
+
+impl<A, B> fmt::Display for ReducedProduct<A, B>
+where
+    A: fmt::Display,
+    B: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.0, self.1)
+    }
+}
+
+/// The lattice of finite sets of values (ordered by inclusion),
+/// where sets with more than `N` values are the top element.
+///
+/// Each value is propagated separately - the rules of `T` are applied
+/// to every combination of the values of the operands.
+#[derive(Debug, Clone)]
+pub enum Powerset<T, const N: usize> {
+    Set(Vec<T>),
+    Top,
+}
+
+// Sets are equal regardless of the order of their values.
+impl<T, const N: usize> PartialEq for Powerset<T, N>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Powerset<T, N>) -> bool {
+        match (self, other) {
+            (Powerset::Top, Powerset::Top) => true,
+            (Powerset::Set(a), Powerset::Set(b)) => {
+                a.len() == b.len() && a.iter().all(|v| b.contains(v))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<T, const N: usize> Powerset<T, N>
+where
+    T: PartialEq,
+{
+    /// Create a set from `values` (without duplicates), or
+    /// the top element if there are more than `N` values.
+    pub fn new(values: Vec<T>) -> Powerset<T, N> {
+        let mut set = Vec::new();
+        for v in values {
+            if !set.contains(&v) {
+                set.push(v);
+            }
+        }
+        match set.len() > N {
+            true => Powerset::Top,
+            false => Powerset::Set(set),
+        }
+    }
+
+    pub fn singleton(v: T) -> Powerset<T, N> {
+        Powerset::new(vec![v])
+    }
+
+    pub fn get_values(&self) -> Option<&[T]> {
+        match self {
+            Powerset::Set(v) => Some(v),
+            Powerset::Top => None,
+        }
+    }
+
+    // Apply `f` to every combination of the values of `sets` (which are not `Top`).
+    fn for_each_combination<F>(sets: &[&Powerset<T, N>], mut f: F) -> Result<(), Report>
+    where
+        F: FnMut(Vec<&T>) -> Result<(), Report>,
+    {
+        let sets = sets
+            .iter()
+            .filter_map(|s| s.get_values())
+            .collect::<Vec<_>>();
+        if sets.iter().any(|s| s.is_empty()) {
+            return Ok(());
+        }
+        let mut inds = vec![0; sets.len()];
+        loop {
+            f(inds.iter().zip(sets.iter()).map(|(i, s)| &s[*i]).collect())?;
+            match (0..inds.len())
+                .rev()
+                .find(|i| inds[*i] + 1 < sets[*i].len())
+            {
+                None => return Ok(()),
+                Some(i) => {
+                    inds[i] += 1;
+                    inds[i + 1..].iter_mut().for_each(|i| *i = 0);
+                }
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> LatticeJoin for Powerset<T, N>
+where
+    T: Clone + PartialEq,
+{
+    fn join(&self, other: &Powerset<T, N>) -> Powerset<T, N> {
+        match (self, other) {
+            (Powerset::Set(a), Powerset::Set(b)) => {
+                Powerset::new(a.iter().chain(b.iter()).cloned().collect())
+            }
+            _ => Powerset::Top,
+        }
+    }
+}
+
+impl<T, const N: usize> LatticeMeet for Powerset<T, N>
+where
+    T: Clone + PartialEq,
+{
+    fn meet(&self, other: &Powerset<T, N>) -> Powerset<T, N> {
+        match (self, other) {
+            (Powerset::Top, v) | (v, Powerset::Top) => v.clone(),
+            (Powerset::Set(a), Powerset::Set(b)) => {
+                Powerset::Set(a.iter().filter(|v| b.contains(v)).cloned().collect())
+            }
+        }
+    }
+}
+
+impl<I, T, const N: usize> LatticeSemantics<Powerset<T, N>> for I
+where
+    I: Intrinsic,
+    T: PartialEq + 'static,
+{
+    fn propagate(
+        &self,
+        op: &Operation,
+        vtypes: Vec<&Powerset<T, N>>,
+    ) -> Result<Powerset<T, N>, Report> {
+        if vtypes.iter().any(|v| matches!(v, Powerset::Top)) {
+            return Ok(Powerset::Top);
+        }
+        let mut values = Vec::new();
+        Powerset::for_each_combination(&vtypes, |combination| {
+            let v = propagate(op, combination)?;
+            if !values.contains(&v) {
+                values.push(v);
+            }
+            Ok(())
+        })?;
+        Ok(Powerset::new(values))
+    }
+}
+
+// A successor is feasible if it is feasible for some combination of the values.
+impl<I, T, const N: usize> LatticeBranchSemantics<Powerset<T, N>> for I
+where
+    I: Intrinsic,
+    T: PartialEq + 'static,
+{
+    fn get_feasible_successors(
+        &self,
+        op: &Operation,
+        vtypes: Vec<&Powerset<T, N>>,
+    ) -> Result<Vec<usize>, Report> {
+        if vtypes.iter().any(|v| matches!(v, Powerset::Top)) {
+            return Ok((0..op.get_successors().len()).collect());
+        }
+        let mut feasible = Vec::new();
+        Powerset::for_each_combination(&vtypes, |combination| {
+            for s in get_feasible_successors(op, combination)? {
+                if !feasible.contains(&s) {
+                    feasible.push(s);
+                }
+            }
+            Ok(())
+        })?;
+        feasible.sort_unstable();
+        Ok(feasible)
+    }
+}
+
+impl<T, const N: usize> fmt::Display for Powerset<T, N>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Powerset::Top => write!(f, "{}", Paint::magenta("top").bold()),
+            Powerset::Set(v) => {
+                let values = v.iter().map(|v| format!("{}", v)).collect::<Vec<_>>();
+                write!(f, "{{{}}}", values.join(", "))
+            }
+        }
+    }
+}
+
+/// The lattice of maps from keys to lattice values, ordered pointwise
+/// (a key which is not in the map is the bottom element of `V`).
+///
+/// Maps are useful for values which are not held by SSA values
+/// (e.g. an abstract store, from locations to values).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapLattice<K, V>(BTreeMap<K, V>);
+
+impl<K, V> Default for MapLattice<K, V> {
+    fn default() -> MapLattice<K, V> {
+        MapLattice(BTreeMap::new())
+    }
+}
+
+impl<K, V> MapLattice<K, V>
+where
+    K: Ord,
+{
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.0.get(k)
+    }
+
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        self.0.insert(k, v)
+    }
+
+    pub fn remove(&mut self, k: &K) -> Option<V> {
+        self.0.remove(k)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<K, V> MapLattice<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    // Combine the values of keys in both maps with `f`,
+    // and keep the values of keys in either map if `union`.
+    fn pointwise(
+        &self,
+        other: &MapLattice<K, V>,
+        union: bool,
+        f: fn(&V, &V) -> V,
+    ) -> MapLattice<K, V> {
+        let mut m = BTreeMap::new();
+        for (k, v) in self.0.iter() {
+            match other.0.get(k) {
+                Some(w) => m.insert(k.clone(), f(v, w)),
+                None if union => m.insert(k.clone(), v.clone()),
+                None => None,
+            };
+        }
+        if union {
+            for (k, w) in other.0.iter() {
+                if !self.0.contains_key(k) {
+                    m.insert(k.clone(), w.clone());
+                }
+            }
+        }
+        MapLattice(m)
+    }
+}
+
+impl<K, V> LatticeJoin for MapLattice<K, V>
+where
+    K: Ord + Clone,
+    V: Clone + LatticeJoin,
+{
+    fn join(&self, other: &MapLattice<K, V>) -> MapLattice<K, V> {
+        self.pointwise(other, true, V::join)
+    }
+}
+
+impl<K, V> LatticeMeet for MapLattice<K, V>
+where
+    K: Ord + Clone,
+    V: Clone + LatticeMeet,
+{
+    fn meet(&self, other: &MapLattice<K, V>) -> MapLattice<K, V> {
+        self.pointwise(other, false, V::meet)
+    }
+}
+
+impl<K, V> LatticeWiden for MapLattice<K, V>
+where
+    K: Ord + Clone,
+    V: Clone + LatticeWiden,
+{
+    fn widen(&self, other: &MapLattice<K, V>) -> MapLattice<K, V> {
+        self.pointwise(other, true, V::widen)
+    }
+}
+
+// Keys which are not in `other` are (below `self`, so) narrowed to the bottom element.
+impl<K, V> LatticeNarrow for MapLattice<K, V>
+where
+    K: Ord + Clone,
+    V: Clone + LatticeNarrow,
+{
+    fn narrow(&self, other: &MapLattice<K, V>) -> MapLattice<K, V> {
+        let mut m = other.pointwise(self, false, |w, v| v.narrow(w));
+        for (k, w) in other.0.iter() {
+            if !m.0.contains_key(k) {
+                m.0.insert(k.clone(), w.clone());
+            }
+        }
+        m
+    }
+}
+
+impl<K, V> fmt::Display for MapLattice<K, V>
+where
+    K: fmt::Display,
+    V: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let entries = self
+            .0
+            .iter()
+            .map(|(k, v)| format!("{}: {}", k, v))
+            .collect::<Vec<_>>();
+        write!(f, "{{{}}}", entries.join(", "))
+    }
+}
@@ -0,0 +1,404 @@
+use crate::core::ir::{CallLike, Operation, SuccessorOperands, SupportsInterfaceTraits, Var};
+use color_eyre::{eyre::bail, Report};
+use std::fmt;
+use yansi::Paint;
+
+/// A value computed by the [`ConcreteInterpreter`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeValue {
+    /// An integer (sign extended from its width).
+    Integer(i64, usize),
+    Float(f64, usize),
+    Bool(bool),
+    /// A reference to a [`Buffer`] in the [`Heap`].
+    MemRef(usize),
+}
+
+impl RuntimeValue {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            RuntimeValue::Bool(b) => Some(*b),
+            RuntimeValue::Integer(v, _) => Some(*v != 0),
+            _ => None,
+        }
+    }
+
+    /// Get the value as an index (or size), if it's a non-negative integer.
+    pub fn as_index(&self) -> Option<usize> {
+        match self {
+            RuntimeValue::Integer(v, _) if *v >= 0 => Some(*v as usize),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RuntimeValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuntimeValue::Integer(v, w) => {
+                write!(f, "{} : {}", v, Paint::magenta(format!("i{}", w)))
+            }
+            RuntimeValue::Float(v, w) => write!(f, "{} : {}", v, Paint::magenta(format!("f{}", w))),
+            RuntimeValue::Bool(b) => write!(f, "{}", b),
+            RuntimeValue::MemRef(id) => write!(f, "{}#{}", Paint::magenta("memref"), id),
+        }
+    }
+}
+
+/// A row-major buffer of (possibly uninitialized) values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Buffer {
+    shape: Vec<usize>,
+    data: Vec<Option<RuntimeValue>>,
+}
+
+impl Buffer {
+    pub fn new(shape: Vec<usize>) -> Buffer {
+        let len = shape.iter().product();
+        Buffer {
+            shape,
+            data: vec![None; len],
+        }
+    }
+
+    pub fn get_shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn get_data(&self) -> &[Option<RuntimeValue>] {
+        &self.data
+    }
+
+    fn offset(&self, indices: &[usize]) -> Result<usize, Report> {
+        if indices.len() != self.shape.len()
+            || indices.iter().zip(self.shape.iter()).any(|(i, d)| i >= d)
+        {
+            bail!(format!(
+                "Index {:?} is out of bounds for a buffer of shape {:?}.",
+                indices, self.shape
+            ))
+        }
+        Ok(indices
+            .iter()
+            .zip(self.shape.iter())
+            .fold(0, |acc, (i, d)| acc * d + i))
+    }
+
+    pub fn load(&self, indices: &[usize]) -> Result<RuntimeValue, Report> {
+        match &self.data[self.offset(indices)?] {
+            None => bail!(format!("Load of uninitialized element {:?}.", indices)),
+            Some(v) => Ok(v.clone()),
+        }
+    }
+
+    pub fn store(&mut self, indices: &[usize], v: RuntimeValue) -> Result<(), Report> {
+        let offset = self.offset(indices)?;
+        self.data[offset] = Some(v);
+        Ok(())
+    }
+
+    /// Copy the contents of `other` (which must have the same shape).
+    pub fn copy_from(&mut self, other: &Buffer) -> Result<(), Report> {
+        if self.shape != other.shape {
+            bail!(format!(
+                "Can't copy a buffer of shape {:?} into a buffer of shape {:?}.",
+                other.shape, self.shape
+            ))
+        }
+        self.data = other.data.to_vec();
+        Ok(())
+    }
+}
+
+/// The memory of the [`ConcreteInterpreter`].
+///
+/// Buffers are referenced by [`RuntimeValue::MemRef`]. Buffers which are
+/// allocated in a scope (e.g. by `memref.alloca`) are freed when the scope
+/// (the call of the function which allocated them) is exited.
+#[derive(Debug, Default)]
+pub struct Heap {
+    buffers: Vec<Option<Buffer>>,
+    scopes: Vec<Vec<usize>>,
+}
+
+impl Heap {
+    pub fn alloc(&mut self, shape: Vec<usize>) -> RuntimeValue {
+        self.buffers.push(Some(Buffer::new(shape)));
+        RuntimeValue::MemRef(self.buffers.len() - 1)
+    }
+
+    /// Allocate a buffer which is freed when the current scope is exited.
+    pub fn alloc_scoped(&mut self, shape: Vec<usize>) -> RuntimeValue {
+        let v = self.alloc(shape);
+        if let (Some(scope), RuntimeValue::MemRef(id)) = (self.scopes.last_mut(), &v) {
+            scope.push(*id);
+        }
+        v
+    }
+
+    pub fn free(&mut self, v: &RuntimeValue) -> Result<(), Report> {
+        let id = self.get_id(v)?;
+        self.buffers[id] = None;
+        Ok(())
+    }
+
+    fn get_id(&self, v: &RuntimeValue) -> Result<usize, Report> {
+        match v {
+            RuntimeValue::MemRef(id) if self.buffers.get(*id).is_some_and(|b| b.is_some()) => {
+                Ok(*id)
+            }
+            RuntimeValue::MemRef(id) => bail!(format!(
+                "Use of {} after it was freed.",
+                RuntimeValue::MemRef(*id)
+            )),
+            _ => bail!(format!("{} is not a memref.", v)),
+        }
+    }
+
+    pub fn get(&self, v: &RuntimeValue) -> Result<&Buffer, Report> {
+        let id = self.get_id(v)?;
+        Ok(self.buffers[id].as_ref().unwrap())
+    }
+
+    pub fn get_mut(&mut self, v: &RuntimeValue) -> Result<&mut Buffer, Report> {
+        let id = self.get_id(v)?;
+        Ok(self.buffers[id].as_mut().unwrap())
+    }
+
+    /// The number of buffers which haven't been freed.
+    pub fn live(&self) -> usize {
+        self.buffers.iter().filter(|b| b.is_some()).count()
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn pop_scope(&mut self) {
+        for id in self.scopes.pop().unwrap_or_default() {
+            self.buffers[id] = None;
+        }
+    }
+}
+
+/// Evaluation rules of intrinsics for the [`ConcreteInterpreter`]
+/// (registered as dynamic interfaces, like [`crate::core::LatticeSemantics`]).
+pub trait ConcreteSemantics {
+    /// Evaluate `op` with the values of its operands,
+    /// returns `None` for operations which don't produce a value.
+    fn evaluate(
+        &self,
+        op: &Operation,
+        args: Vec<&RuntimeValue>,
+        heap: &mut Heap,
+    ) -> Result<Option<RuntimeValue>, Report>;
+}
+
+/// Evaluation rules for terminators with more than one successor,
+/// which select the successor which is taken.
+pub trait ConcreteBranchSemantics {
+    /// Get the position (in [`Operation::get_successors`]) of the successor which is taken.
+    fn get_successor(&self, op: &Operation, args: Vec<&RuntimeValue>) -> Result<usize, Report>;
+}
+
+type SymbolLookup<'a> = Box<dyn Fn(&str) -> Option<&'a Operation> + 'a>;
+
+/// A reference interpreter, which executes the first region of
+/// functions (e.g. `builtin.func`) with runtime values.
+///
+/// Operations are evaluated with their [`ConcreteSemantics`], and control flow
+/// follows the successor of each terminator (see [`ConcreteBranchSemantics`]), forwarding
+/// operands to the arguments of the successor (see [`SuccessorOperands`]).
+/// Terminators without successors return their operands. Calls (see [`CallLike`])
+/// are executed with the function which `lookup` returns for the callee.
+pub struct ConcreteInterpreter<'a> {
+    heap: Heap,
+    lookup: SymbolLookup<'a>,
+    depth: usize,
+    max_depth: usize,
+    fuel: Option<usize>,
+}
+
+impl<'a> ConcreteInterpreter<'a> {
+    pub fn new<F>(lookup: F) -> ConcreteInterpreter<'a>
+    where
+        F: Fn(&str) -> Option<&'a Operation> + 'a,
+    {
+        ConcreteInterpreter {
+            heap: Heap::default(),
+            lookup: Box::new(lookup),
+            depth: 0,
+            max_depth: 256,
+            fuel: None,
+        }
+    }
+
+    /// Limit the depth of nested calls (by default, 256).
+    pub fn with_max_depth(mut self, max_depth: usize) -> ConcreteInterpreter<'a> {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Limit the number of blocks which are executed.
+    pub fn with_fuel(mut self, fuel: usize) -> ConcreteInterpreter<'a> {
+        self.fuel = Some(fuel);
+        self
+    }
+
+    pub fn get_heap(&self) -> &Heap {
+        &self.heap
+    }
+
+    pub fn get_heap_mut(&mut self) -> &mut Heap {
+        &mut self.heap
+    }
+
+    /// Call the function named `name` (see `lookup`).
+    pub fn call_symbol(
+        &mut self,
+        name: &str,
+        args: Vec<RuntimeValue>,
+    ) -> Result<Vec<RuntimeValue>, Report> {
+        match (self.lookup)(name) {
+            None => bail!(format!(
+                "No function named {} is defined.",
+                Paint::blue(name)
+            )),
+            Some(op) => self.call(op, args),
+        }
+    }
+
+    /// Call the function `op` with `args` (the values of
+    /// the arguments of its entry block), and return its results.
+    pub fn call(
+        &mut self,
+        op: &'a Operation,
+        args: Vec<RuntimeValue>,
+    ) -> Result<Vec<RuntimeValue>, Report> {
+        let r = match op.get_regions().first() {
+            Some(r) if !r.get_blocks().is_empty() => r,
+            _ => bail!(format!("{} has no body to execute.", op.get_intrinsic())),
+        };
+        let params = r.get_block(0).get_operands();
+        if params.len() != args.len() {
+            bail!(format!(
+                "{} is called with {} arguments, but has {}.",
+                op.get_intrinsic(),
+                args.len(),
+                params.len()
+            ))
+        }
+        if self.depth >= self.max_depth {
+            bail!(format!(
+                "Call depth exceeds the limit ({}).",
+                self.max_depth
+            ))
+        }
+        let mut env = vec![None; r.len()];
+        for (p, a) in params.iter().zip(args) {
+            env[p.get_id()] = Some(a);
+        }
+        self.depth += 1;
+        self.heap.push_scope();
+        let ret = self.execute(op, env);
+        self.heap.pop_scope();
+        self.depth -= 1;
+        ret
+    }
+
+    fn execute(
+        &mut self,
+        op: &'a Operation,
+        mut env: Vec<Option<RuntimeValue>>,
+    ) -> Result<Vec<RuntimeValue>, Report> {
+        let r = &op.get_regions()[0];
+        let mut blk = 0;
+        'blocks: loop {
+            if let Some(fuel) = self.fuel.as_mut() {
+                if *fuel == 0 {
+                    bail!("The interpreter ran out of fuel.")
+                }
+                *fuel -= 1;
+            }
+            let last = r.get_block_iter(blk).last().map(|(v, _)| v);
+            for (v, o) in r.get_block_iter(blk) {
+                let args = get_args(&env, o)?;
+                let succs = o.get_successors();
+                if !succs.is_empty() {
+                    let ind = match o.get_intrinsic().query_ref::<dyn ConcreteBranchSemantics>() {
+                        Some(sema) => sema.get_successor(o, args)?,
+                        None if succs.len() == 1 => 0,
+                        None => bail!(format!(
+                            "{} has {} successors, but no concrete branch semantics.",
+                            o.get_intrinsic(),
+                            succs.len()
+                        )),
+                    };
+                    let s = match succs.get(ind) {
+                        None => bail!(format!("{} has no successor {}.", o.get_intrinsic(), ind)),
+                        Some(s) => *s,
+                    };
+                    let forwarded = match o.get_intrinsic().query_ref::<dyn SuccessorOperands>() {
+                        Some(trt) => trt.get_successor_operands(r, o).swap_remove(ind),
+                        None => Vec::new(),
+                    };
+                    let values = forwarded
+                        .iter()
+                        .map(|a| get_value(&env, *a).cloned())
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let params = r.get_block(s).get_operands();
+                    if params.len() != values.len() {
+                        bail!(format!(
+                            "{} forwards {} operands to block {}, which has {} arguments.",
+                            o.get_intrinsic(),
+                            values.len(),
+                            s,
+                            params.len()
+                        ))
+                    }
+                    for (p, val) in params.iter().zip(values) {
+                        env[p.get_id()] = Some(val);
+                    }
+                    blk = s;
+                    continue 'blocks;
+                }
+                if Some(v) == last {
+                    return Ok(args.into_iter().cloned().collect());
+                }
+                let intr = o.get_intrinsic();
+                env[v.get_id()] = match intr.query_ref::<dyn CallLike>() {
+                    Some(call) => {
+                        let callee = match call.get_callee(o) {
+                            None => bail!(format!("{} has no callee.", intr)),
+                            Some(callee) => callee,
+                        };
+                        let args = args.into_iter().cloned().collect();
+                        self.call_symbol(&callee, args)?.into_iter().next()
+                    }
+                    None => match intr.query_ref::<dyn ConcreteSemantics>() {
+                        None => bail!(format!("{} has no concrete semantics.", intr)),
+                        Some(sema) => sema.evaluate(o, args, &mut self.heap)?,
+                    },
+                };
+            }
+            bail!(format!("Block {} has no terminator.", blk))
+        }
+    }
+}
+
+fn get_value(env: &[Option<RuntimeValue>], v: Var) -> Result<&RuntimeValue, Report> {
+    match env.get(v.get_id()) {
+        Some(Some(val)) => Ok(val),
+        _ => bail!(format!("No value for SSA variable {}.", v)),
+    }
+}
+
+fn get_args<'b>(
+    env: &'b [Option<RuntimeValue>],
+    op: &Operation,
+) -> Result<Vec<&'b RuntimeValue>, Report> {
+    op.get_operands()
+        .into_iter()
+        .map(|v| get_value(env, v))
+        .collect()
+}
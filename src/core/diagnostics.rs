@@ -24,7 +24,7 @@ pub fn diagnostics_paint_disable() {
 ///// Locations
 /////
 
-#[derive(Debug, Hash)]
+#[derive(Debug, Clone, Hash)]
 pub enum LocationInfo {
     Unknown,
     FileLineCol(String, usize, usize),
@@ -165,30 +165,27 @@ impl fmt::Display for OperationBuilder {
     }
 }
 
-use crate::core::pass_manager::OperationPassManager;
+use crate::core::pass_manager::{OperationPassManager, PipelineEntry};
 impl<T> fmt::Display for OperationPassManager<T>
 where
     T: Intrinsic,
 {
+    // Printed as a textual pipeline (see `parse_pass_pipeline`), without colors.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let tag = self.get_intrinsic();
-        let intr = format!(
-            "{}.{}",
-            Paint::green(tag.get_namespace()).underline(),
-            Paint::green(tag.get_name()).bold()
-        );
-        writeln!(f, "({})", intr)?;
-        for p in self.get_passes().iter() {
-            writeln!(
-                indented(f).with_str("  "),
-                "{}",
-                Paint::magenta(format!("{:?}", p))
-            )?;
-        }
-        for pm in self.get_managers().iter() {
-            writeln!(indented(f).with_str("  "), "{}", pm)?;
-        }
-        Ok(())
+        let elements = self
+            .get_entries()
+            .iter()
+            .map(|e| match e {
+                PipelineEntry::Pass(p) => format!("{}{}", p.get_argument(), p.get_options()),
+                PipelineEntry::Nested(pm) => format!("{}", pm),
+            })
+            .collect::<Vec<_>>();
+        write!(
+            f,
+            "{}({})",
+            self.get_intrinsic().get_unique_id(),
+            elements.join(", ")
+        )
     }
 }
 
@@ -196,14 +193,21 @@ use crate::core::pass_manager::AnalysisManager;
 impl fmt::Display for AnalysisManager {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "({})", Paint::green("AnalysisManager").bold())?;
-        for (key, analysis) in self.get_cached().iter() {
-            match key.query_ref::<dyn fmt::Display>() {
-                None => (),
-                Some(v) => writeln!(indented(f).with_str(" "), "{} =>", v)?,
-            }
-            match analysis.query_ref::<dyn fmt::Display>() {
-                None => (),
-                Some(v) => writeln!(indented(f).with_str(" | "), "{}", v)?,
+        for (id, analyses) in self.get_cached().iter() {
+            writeln!(
+                indented(f).with_str(" "),
+                "{}",
+                Paint::white(format!("#{}", id))
+            )?;
+            for (key, analysis) in analyses.iter() {
+                match key.query_ref::<dyn fmt::Display>() {
+                    None => (),
+                    Some(v) => writeln!(indented(f).with_str("  "), "{} =>", v)?,
+                }
+                match analysis.query_ref::<dyn fmt::Display>() {
+                    None => (),
+                    Some(v) => writeln!(indented(f).with_str("  | "), "{}", v)?,
+                }
             }
         }
         Ok(())
@@ -0,0 +1,277 @@
+use crate::core::ir::Var;
+use crate::core::region::Region;
+
+/// The dominator tree over the blocks of a [`Region`].
+///
+/// Block `a` dominates block `b` if every path from the entry block (block 0)
+/// to `b` passes through `a`. Control flow edges are given by the successors
+/// of each block's terminator. Blocks which are unreachable from the entry
+/// block are not part of the tree, and neither dominate nor are dominated by other blocks.
+///
+/// The tree is computed with the iterative algorithm of Cooper, Harvey and Kennedy
+/// ("A Simple, Fast Dominance Algorithm").
+#[derive(Debug, Clone)]
+pub struct DominanceInfo {
+    idoms: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    predecessors: Vec<Vec<usize>>,
+    rpo: Vec<usize>,
+}
+
+impl DominanceInfo {
+    pub fn new(r: &Region) -> DominanceInfo {
+        let nblocks = r.get_blocks().len();
+        let successors = (0..nblocks)
+            .map(|b| r.get_block_successors(b))
+            .collect::<Vec<_>>();
+        let mut predecessors = vec![Vec::new(); nblocks];
+        for (b, succs) in successors.iter().enumerate() {
+            for s in succs.iter() {
+                if *s < nblocks && !predecessors[*s].contains(&b) {
+                    predecessors[*s].push(b);
+                }
+            }
+        }
+
+        // Postorder traversal from the entry block.
+        let mut postorder = Vec::new();
+        let mut visited = vec![false; nblocks];
+        let mut stack = Vec::new();
+        if nblocks > 0 {
+            visited[0] = true;
+            stack.push((0, 0));
+        }
+        while let Some((b, next)) = stack.pop() {
+            match successors[b].get(next) {
+                None => postorder.push(b),
+                Some(s) => {
+                    stack.push((b, next + 1));
+                    if *s < nblocks && !visited[*s] {
+                        visited[*s] = true;
+                        stack.push((*s, 0));
+                    }
+                }
+            }
+        }
+        let mut order = vec![usize::MAX; nblocks];
+        for (i, b) in postorder.iter().enumerate() {
+            order[*b] = i;
+        }
+        let rpo = postorder.into_iter().rev().collect::<Vec<_>>();
+
+        let mut idoms: Vec<Option<usize>> = vec![None; nblocks];
+        if nblocks > 0 {
+            idoms[0] = Some(0);
+        }
+        let intersect = |idoms: &[Option<usize>], mut a: usize, mut b: usize| {
+            while a != b {
+                while order[a] < order[b] {
+                    a = idoms[a].unwrap();
+                }
+                while order[b] < order[a] {
+                    b = idoms[b].unwrap();
+                }
+            }
+            a
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for b in rpo.iter().skip(1) {
+                let mut new_idom = None;
+                for p in predecessors[*b].iter() {
+                    if idoms[*p].is_none() {
+                        continue;
+                    }
+                    new_idom = match new_idom {
+                        None => Some(*p),
+                        Some(i) => Some(intersect(&idoms, *p, i)),
+                    };
+                }
+                if new_idom.is_some() && idoms[*b] != new_idom {
+                    idoms[*b] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut children = vec![Vec::new(); nblocks];
+        for b in rpo.iter().skip(1) {
+            children[idoms[*b].unwrap()].push(*b);
+        }
+        DominanceInfo {
+            idoms,
+            children,
+            predecessors,
+            rpo,
+        }
+    }
+
+    pub fn is_reachable(&self, blk: usize) -> bool {
+        self.idoms.get(blk).is_some_and(|i| i.is_some())
+    }
+
+    /// Get the immediate dominator of `blk`.
+    /// Returns `None` for the entry block and unreachable blocks.
+    pub fn get_idom(&self, blk: usize) -> Option<usize> {
+        match self.idoms.get(blk) {
+            Some(Some(i)) if blk != 0 => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Get the blocks which are immediately dominated by `blk`.
+    pub fn get_children(&self, blk: usize) -> &[usize] {
+        &self.children[blk]
+    }
+
+    /// Get the predecessors of `blk` in the control flow graph.
+    pub fn get_predecessors(&self, blk: usize) -> &[usize] {
+        &self.predecessors[blk]
+    }
+
+    /// Get the reachable blocks in reverse postorder.
+    pub fn get_reverse_postorder(&self) -> &[usize] {
+        &self.rpo
+    }
+
+    /// Get the reachable blocks in a preorder traversal of the dominator tree,
+    /// every block is visited after the blocks which dominate it.
+    pub fn get_preorder(&self) -> Vec<usize> {
+        let mut order = Vec::new();
+        let mut stack = Vec::new();
+        if self.is_reachable(0) {
+            stack.push(0);
+        }
+        while let Some(b) = stack.pop() {
+            order.push(b);
+            stack.extend(self.children[b].iter().rev());
+        }
+        order
+    }
+
+    /// Returns `true` if block `a` dominates block `b`.
+    /// Every reachable block dominates itself.
+    pub fn dominates(&self, a: usize, b: usize) -> bool {
+        if !self.is_reachable(a) || !self.is_reachable(b) {
+            return false;
+        }
+        let mut b = b;
+        loop {
+            if a == b {
+                return true;
+            }
+            match self.get_idom(b) {
+                None => return false,
+                Some(i) => b = i,
+            }
+        }
+    }
+
+    pub fn properly_dominates(&self, a: usize, b: usize) -> bool {
+        a != b && self.dominates(a, b)
+    }
+
+    /// Returns `true` if the operation defining `a` dominates
+    /// the operation defining `b` (in region `r`) -
+    /// either `a` is defined earlier in the same block,
+    /// or the block of `a` properly dominates the block of `b`.
+    pub fn dominates_op(&self, r: &Region, a: Var, b: Var) -> bool {
+        match (r.get_op_position(a), r.get_op_position(b)) {
+            (Some((ba, ia)), Some((bb, ib))) if ba == bb => ia <= ib && self.is_reachable(ba),
+            (Some((ba, _)), Some((bb, _))) => self.properly_dominates(ba, bb),
+            _ => false,
+        }
+    }
+}
+
+/// A natural loop in the control flow graph of a [`Region`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    header: usize,
+    blocks: Vec<usize>,
+    latches: Vec<usize>,
+}
+
+impl Loop {
+    /// The loop header, which dominates every block in the loop.
+    pub fn get_header(&self) -> usize {
+        self.header
+    }
+
+    /// The blocks in the loop (including the header), in ascending order.
+    pub fn get_blocks(&self) -> &[usize] {
+        &self.blocks
+    }
+
+    /// The blocks in the loop which branch back to the header.
+    pub fn get_latches(&self) -> &[usize] {
+        &self.latches
+    }
+
+    pub fn contains(&self, blk: usize) -> bool {
+        self.blocks.binary_search(&blk).is_ok()
+    }
+}
+
+/// The natural loops of a [`Region`], computed from a [`DominanceInfo`].
+///
+/// A back edge is a control flow edge from a block to a block which dominates it
+/// (the loop header). The loop with that header consists of the blocks which reach
+/// a back edge without passing through the header. Back edges to the same
+/// header form a single loop.
+#[derive(Debug, Clone)]
+pub struct LoopInfo {
+    loops: Vec<Loop>,
+}
+
+impl LoopInfo {
+    pub fn new(dom: &DominanceInfo) -> LoopInfo {
+        let mut loops = Vec::new();
+        for h in dom.get_reverse_postorder().iter() {
+            let latches = dom
+                .get_predecessors(*h)
+                .iter()
+                .filter(|p| dom.dominates(*h, **p))
+                .copied()
+                .collect::<Vec<_>>();
+            if latches.is_empty() {
+                continue;
+            }
+            let mut blocks = vec![*h];
+            let mut stack = latches.clone();
+            while let Some(b) = stack.pop() {
+                if blocks.contains(&b) || !dom.is_reachable(b) {
+                    continue;
+                }
+                blocks.push(b);
+                stack.extend(dom.get_predecessors(b).iter());
+            }
+            blocks.sort_unstable();
+            loops.push(Loop {
+                header: *h,
+                blocks,
+                latches,
+            });
+        }
+        LoopInfo { loops }
+    }
+
+    /// The loops, ordered by their header in reverse postorder
+    /// (so outer loops come before the loops nested in them).
+    pub fn get_loops(&self) -> &[Loop] {
+        &self.loops
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.loops.is_empty()
+    }
+
+    /// Get the innermost loop which contains `blk`.
+    pub fn get_loop_for(&self, blk: usize) -> Option<&Loop> {
+        self.loops
+            .iter()
+            .filter(|l| l.contains(blk))
+            .min_by_key(|l| l.blocks.len())
+    }
+}
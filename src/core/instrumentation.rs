@@ -0,0 +1,315 @@
+use crate::core::ir::{Operation, SupportsInterfaceTraits};
+use crate::core::pass_manager::{AnalysisKey, OperationPass};
+use color_eyre::{eyre::bail, Report};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+use yansi::Paint;
+
+/// Hooks which are called by an [`crate::core::OperationPassManager`]
+/// (and the [`crate::core::AnalysisManager`] it creates) around pass and analysis execution.
+///
+/// Instrumentations are shared (by `Arc`) between nested pass managers,
+/// and between threads - so any state should be kept behind a lock.
+/// Errors returned from `run_before_pass` and `run_after_pass` fail the pipeline.
+///
+/// When a `run_before_pass` hook fails, the pass isn't run, and the instrumentations
+/// whose `run_before_pass` hook already ran are unwound with `run_after_pass_failed`.
+/// When the pass succeeds, every `run_after_pass` hook is called, even if an earlier one fails.
+pub trait PassInstrumentation: Send + Sync + fmt::Debug {
+    fn run_before_pass(&self, _pass: &dyn OperationPass, _op: &Operation) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn run_after_pass(&self, _pass: &dyn OperationPass, _op: &Operation) -> Result<(), Report> {
+        Ok(())
+    }
+
+    /// Called (instead of `run_after_pass`) when the pass fails.
+    fn run_after_pass_failed(&self, _pass: &dyn OperationPass, _op: &Operation, _err: &Report) {}
+
+    fn run_before_analysis(&self, _key: &dyn AnalysisKey, _op: &Operation) {}
+
+    fn run_after_analysis(&self, _key: &dyn AnalysisKey, _op: &Operation) {}
+}
+
+/// Records the wall-clock time spent in each pass (by [`OperationPass::get_name`]),
+/// summed over every operation the pass is applied to.
+#[derive(Debug, Default)]
+pub struct PassTiming {
+    started: Mutex<HashMap<ThreadId, Vec<Instant>>>,
+    timings: Mutex<Vec<(String, Duration, usize)>>,
+}
+
+impl PassTiming {
+    pub fn new() -> PassTiming {
+        PassTiming::default()
+    }
+
+    /// Get the total time and number of runs of each pass,
+    /// in the order the passes were first run.
+    pub fn get_timings(&self) -> Vec<(String, Duration, usize)> {
+        self.timings.lock().unwrap().to_vec()
+    }
+
+    pub fn get_total(&self) -> Duration {
+        self.timings
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, d, _)| *d)
+            .sum()
+    }
+}
+
+impl PassInstrumentation for PassTiming {
+    fn run_before_pass(&self, _pass: &dyn OperationPass, _op: &Operation) -> Result<(), Report> {
+        let id = std::thread::current().id();
+        let mut started = self.started.lock().unwrap();
+        started.entry(id).or_default().push(Instant::now());
+        Ok(())
+    }
+
+    fn run_after_pass(&self, pass: &dyn OperationPass, _op: &Operation) -> Result<(), Report> {
+        let id = std::thread::current().id();
+        let start = match self.started.lock().unwrap().get_mut(&id) {
+            Some(stack) => stack.pop(),
+            None => None,
+        };
+        if let Some(start) = start {
+            let elapsed = start.elapsed();
+            let name = pass.get_name();
+            let mut timings = self.timings.lock().unwrap();
+            match timings.iter_mut().find(|(n, _, _)| *n == name) {
+                Some((_, d, runs)) => {
+                    *d += elapsed;
+                    *runs += 1;
+                }
+                None => timings.push((name, elapsed, 1)),
+            }
+        }
+        Ok(())
+    }
+
+    fn run_after_pass_failed(&self, _pass: &dyn OperationPass, _op: &Operation, _err: &Report) {
+        let id = std::thread::current().id();
+        if let Some(stack) = self.started.lock().unwrap().get_mut(&id) {
+            stack.pop();
+        }
+    }
+}
+
+impl fmt::Display for PassTiming {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let total = self.get_total();
+        writeln!(
+            f,
+            "({}) Total: {:.6}s",
+            Paint::green("PassTiming").bold(),
+            total.as_secs_f64()
+        )?;
+        for (name, d, runs) in self.get_timings().iter() {
+            let percent = match total.is_zero() {
+                true => 0.0,
+                false => 100.0 * d.as_secs_f64() / total.as_secs_f64(),
+            };
+            writeln!(
+                f,
+                "  {:.6}s ({:5.1}%) {} ({} runs)",
+                d.as_secs_f64(),
+                percent,
+                Paint::magenta(name),
+                runs
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Selects the passes (by [`OperationPass::get_argument`], e.g. `cse`) for [`IRPrinting`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PassFilter {
+    None,
+    All,
+    Only(Vec<String>),
+}
+
+impl PassFilter {
+    pub fn matches(&self, pass: &dyn OperationPass) -> bool {
+        match self {
+            PassFilter::None => false,
+            PassFilter::All => true,
+            PassFilter::Only(args) => args.iter().any(|a| *a == pass.get_argument()),
+        }
+    }
+}
+
+/// Prints the IR before and/or after selected passes.
+///
+/// By default, the IR is printed to stdout -
+/// [`IRPrinting::with_output`] collects the printed IR in a buffer instead.
+#[derive(Debug)]
+pub struct IRPrinting {
+    before: PassFilter,
+    after: PassFilter,
+    output: Option<Arc<Mutex<String>>>,
+}
+
+impl Default for IRPrinting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IRPrinting {
+    pub fn new() -> IRPrinting {
+        IRPrinting {
+            before: PassFilter::None,
+            after: PassFilter::None,
+            output: None,
+        }
+    }
+
+    pub fn print_before(mut self, filter: PassFilter) -> IRPrinting {
+        self.before = filter;
+        self
+    }
+
+    pub fn print_after(mut self, filter: PassFilter) -> IRPrinting {
+        self.after = filter;
+        self
+    }
+
+    pub fn with_output(mut self, output: Arc<Mutex<String>>) -> IRPrinting {
+        self.output = Some(output);
+        self
+    }
+
+    fn print(&self, when: &str, pass: &dyn OperationPass, op: &Operation) {
+        let s = format!(
+            "// -----// IR Dump {} {} //----- //\n{}\n",
+            when,
+            Paint::magenta(pass.get_name()),
+            op
+        );
+        match &self.output {
+            None => println!("{}", s),
+            Some(out) => out.lock().unwrap().push_str(&s),
+        }
+    }
+}
+
+impl PassInstrumentation for IRPrinting {
+    fn run_before_pass(&self, pass: &dyn OperationPass, op: &Operation) -> Result<(), Report> {
+        if self.before.matches(pass) {
+            self.print("Before", pass, op);
+        }
+        Ok(())
+    }
+
+    fn run_after_pass(&self, pass: &dyn OperationPass, op: &Operation) -> Result<(), Report> {
+        if self.after.matches(pass) {
+            self.print("After", pass, op);
+        }
+        Ok(())
+    }
+
+    fn run_after_pass_failed(&self, pass: &dyn OperationPass, op: &Operation, _err: &Report) {
+        if self.after.matches(pass) {
+            self.print("After (failed)", pass, op);
+        }
+    }
+}
+
+/// Verifies the operation after every pass, failing the
+/// pipeline if a pass produces invalid IR.
+#[derive(Debug, Default)]
+pub struct VerifyEach;
+
+impl PassInstrumentation for VerifyEach {
+    fn run_after_pass(&self, pass: &dyn OperationPass, op: &Operation) -> Result<(), Report> {
+        let intr = op.get_intrinsic();
+        if let Err(e) = intr.verify(intr, op) {
+            bail!(format!(
+                "Verification failed after {}:\n{}\n\n{}",
+                Paint::magenta(pass.get_name()),
+                e,
+                op
+            ))
+        }
+        Ok(())
+    }
+}
+
+/// Emits a `tracing` span (at the `INFO` level) around each pass
+/// and analysis.
+#[derive(Debug, Default)]
+pub struct PassTracing {
+    spans: Mutex<HashMap<ThreadId, Vec<tracing::Span>>>,
+}
+
+impl PassTracing {
+    pub fn new() -> PassTracing {
+        PassTracing::default()
+    }
+
+    fn enter(&self, span: tracing::Span) {
+        span.with_subscriber(|(id, dispatch)| dispatch.enter(id));
+        let mut spans = self.spans.lock().unwrap();
+        spans
+            .entry(std::thread::current().id())
+            .or_default()
+            .push(span);
+    }
+
+    fn exit(&self) {
+        let span = match self
+            .spans
+            .lock()
+            .unwrap()
+            .get_mut(&std::thread::current().id())
+        {
+            Some(stack) => stack.pop(),
+            None => None,
+        };
+        if let Some(span) = span {
+            span.with_subscriber(|(id, dispatch)| dispatch.exit(id));
+        }
+    }
+}
+
+impl PassInstrumentation for PassTracing {
+    fn run_before_pass(&self, pass: &dyn OperationPass, op: &Operation) -> Result<(), Report> {
+        let intr = op.get_intrinsic();
+        self.enter(tracing::info_span!(
+            "pass",
+            name = %pass.get_name(),
+            op = %intr.get_unique_id()
+        ));
+        Ok(())
+    }
+
+    fn run_after_pass(&self, _pass: &dyn OperationPass, _op: &Operation) -> Result<(), Report> {
+        self.exit();
+        Ok(())
+    }
+
+    fn run_after_pass_failed(&self, _pass: &dyn OperationPass, _op: &Operation, err: &Report) {
+        tracing::error!("{}", err);
+        self.exit();
+    }
+
+    fn run_before_analysis(&self, _key: &dyn AnalysisKey, op: &Operation) {
+        let intr = op.get_intrinsic();
+        self.enter(tracing::info_span!(
+            "analysis",
+            op = %intr.get_unique_id()
+        ));
+    }
+
+    fn run_after_analysis(&self, _key: &dyn AnalysisKey, _op: &Operation) {
+        self.exit();
+    }
+}
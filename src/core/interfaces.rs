@@ -44,7 +44,7 @@ macro_rules! vtable_for {
         let x = ::std::ptr::null::<$x>() as *const $y;
         #[allow(unused_unsafe)]
         unsafe {
-            ::std::mem::transmute::<_, $crate::core::TraitObject>(x).vtable
+            ::std::mem::transmute::<*const $y, $crate::core::TraitObject>(x).vtable
         }
     }};
 }
@@ -63,7 +63,9 @@ macro_rules! mopo {
                             data: data as *const (),
                             vtable,
                         };
-                        Some(*::std::mem::transmute::<_, &&U>(&u))
+                        Some(*::std::mem::transmute::<&$crate::core::TraitObject, &&U>(
+                            &u,
+                        ))
                     }
                 } else {
                     None
@@ -79,7 +81,10 @@ macro_rules! mopo {
                             data: data as *const (),
                             vtable,
                         };
-                        Some(*::std::mem::transmute::<_, &mut &mut U>(&mut u))
+                        Some(*::std::mem::transmute::<
+                            &mut $crate::core::TraitObject,
+                            &mut &mut U,
+                        >(&mut u))
                     }
                 } else {
                     None
@@ -97,9 +102,10 @@ macro_rules! mopo {
                             data: data as *const (),
                             vtable,
                         };
-                        Ok(Box::from_raw(*::std::mem::transmute::<_, &mut *mut U>(
-                            &mut u,
-                        )))
+                        Ok(Box::from_raw(*::std::mem::transmute::<
+                            &mut $crate::core::TraitObject,
+                            &mut *mut U,
+                        >(&mut u)))
                     }
                 } else {
                     Err(self)
@@ -118,7 +124,7 @@ macro_rules! mopo {
                             vtable,
                         };
                         Ok(::std::sync::Arc::from_raw(*::std::mem::transmute::<
-                            _,
+                            &mut $crate::core::TraitObject,
                             &mut *mut U,
                         >(&mut u)))
                     }
@@ -139,7 +145,7 @@ macro_rules! mopo {
                             vtable,
                         };
                         Ok(::std::rc::Rc::from_raw(*::std::mem::transmute::<
-                            _,
+                            &mut $crate::core::TraitObject,
                             &mut *mut U,
                         >(&mut u)))
                     }
@@ -228,7 +234,10 @@ macro_rules! mopo {
                         return o;
                     }
                 }
-                Ord::cmp(&(self as *const Self), &(other as *const Self))
+                Ord::cmp(
+                    &(self as *const Self).cast::<()>(),
+                    &(other as *const Self).cast::<()>(),
+                )
             }
         }
 
@@ -250,6 +259,11 @@ macro_rules! mopo {
 ///
 /// This trait is the core provider of the interface functionality. Consumers of the library should
 /// not need to deeply understand `Object`.
+///
+/// # Safety
+///
+/// Implementations must only return vtables which belong to the
+/// implementing type, which is guaranteed by the `interfaces!` macro.
 pub unsafe trait Object: Any {
     /// This is implemented by the `interfaces!` macro, and should never be
     /// manually implemented.
@@ -267,6 +281,11 @@ pub unsafe trait Object: Any {
 /// stringent requirement than, and in practice implies `SomeTrait`, the
 /// compiler cannot deduce that because it is enforced through macros rather
 /// than the type system.
+///
+/// # Safety
+///
+/// Only implemented by the `interfaces!` macro, for traits which are
+/// registered for the type.
 pub unsafe trait HasInterface<I: ?Sized> {}
 
 mopo!(dyn Object);
@@ -505,6 +524,9 @@ pub struct Registry {
 }
 
 impl Registry {
+    /// # Safety
+    ///
+    /// `vtable` must be the vtable of `Type` as a `Trait` object.
     #[doc(hidden)]
     pub unsafe fn register<Type: 'static + ?Sized, Trait: 'static + ?Sized>(
         &mut self,
@@ -634,6 +656,7 @@ mod tests {
     }
     impl Foo2 for Bar {}
 
+    #[allow(dead_code)]
     #[derive(Debug, Clone)]
     struct GenericBar<T>(T);
     interfaces!(<T: Debug + 'static> GenericBar<T>: dyn super::ObjectClone, dyn Debug where T: Clone);
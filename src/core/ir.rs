@@ -1,10 +1,13 @@
+use crate::core::builder::OperationBuilder;
 use crate::core::diagnostics::LocationInfo;
 use crate::core::interfaces::*;
 use crate::core::region::Region;
-use color_eyre::Report;
+use color_eyre::{eyre::bail, Report};
 use downcast_rs::{impl_downcast, Downcast};
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use yansi::Paint;
 
 /// A primitive SSA register.
 ///
@@ -35,7 +38,10 @@ impl Var {
 ///
 /// Users of the crate should likely use the [`intrinsic!`] declarative
 /// macro to define new intrinsics.
-pub trait Intrinsic: Downcast + Object + ObjectClone {
+///
+/// Intrinsics (like attributes) are `Send + Sync`, so that operations
+/// can be transformed on multiple threads.
+pub trait Intrinsic: Downcast + Object + ObjectClone + Send + Sync {
     fn get_namespace(&self) -> &str;
     fn get_name(&self) -> &str;
     fn get_unique_id(&self) -> String {
@@ -50,6 +56,7 @@ pub trait Intrinsic: Downcast + Object + ObjectClone {
     /// Mostly, the user will never be required to define this interface
     /// directly, and should use the declarative [`intrinsic!`] macro
     /// (which will handle defining this method).
+    #[allow(clippy::borrowed_box)]
     fn verify(
         &self,
         boxed: &Box<dyn Intrinsic>,
@@ -112,7 +119,10 @@ macro_rules! intrinsic {
 }
 
 /// Constant metadata which can be attached to [`Operation`] instances.
-pub trait Attribute: Object + std::fmt::Display {}
+///
+/// Attributes are compared with `PartialEq` when comparing operations
+/// (e.g. to detect redundant operations), and must be `Send + Sync`.
+pub trait Attribute: Object + std::fmt::Display + Send + Sync {}
 mopo!(dyn Attribute);
 
 pub trait AttributeValue<T> {
@@ -180,6 +190,8 @@ macro_rules! attribute {
         }
 
         interfaces!($struct: dyn Attribute,
+            dyn ObjectClone,
+            dyn ObjectPartialEq,
             dyn std::fmt::Display,
             dyn std::fmt::Debug,
             dyn AttributeValue<$struct>);
@@ -189,6 +201,7 @@ macro_rules! attribute {
 /// A trait which provides non-mutating accessors for use in checking
 /// intrinsic/operation verification conditions.
 pub trait SupportsInterfaceTraits: std::fmt::Display {
+    #[allow(clippy::borrowed_box)]
     fn get_intrinsic(&self) -> &Box<dyn Intrinsic>;
     fn get_operands(&self) -> &[Var];
     fn get_regions(&self) -> &[Region];
@@ -207,8 +220,12 @@ pub trait SupportsInterfaceTraits: std::fmt::Display {
 ///
 /// [`Operation`] instances are almost always created through the builder interface
 /// ([`crate::core::OperationBuilder`]).
+///
+/// Each [`Operation`] is given a unique id when it is created, which identifies
+/// the operation (e.g. for analysis caching) as it is moved and mutated.
 #[derive(Debug)]
 pub struct Operation {
+    id: usize,
     location: LocationInfo,
     intrinsic: Box<dyn Intrinsic>,
     operands: Vec<Var>,
@@ -217,6 +234,8 @@ pub struct Operation {
     successors: Vec<usize>,
 }
 
+static NEXT_OPERATION_ID: AtomicUsize = AtomicUsize::new(0);
+
 impl Hash for Operation {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.location.hash(state);
@@ -259,6 +278,7 @@ impl Operation {
         successors: Vec<usize>,
     ) -> Operation {
         Operation {
+            id: NEXT_OPERATION_ID.fetch_add(1, Ordering::Relaxed),
             location,
             intrinsic,
             operands,
@@ -268,6 +288,10 @@ impl Operation {
         }
     }
 
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+
     pub fn get_location(&self) -> &LocationInfo {
         &self.location
     }
@@ -276,6 +300,14 @@ impl Operation {
         self.operands.to_vec()
     }
 
+    pub fn get_operands_mut(&mut self) -> &mut Vec<Var> {
+        &mut self.operands
+    }
+
+    pub fn set_operands(&mut self, operands: Vec<Var>) {
+        self.operands = operands;
+    }
+
     pub fn get_attributes(&self) -> &HashMap<String, Box<dyn Attribute>> {
         &self.attributes
     }
@@ -283,23 +315,26 @@ impl Operation {
     pub fn get_attributes_mut(&mut self) -> &mut HashMap<String, Box<dyn Attribute>> {
         &mut self.attributes
     }
+
+    pub fn get_regions_mut(&mut self) -> &mut [Region] {
+        &mut self.regions
+    }
+
+    pub fn get_successors(&self) -> &[usize] {
+        &self.successors
+    }
+
+    pub fn set_successors(&mut self, successors: Vec<usize>) {
+        self.successors = successors;
+    }
 }
 
-#[derive(Debug, Hash)]
+#[derive(Debug, Default, Hash)]
 pub struct BasicBlock {
     operands: Vec<Var>,
     ops: Vec<Operation>,
 }
 
-impl Default for BasicBlock {
-    fn default() -> BasicBlock {
-        BasicBlock {
-            ops: Vec::new(),
-            operands: Vec::new(),
-        }
-    }
-}
-
 impl BasicBlock {
     pub fn get_ops(&self) -> &[Operation] {
         &self.ops
@@ -318,6 +353,81 @@ impl BasicBlock {
     }
 }
 
+/// Operations of `IsolatedFromAbove` intrinsics don't use values
+/// defined outside of their regions, so they can be transformed
+/// independently of (and concurrently with) their siblings.
+/// See <https://mlir.llvm.org/docs/Traits/#isolatedfromabove>.
+pub trait IsolatedFromAbove {
+    fn verify(&self, op: &dyn SupportsInterfaceTraits) -> Result<(), Report> {
+        for r in op.get_regions().iter() {
+            let is_arg = |o: &Var| r.get_blocks().iter().any(|b| b.get_operands().contains(o));
+            for b in 0..r.get_blocks().len() {
+                for (v, child) in r.get_block_iter(b) {
+                    // The arguments of the entry block of an operation's first region
+                    // are also operands of the operation (see `OperationBuilder::push_arg`).
+                    let entry = child
+                        .get_regions()
+                        .first()
+                        .and_then(|cr| cr.get_blocks().first())
+                        .map_or(&[][..], |blk| blk.get_operands());
+                    for o in child.get_operands().iter() {
+                        if r.get_op(*o).is_none() && !is_arg(o) && !entry.contains(o) {
+                            bail!(format!(
+                                "{} at ({}, {}) uses {}, which is not defined in the region of {}.",
+                                child.get_intrinsic(),
+                                Paint::white(format!("{}", b)).bold(),
+                                v,
+                                o,
+                                op.get_intrinsic()
+                            ))
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Operations which call a function by its symbol (see [`crate::core::Interpreter`]).
+pub trait CallLike {
+    fn verify(&self, _op: &dyn SupportsInterfaceTraits) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn get_callee(&self, op: &dyn SupportsInterfaceTraits) -> Option<String>;
+}
+
+/// Terminators which forward operands to the arguments of their successor blocks.
+pub trait SuccessorOperands {
+    fn verify(&self, _op: &dyn SupportsInterfaceTraits) -> Result<(), Report> {
+        Ok(())
+    }
+
+    /// Get the operands forwarded to each successor of `op`
+    /// (in the order of [`Operation::get_successors`]), where `r`
+    /// is the region which holds `op`.
+    fn get_successor_operands(&self, r: &Region, op: &Operation) -> Vec<Vec<Var>>;
+}
+
+/// Terminators which can be replaced with an unconditional branch
+/// to one of their successors (e.g. once the condition of a branch is known).
+pub trait FoldSuccessor {
+    fn verify(&self, _op: &dyn SupportsInterfaceTraits) -> Result<(), Report> {
+        Ok(())
+    }
+
+    /// Build a branch to successor `ind` of `op` (in the order of
+    /// [`Operation::get_successors`]) which forwards the same operands,
+    /// where `r` is the region which holds `op`.
+    fn fold_successor(
+        &self,
+        r: &Region,
+        op: &Operation,
+        ind: usize,
+    ) -> Result<OperationBuilder, Report>;
+}
+
 /////
 ///// Lowering.
 /////
@@ -0,0 +1,631 @@
+//! A library of standard lattices for abstract interpretation.
+//!
+//! The lattices in this module are independent of any dialect -
+//! the propagation rules for the standard dialects are registered
+//! by the dialects (e.g. `register_interval_semantics` in [`crate::dialects::arith`]).
+//!
+//! Integers are modelled as (sign extended) `i64` values, and integers of width 1
+//! (booleans) are `0` or `1`. Operations which wrap take the width of the integers
+//! (e.g. [`Interval::add`]) - when the width isn't known, the result must include
+//! the result at every width the operands may have (see [`Interval::has_width`]).
+
+use crate::core::absint::{LatticeConvert, LatticeJoin, LatticeMeet, LatticeNarrow, LatticeWiden};
+use std::fmt;
+use yansi::Paint;
+
+/// The lattice of signs.
+///
+/// Each element is a set of signs (negative, zero and positive),
+/// ordered by inclusion - `Bottom` is the empty set and `Top` includes every sign.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sign {
+    Bottom,
+    Negative,
+    Zero,
+    Positive,
+    NonPositive,
+    NonZero,
+    NonNegative,
+    Top,
+}
+
+impl Sign {
+    const NEG: u8 = 1;
+    const ZERO: u8 = 2;
+    const POS: u8 = 4;
+
+    fn from_bits(bits: u8) -> Sign {
+        match bits {
+            0 => Sign::Bottom,
+            Sign::NEG => Sign::Negative,
+            Sign::ZERO => Sign::Zero,
+            Sign::POS => Sign::Positive,
+            3 => Sign::NonPositive,
+            5 => Sign::NonZero,
+            6 => Sign::NonNegative,
+            _ => Sign::Top,
+        }
+    }
+
+    fn get_bits(&self) -> u8 {
+        match self {
+            Sign::Bottom => 0,
+            Sign::Negative => Sign::NEG,
+            Sign::Zero => Sign::ZERO,
+            Sign::Positive => Sign::POS,
+            Sign::NonPositive => Sign::NEG | Sign::ZERO,
+            Sign::NonZero => Sign::NEG | Sign::POS,
+            Sign::NonNegative => Sign::ZERO | Sign::POS,
+            Sign::Top => Sign::NEG | Sign::ZERO | Sign::POS,
+        }
+    }
+
+    pub fn from_integer(v: i64) -> Sign {
+        match v {
+            0 => Sign::Zero,
+            v if v < 0 => Sign::Negative,
+            _ => Sign::Positive,
+        }
+    }
+
+    pub fn from_float(v: f64) -> Sign {
+        match v {
+            v if v.is_nan() => Sign::Top,
+            v if v < 0.0 => Sign::Negative,
+            v if v > 0.0 => Sign::Positive,
+            _ => Sign::Zero,
+        }
+    }
+
+    pub fn may_be_zero(&self) -> bool {
+        self.get_bits() & Sign::ZERO != 0
+    }
+
+    pub fn may_be_nonzero(&self) -> bool {
+        self.get_bits() & (Sign::NEG | Sign::POS) != 0
+    }
+
+    /// The signs of `a + b`, for any `a` in `self` and `b` in `other`,
+    /// where the sum doesn't overflow (e.g. floating point addition).
+    pub fn add(&self, other: &Sign) -> Sign {
+        let mut bits = 0;
+        for a in [Sign::NEG, Sign::ZERO, Sign::POS] {
+            for b in [Sign::NEG, Sign::ZERO, Sign::POS] {
+                if self.get_bits() & a == 0 || other.get_bits() & b == 0 {
+                    continue;
+                }
+                bits |= match (a, b) {
+                    (Sign::ZERO, s) | (s, Sign::ZERO) => s,
+                    (a, b) if a == b => a,
+                    _ => Sign::NEG | Sign::ZERO | Sign::POS,
+                };
+            }
+        }
+        Sign::from_bits(bits)
+    }
+
+    /// Whether some integer of width `width` has a sign in `self`.
+    pub fn has_width(&self, width: usize) -> bool {
+        match width {
+            1 => self.get_bits() & (Sign::ZERO | Sign::POS) != 0,
+            _ => *self != Sign::Bottom,
+        }
+    }
+
+    /// The signs of `a + b` (wrapping), for any integers `a` in `self` and `b` in `other`
+    /// of width `width`. The sum of two positive integers may overflow to a negative
+    /// integer (or to zero, at width 1), and the sum of two negative integers to any
+    /// integer (`MIN + MIN == 0`).
+    pub fn add_wrapping(&self, other: &Sign, width: usize) -> Sign {
+        let mut bits = 0;
+        for a in [Sign::NEG, Sign::ZERO, Sign::POS] {
+            for b in [Sign::NEG, Sign::ZERO, Sign::POS] {
+                if self.get_bits() & a == 0 || other.get_bits() & b == 0 {
+                    continue;
+                }
+                bits |= match (a, b) {
+                    (Sign::ZERO, s) | (s, Sign::ZERO) => s,
+                    (Sign::POS, Sign::POS) if width == 1 => Sign::ZERO,
+                    (Sign::POS, Sign::POS) => Sign::NEG | Sign::POS,
+                    _ => Sign::NEG | Sign::ZERO | Sign::POS,
+                };
+            }
+        }
+        Sign::from_bits(bits)
+    }
+
+    /// The signs of `a & b` (bitwise, in two's complement),
+    /// for any `a` in `self` and `b` in `other`.
+    pub fn and(&self, other: &Sign) -> Sign {
+        let mut bits = 0;
+        for a in [Sign::NEG, Sign::ZERO, Sign::POS] {
+            for b in [Sign::NEG, Sign::ZERO, Sign::POS] {
+                if self.get_bits() & a == 0 || other.get_bits() & b == 0 {
+                    continue;
+                }
+                bits |= match (a, b) {
+                    (Sign::ZERO, _) | (_, Sign::ZERO) => Sign::ZERO,
+                    (Sign::NEG, Sign::NEG) => Sign::NEG,
+                    _ => Sign::ZERO | Sign::POS,
+                };
+            }
+        }
+        Sign::from_bits(bits)
+    }
+}
+
+impl LatticeJoin for Sign {
+    fn join(&self, other: &Sign) -> Sign {
+        Sign::from_bits(self.get_bits() | other.get_bits())
+    }
+}
+
+impl LatticeWiden for Sign {
+    fn widen(&self, other: &Sign) -> Sign {
+        self.join(other)
+    }
+}
+
+impl LatticeMeet for Sign {
+    fn meet(&self, other: &Sign) -> Sign {
+        Sign::from_bits(self.get_bits() & other.get_bits())
+    }
+}
+
+impl LatticeConvert<Interval> for Sign {
+    fn convert(&self) -> Interval {
+        let bits = self.get_bits();
+        if bits == 0 {
+            return Interval::Bottom;
+        }
+        let lo = match bits {
+            b if b & Sign::NEG != 0 => i64::MIN,
+            b if b & Sign::ZERO != 0 => 0,
+            _ => 1,
+        };
+        let hi = match bits {
+            b if b & Sign::POS != 0 => i64::MAX,
+            b if b & Sign::ZERO != 0 => 0,
+            _ => -1,
+        };
+        Interval::Range(lo, hi)
+    }
+}
+
+impl fmt::Display for Sign {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Sign::Bottom => "{}",
+            Sign::Negative => "< 0",
+            Sign::Zero => "0",
+            Sign::Positive => "> 0",
+            Sign::NonPositive => "<= 0",
+            Sign::NonZero => "!= 0",
+            Sign::NonNegative => ">= 0",
+            Sign::Top => "top",
+        };
+        write!(f, "{}", Paint::magenta(s).bold())
+    }
+}
+
+/// The lattice of integer intervals, where
+/// `i64::MIN` and `i64::MAX` are infinite bounds.
+///
+/// Intervals have infinite increasing chains, so interpretation
+/// of loops should use widening (see [`crate::core::Interpreter::with_widening`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Interval {
+    Bottom,
+    Range(i64, i64),
+}
+
+// The smallest and the largest (sign extended) integers of width `width`.
+fn width_bounds(width: usize) -> (i64, i64) {
+    match width {
+        0 | 64.. => (i64::MIN, i64::MAX),
+        1 => (0, 1),
+        w => (-(1 << (w - 1)), (1 << (w - 1)) - 1),
+    }
+}
+
+impl Interval {
+    pub fn top() -> Interval {
+        Interval::Range(i64::MIN, i64::MAX)
+    }
+
+    pub fn constant(v: i64) -> Interval {
+        Interval::Range(v, v)
+    }
+
+    pub fn get_constant(&self) -> Option<i64> {
+        match self {
+            Interval::Range(lo, hi) if lo == hi => Some(*lo),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, v: i64) -> bool {
+        match self {
+            Interval::Bottom => false,
+            Interval::Range(lo, hi) => *lo <= v && v <= *hi,
+        }
+    }
+
+    /// Whether some integer of width `width` is in `self`.
+    pub fn has_width(&self, width: usize) -> bool {
+        let (min, max) = width_bounds(width);
+        match self {
+            Interval::Bottom => false,
+            Interval::Range(lo, hi) => *lo <= max && min <= *hi,
+        }
+    }
+
+    /// The interval of `a + b` (wrapping), for any integers `a` in `self`
+    /// and `b` in `other` of width `width`. If the sum may overflow
+    /// (including at infinite bounds), every integer of the width is included.
+    pub fn add(&self, other: &Interval, width: usize) -> Interval {
+        let (min, max) = width_bounds(width);
+        let bounds = Interval::Range(min, max);
+        match (&self.meet(&bounds), &other.meet(&bounds)) {
+            (Interval::Range(a, b), Interval::Range(c, d)) => {
+                match (a.checked_add(*c), b.checked_add(*d)) {
+                    (Some(lo), Some(hi)) if min <= lo && hi <= max => Interval::Range(lo, hi),
+                    _ => Interval::Range(min, max),
+                }
+            }
+            _ => Interval::Bottom,
+        }
+    }
+
+    /// The interval of `a & b` (bitwise, in two's complement),
+    /// for any `a` in `self` and `b` in `other`.
+    pub fn and(&self, other: &Interval) -> Interval {
+        match (self, other) {
+            (Interval::Bottom, _) | (_, Interval::Bottom) => Interval::Bottom,
+            (Interval::Range(a, b), Interval::Range(c, d)) if a == b && c == d => {
+                Interval::constant(a & c)
+            }
+            // Masking with a non-negative value bounds the result.
+            (Interval::Range(a, b), Interval::Range(c, d)) => match (*a >= 0, *c >= 0) {
+                (true, true) => Interval::Range(0, *b.min(d)),
+                (true, false) => Interval::Range(0, *b),
+                (false, true) => Interval::Range(0, *d),
+                (false, false) => Interval::top(),
+            },
+        }
+    }
+
+    /// Compare the values of two intervals - `Some(true)` if every value of `self`
+    /// is less than every value of `other`, `Some(false)` if no value is, and `None` otherwise.
+    pub fn less_than(&self, other: &Interval) -> Option<bool> {
+        match (self, other) {
+            (Interval::Range(a, b), Interval::Range(c, d)) => match (b < c, a >= d) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl LatticeJoin for Interval {
+    fn join(&self, other: &Interval) -> Interval {
+        match (self, other) {
+            (Interval::Bottom, v) | (v, Interval::Bottom) => *v,
+            (Interval::Range(a, b), Interval::Range(c, d)) => Interval::Range(*a.min(c), *b.max(d)),
+        }
+    }
+}
+
+impl LatticeMeet for Interval {
+    fn meet(&self, other: &Interval) -> Interval {
+        match (self, other) {
+            (Interval::Range(a, b), Interval::Range(c, d)) if a.max(c) <= b.min(d) => {
+                Interval::Range(*a.max(c), *b.min(d))
+            }
+            _ => Interval::Bottom,
+        }
+    }
+}
+
+impl LatticeWiden for Interval {
+    fn widen(&self, other: &Interval) -> Interval {
+        match (self, other) {
+            (Interval::Bottom, v) | (v, Interval::Bottom) => *v,
+            (Interval::Range(a, b), Interval::Range(c, d)) => Interval::Range(
+                if c < a { i64::MIN } else { *a },
+                if d > b { i64::MAX } else { *b },
+            ),
+        }
+    }
+}
+
+impl LatticeNarrow for Interval {
+    fn narrow(&self, other: &Interval) -> Interval {
+        match (self, other) {
+            (Interval::Bottom, _) | (_, Interval::Bottom) => Interval::Bottom,
+            (Interval::Range(a, b), Interval::Range(c, d)) => Interval::Range(
+                if *a == i64::MIN { *c } else { *a },
+                if *b == i64::MAX { *d } else { *b },
+            ),
+        }
+    }
+}
+
+impl LatticeConvert<Sign> for Interval {
+    fn convert(&self) -> Sign {
+        match self {
+            Interval::Bottom => Sign::Bottom,
+            Interval::Range(lo, hi) => match self.contains(0) {
+                true => Sign::from_integer(*lo)
+                    .join(&Sign::Zero)
+                    .join(&Sign::from_integer(*hi)),
+                false => Sign::from_integer(*lo),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bound = |b: i64| match b {
+            i64::MIN => "-inf".to_string(),
+            i64::MAX => "+inf".to_string(),
+            b => b.to_string(),
+        };
+        match self {
+            Interval::Bottom => write!(f, "{}", Paint::magenta("{}").bold()),
+            Interval::Range(lo, hi) => write!(f, "[{}, {}]", bound(*lo), bound(*hi)),
+        }
+    }
+}
+
+/// The lattice of known bits of integers (in two's complement),
+/// where `zeros` and `ones` are the bits which are known to be `0` and `1`.
+///
+/// The lattice has finite height, so interpretation terminates without widening.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KnownBits {
+    Bottom,
+    Known { zeros: u64, ones: u64 },
+}
+
+impl KnownBits {
+    pub fn top() -> KnownBits {
+        KnownBits::Known { zeros: 0, ones: 0 }
+    }
+
+    pub fn constant(v: i64) -> KnownBits {
+        KnownBits::Known {
+            zeros: !(v as u64),
+            ones: v as u64,
+        }
+    }
+
+    pub fn get_constant(&self) -> Option<i64> {
+        match self {
+            KnownBits::Known { zeros, ones } if zeros | ones == u64::MAX => Some(*ones as i64),
+            _ => None,
+        }
+    }
+
+    pub fn may_be_zero(&self) -> bool {
+        matches!(self, KnownBits::Known { ones: 0, .. })
+    }
+
+    pub fn may_be_nonzero(&self) -> bool {
+        matches!(self, KnownBits::Known { zeros, .. } if *zeros != u64::MAX)
+    }
+
+    /// The known bits of `a + b` (wrapping), for any `a` and `b` with
+    /// the known bits of `self` and `other`.
+    pub fn add(&self, other: &KnownBits) -> KnownBits {
+        let ((z1, o1), (z2, o2)) = match (self, other) {
+            (
+                KnownBits::Known {
+                    zeros: z1,
+                    ones: o1,
+                },
+                KnownBits::Known {
+                    zeros: z2,
+                    ones: o2,
+                },
+            ) => ((*z1, *o1), (*z2, *o2)),
+            _ => return KnownBits::Bottom,
+        };
+
+        // The sums of the largest and the smallest possible values
+        // determine the carries which are known.
+        let max = (!z1).wrapping_add(!z2);
+        let min = o1.wrapping_add(o2);
+        let carry_zeros = !(max ^ z1 ^ z2);
+        let carry_ones = min ^ o1 ^ o2;
+        let known = (z1 | o1) & (z2 | o2) & (carry_zeros | carry_ones);
+        KnownBits::Known {
+            zeros: !max & known,
+            ones: min & known,
+        }
+    }
+
+    /// Whether some integer of width `width` has the known bits of `self`.
+    pub fn has_width(&self, width: usize) -> bool {
+        match (self, width) {
+            (KnownBits::Bottom, _) => false,
+            (_, 0 | 64..) => true,
+            (KnownBits::Known { ones, .. }, 1) => ones & !1 == 0,
+            (KnownBits::Known { zeros, ones }, w) => {
+                let high = u64::MAX << (w - 1);
+                zeros & high == 0 || ones & high == 0
+            }
+        }
+    }
+
+    /// The known bits of `a + b` (wrapping), for any integers `a` and `b`
+    /// of width `width` with the known bits of `self` and `other`.
+    pub fn add_wrapping(&self, other: &KnownBits, width: usize) -> KnownBits {
+        // The low bits of the sum don't depend on the width.
+        match (self.add(other), width) {
+            (KnownBits::Known { zeros, ones }, 1) => KnownBits::Known {
+                zeros: zeros | !1,
+                ones: ones & 1,
+            },
+            (KnownBits::Known { zeros, ones }, w @ 2..=63) => {
+                // The bits above the width extend the sign bit.
+                let high = u64::MAX << w;
+                let extend = |bits: u64| match bits & (1 << (w - 1)) {
+                    0 => bits & !high,
+                    _ => bits | high,
+                };
+                KnownBits::Known {
+                    zeros: extend(zeros),
+                    ones: extend(ones),
+                }
+            }
+            (v, _) => v,
+        }
+    }
+
+    /// The known bits of `a & b`, for any `a` and `b` with
+    /// the known bits of `self` and `other`.
+    pub fn and(&self, other: &KnownBits) -> KnownBits {
+        match (self, other) {
+            (
+                KnownBits::Known {
+                    zeros: z1,
+                    ones: o1,
+                },
+                KnownBits::Known {
+                    zeros: z2,
+                    ones: o2,
+                },
+            ) => KnownBits::Known {
+                zeros: z1 | z2,
+                ones: o1 & o2,
+            },
+            _ => KnownBits::Bottom,
+        }
+    }
+}
+
+impl LatticeJoin for KnownBits {
+    fn join(&self, other: &KnownBits) -> KnownBits {
+        match (self, other) {
+            (KnownBits::Bottom, v) | (v, KnownBits::Bottom) => *v,
+            (
+                KnownBits::Known {
+                    zeros: z1,
+                    ones: o1,
+                },
+                KnownBits::Known {
+                    zeros: z2,
+                    ones: o2,
+                },
+            ) => KnownBits::Known {
+                zeros: z1 & z2,
+                ones: o1 & o2,
+            },
+        }
+    }
+}
+
+impl LatticeWiden for KnownBits {
+    fn widen(&self, other: &KnownBits) -> KnownBits {
+        self.join(other)
+    }
+}
+
+impl LatticeMeet for KnownBits {
+    fn meet(&self, other: &KnownBits) -> KnownBits {
+        match (self, other) {
+            (
+                KnownBits::Known {
+                    zeros: z1,
+                    ones: o1,
+                },
+                KnownBits::Known {
+                    zeros: z2,
+                    ones: o2,
+                },
+            ) => {
+                let (zeros, ones) = (z1 | z2, o1 | o2);
+                match zeros & ones {
+                    0 => KnownBits::Known { zeros, ones },
+                    // Some bit is known to be both `0` and `1`.
+                    _ => KnownBits::Bottom,
+                }
+            }
+            _ => KnownBits::Bottom,
+        }
+    }
+}
+
+impl fmt::Display for KnownBits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KnownBits::Bottom => write!(f, "{}", Paint::magenta("{}").bold()),
+            KnownBits::Known { zeros, ones } => {
+                // Bits are printed from the most significant, with unknown bits as `?`
+                // (and leading bits which are the same as the next bit are elided).
+                let bits = (0..64)
+                    .rev()
+                    .map(|i| match (zeros >> i & 1, ones >> i & 1) {
+                        (1, _) => '0',
+                        (_, 1) => '1',
+                        _ => '?',
+                    })
+                    .collect::<Vec<_>>();
+                let start = (0..63).find(|i| bits[*i] != bits[i + 1]).unwrap_or(63);
+                write!(f, "0b{}", bits[start..].iter().collect::<String>())
+            }
+        }
+    }
+}
+
+/// The lattice of nullness, for references (e.g. memrefs) and integers
+/// (where zero is null).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Nullness {
+    Bottom,
+    Null,
+    NonNull,
+    Top,
+}
+
+impl LatticeJoin for Nullness {
+    fn join(&self, other: &Nullness) -> Nullness {
+        match (self, other) {
+            (Nullness::Bottom, v) | (v, Nullness::Bottom) => *v,
+            (a, b) if a == b => *a,
+            _ => Nullness::Top,
+        }
+    }
+}
+
+impl LatticeWiden for Nullness {
+    fn widen(&self, other: &Nullness) -> Nullness {
+        self.join(other)
+    }
+}
+
+impl LatticeMeet for Nullness {
+    fn meet(&self, other: &Nullness) -> Nullness {
+        match (self, other) {
+            (Nullness::Top, v) | (v, Nullness::Top) => *v,
+            (a, b) if a == b => *a,
+            _ => Nullness::Bottom,
+        }
+    }
+}
+
+impl fmt::Display for Nullness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Nullness::Bottom => "{}",
+            Nullness::Null => "null",
+            Nullness::NonNull => "nonnull",
+            Nullness::Top => "top",
+        };
+        write!(f, "{}", Paint::magenta(s).bold())
+    }
+}
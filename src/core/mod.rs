@@ -4,26 +4,66 @@
 
 mod absint;
 mod builder;
+mod combinators;
+mod concrete;
 mod diagnostics;
 mod display;
+mod dominance;
+mod instrumentation;
 #[macro_use]
 mod interfaces;
 mod ir;
+mod lattices;
+mod parser;
 mod pass_manager;
+mod pass_registry;
+mod patterns;
 mod region;
+mod reproducer;
+mod rewriter;
+mod statistics;
 
 // Public API.
 pub use self::{
-    absint::{Interpreter, LatticeJoin, LatticeSemantics, Signature},
-    builder::OperationBuilder,
+    absint::{
+        interpret_with_calls, Interpreter, InterpreterError, InterpreterFrame, InterpreterState,
+        InterproceduralInterpreter, LatticeBranchSemantics, LatticeConvert, LatticeInterpreterPass,
+        LatticeJoin, LatticeMaterialize, LatticeMeet, LatticeNarrow, LatticeSemantics,
+        LatticeWiden, Signature,
+    },
+    builder::{BlockHandle, OperationBuilder},
+    combinators::{MapLattice, Powerset, Product, ReducedProduct},
+    concrete::{
+        Buffer, ConcreteBranchSemantics, ConcreteInterpreter, ConcreteSemantics, Heap, RuntimeValue,
+    },
     diagnostics::{diagnostics_paint_disable, diagnostics_setup, LocationInfo},
+    dominance::{DominanceInfo, Loop, LoopInfo},
+    instrumentation::{
+        IRPrinting, PassFilter, PassInstrumentation, PassTiming, PassTracing, VerifyEach,
+    },
     interfaces::*,
     ir::{
-        Attribute, AttributeValue, BasicBlock, Intrinsic, Operation, SupportsInterfaceTraits, Var,
+        Attribute, AttributeValue, BasicBlock, CallLike, FoldSuccessor, Intrinsic,
+        IsolatedFromAbove, Lowering, Operation, SuccessorOperands, SupportsInterfaceTraits, Var,
+    },
+    lattices::{Interval, KnownBits, Nullness, Sign},
+    parser::{
+        parse_generic, print_generic, register_attribute, register_intrinsic, GenericAttribute,
     },
     pass_manager::{
-        AnalysisKey, AnalysisManager, AnalysisPass, OperationPass, OperationPassManager,
-        PassManager,
+        Analyses, AnalysisKey, AnalysisManager, AnalysisPass, OperationPass, OperationPassManager,
+        PassManager, PipelineEntry, PreservedAnalyses,
+    },
+    pass_registry::{
+        create_pass, get_pass_info, get_registered_passes, parse_pass_pipeline,
+        parse_pass_pipeline_into, register_pass, register_pass_manager, PassInfo, PassOptions,
+    },
+    patterns::{
+        apply_patterns_greedily, GreedyRewriteConfig, GreedyRewritePass, GreedyRewriteResult,
+        RewritePattern, RewritePatternSet,
     },
     region::{Graph, Region, SSACFG},
+    reproducer::{read_reproducer_input, read_reproducer_pipeline, CrashReproducer},
+    rewriter::{InsertionPoint, IrRewriter, RewriteListener},
+    statistics::{PassStatistics, Statistic, StatisticValues},
 };
@@ -0,0 +1,493 @@
+use crate::core::diagnostics::LocationInfo;
+use crate::core::ir::{
+    Attribute, AttributeValue, BasicBlock, Intrinsic, Operation, SupportsInterfaceTraits, Var,
+};
+use crate::core::region::{Graph, Region, SSACFG};
+use color_eyre::{
+    eyre::{bail, eyre, WrapErr},
+    Report,
+};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, RwLock};
+use yansi::Paint;
+
+/// Attributes which can be written in the generic textual form of the IR
+/// (see [`print_generic`]), and read back by [`parse_generic`] once
+/// they are registered with [`register_attribute`].
+pub trait GenericAttribute: Sized {
+    /// The text of the attribute, which [`GenericAttribute::parse_generic`] reads back.
+    fn print_generic(&self) -> String;
+
+    fn parse_generic(src: &str) -> Result<Self, Report>;
+}
+
+type AttributePrinter = Arc<dyn Fn(&dyn Attribute) -> Option<String> + Send + Sync>;
+type AttributeParser = Arc<dyn Fn(&str) -> Result<Box<dyn Attribute>, Report> + Send + Sync>;
+
+#[derive(Default)]
+struct IrRegistry {
+    intrinsics: HashMap<String, Box<dyn Intrinsic>>,
+    attributes: Vec<(String, AttributePrinter, AttributeParser)>,
+}
+
+lazy_static! {
+    static ref IR_REGISTRY: RwLock<IrRegistry> = RwLock::default();
+}
+
+/// Register an intrinsic by its unique id (e.g. `arith.addi`),
+/// so that its operations can be read by [`parse_generic`].
+pub fn register_intrinsic(intr: Box<dyn Intrinsic>) {
+    let mut registry = IR_REGISTRY.write().unwrap();
+    registry.intrinsics.insert(intr.get_unique_id(), intr);
+}
+
+/// Register an attribute type under `tag` (e.g. `builtin.value`), so that
+/// its attributes can be written by [`print_generic`] and read by [`parse_generic`].
+pub fn register_attribute<T>(tag: &str)
+where
+    T: 'static + Attribute + GenericAttribute,
+{
+    let printer: AttributePrinter = Arc::new(|attr| {
+        attr.query_ref::<dyn AttributeValue<T>>()
+            .map(|v| v.get_value().print_generic())
+    });
+    let parser: AttributeParser =
+        Arc::new(|src| Ok(Box::new(T::parse_generic(src)?) as Box<dyn Attribute>));
+    let mut registry = IR_REGISTRY.write().unwrap();
+    registry.attributes.retain(|(t, _, _)| t != tag);
+    registry.attributes.push((tag.to_string(), printer, parser));
+}
+
+/// Write an operation in the generic textual form, which [`parse_generic`] reads back:
+///
+/// ```text
+/// "builtin.func"(%0) {"builtin.symbol" = builtin.symbol "public foo"} loc(unknown) (
+///   directed 2 {
+///     ^0(%0):
+///       %1 = "base.return"(%0) loc("foo.jl":3:1)
+///   }
+/// )
+/// ```
+///
+/// Every region records its number of `Var` instances, and the
+/// `Var` instances of the operation are kept. Fails if an intrinsic
+/// or attribute of the operation is not registered.
+pub fn print_generic(op: &Operation) -> Result<String, Report> {
+    let registry = IR_REGISTRY.read().unwrap();
+    let mut out = String::new();
+    print_op(&registry, op, 0, &mut out)?;
+    Ok(out)
+}
+
+// Strings are written (and read) with Rust escapes.
+fn quote(s: &str) -> String {
+    format!("{:?}", s)
+}
+
+fn join<T: ToString>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn print_location(loc: &LocationInfo) -> String {
+    match loc {
+        LocationInfo::Unknown => "unknown".to_string(),
+        LocationInfo::FileLineCol(file, line, col) => {
+            format!("{}:{}:{}", quote(file), line, col)
+        }
+        LocationInfo::NameFileLineCol(name, file, line, col) => {
+            format!("{} at {}:{}:{}", quote(name), quote(file), line, col)
+        }
+        LocationInfo::InlinedFrom(locs) => {
+            let locs = locs.iter().map(print_location).collect::<Vec<_>>();
+            format!("inlined [{}]", locs.join(", "))
+        }
+    }
+}
+
+fn print_op(
+    registry: &IrRegistry,
+    op: &Operation,
+    indent: usize,
+    out: &mut String,
+) -> Result<(), Report> {
+    let intr = op.get_intrinsic();
+    let id = intr.get_unique_id();
+    if !registry.intrinsics.contains_key(&id) {
+        bail!(format!("{} is not a registered intrinsic.", intr))
+    }
+    write!(out, "{}({})", quote(&id), join(&op.get_operands()))?;
+    if !op.get_successors().is_empty() {
+        write!(out, " [{}]", join(op.get_successors()))?;
+    }
+    if !op.get_attributes().is_empty() {
+        let mut attrs = op.get_attributes().iter().collect::<Vec<_>>();
+        attrs.sort_by(|a, b| a.0.cmp(b.0));
+        let mut printed = Vec::new();
+        for (key, attr) in attrs {
+            let text = registry
+                .attributes
+                .iter()
+                .find_map(|(tag, printer, _)| printer(attr.as_ref()).map(|t| (tag, t)));
+            match text {
+                None => bail!(format!(
+                    "The attribute {} of {} is not a registered attribute.",
+                    Paint::magenta(key),
+                    intr
+                )),
+                Some((tag, text)) => {
+                    printed.push(format!("{} = {} {}", quote(key), tag, quote(&text)))
+                }
+            }
+        }
+        write!(out, " {{{}}}", printed.join(", "))?;
+    }
+    write!(out, " loc({})", print_location(op.get_location()))?;
+    if op.get_regions().is_empty() {
+        return Ok(());
+    }
+    let pad = "  ".repeat(indent);
+    out.push_str(" (\n");
+    for (ind, r) in op.get_regions().iter().enumerate() {
+        let kind = match r {
+            Region::Directed(_) => "directed",
+            Region::Undirected(_) => "undirected",
+        };
+        writeln!(out, "{}  {} {} {{", pad, kind, r.len())?;
+        for b in 0..r.get_blocks().len() {
+            let args = r.get_block(b).get_operands();
+            writeln!(out, "{}    ^{}({}):", pad, b, join(args))?;
+            for (v, child) in r.get_block_iter(b) {
+                write!(out, "{}      {} = ", pad, v)?;
+                print_op(registry, child, indent + 3, out)?;
+                out.push('\n');
+            }
+        }
+        match ind + 1 == op.get_regions().len() {
+            true => writeln!(out, "{}  }}", pad)?,
+            false => writeln!(out, "{}  }},", pad)?,
+        };
+    }
+    write!(out, "{})", pad)?;
+    Ok(())
+}
+
+/// Read an operation from the generic textual form written by [`print_generic`].
+/// Line comments (starting with `//`) are skipped.
+///
+/// The intrinsics and attributes of the operation must be registered
+/// (e.g. with [`crate::dialects::builtin::register_builtin_dialect`]),
+/// and every operation is verified as it is read.
+pub fn parse_generic(src: &str) -> Result<Operation, Report> {
+    let registry = IR_REGISTRY.read().unwrap();
+    let mut parser = IrParser {
+        src,
+        pos: 0,
+        registry: &registry,
+    };
+    let op = parser.operation()?;
+    parser.skip_whitespace();
+    if parser.pos != src.len() {
+        return Err(parser.error("Unexpected trailing input"));
+    }
+    Ok(op)
+}
+
+// A recursive descent parser for the generic form:
+//
+//   operation := string `(` vars `)` (`[` integers `]`)? (`{` attributes `}`)?
+//                `loc` `(` location `)` (`(` regions `)`)?
+//   attribute := string `=` tag string
+//   location  := `unknown` | string (`at` string)? `:` integer `:` integer
+//              | `inlined` `[` locations `]`
+//   region    := (`directed` | `undirected`) integer `{` block* `}`
+//   block     := `^` integer `(` vars `)` `:` (var `=` operation)*
+//
+// Lists are separated by commas.
+struct IrParser<'a> {
+    src: &'a str,
+    pos: usize,
+    registry: &'a IrRegistry,
+}
+
+impl<'a> IrParser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = &self.src[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if !trimmed.starts_with("//") {
+                return;
+            }
+            self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    fn position(&self) -> String {
+        let before = &self.src[..self.pos];
+        let line = before.matches('\n').count() + 1;
+        let col = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        format!("line {}, column {}", line, col)
+    }
+
+    fn error(&self, msg: &str) -> Report {
+        eyre!(format!("{} at {}.", msg, self.position()))
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(d) if d == c => {
+                self.pos += c.len_utf8();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Report> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(self.error(&format!("Expected `{}`", c))),
+        }
+    }
+
+    fn take_while<F>(&mut self, f: F) -> &'a str
+    where
+        F: Fn(char) -> bool,
+    {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !f(c) {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn identifier(&mut self) -> &'a str {
+        self.skip_whitespace();
+        self.take_while(|c| c.is_alphanumeric() || "-_.".contains(c))
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        let pos = self.pos;
+        match self.identifier() == word {
+            true => true,
+            false => {
+                self.pos = pos;
+                false
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Result<usize, Report> {
+        self.skip_whitespace();
+        match self.take_while(|c| c.is_ascii_digit()).parse::<usize>() {
+            Ok(n) => Ok(n),
+            Err(_) => Err(self.error("Expected an integer")),
+        }
+    }
+
+    fn var(&mut self) -> Result<Var, Report> {
+        self.expect('%')?;
+        Ok(Var::new(self.integer()?))
+    }
+
+    fn string(&mut self) -> Result<String, Report> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            let c = match self.peek() {
+                None => return Err(self.error("Unterminated string")),
+                Some(c) => c,
+            };
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let e = match self.peek() {
+                        None => return Err(self.error("Unterminated string")),
+                        Some(e) => e,
+                    };
+                    self.pos += e.len_utf8();
+                    match e {
+                        'n' => s.push('\n'),
+                        'r' => s.push('\r'),
+                        't' => s.push('\t'),
+                        '0' => s.push('\0'),
+                        '\\' | '"' | '\'' => s.push(e),
+                        'u' => {
+                            self.expect('{')?;
+                            let hex = self.take_while(|c| c.is_ascii_hexdigit());
+                            match u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+                                None => return Err(self.error("Invalid unicode escape")),
+                                Some(u) => s.push(u),
+                            }
+                            self.expect('}')?;
+                        }
+                        _ => return Err(self.error(&format!("Invalid escape `\\{}`", e))),
+                    }
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn list<T, F>(&mut self, close: char, mut f: F) -> Result<Vec<T>, Report>
+    where
+        F: FnMut(&mut Self) -> Result<T, Report>,
+    {
+        let mut items = Vec::new();
+        if self.eat(close) {
+            return Ok(items);
+        }
+        loop {
+            items.push(f(self)?);
+            if !self.eat(',') {
+                self.expect(close)?;
+                return Ok(items);
+            }
+        }
+    }
+
+    fn attribute(&mut self) -> Result<(String, Box<dyn Attribute>), Report> {
+        let key = self.string()?;
+        self.expect('=')?;
+        self.skip_whitespace();
+        let at = self.position();
+        let tag = self.identifier();
+        let text = self.string()?;
+        let parser = self
+            .registry
+            .attributes
+            .iter()
+            .find(|(t, _, _)| t == tag)
+            .map(|(_, _, parser)| parser.clone());
+        match parser {
+            None => Err(self.error(&format!("Unknown attribute `{}`", tag))),
+            Some(parser) => {
+                let attr = parser(&text)
+                    .wrap_err_with(|| format!("Invalid attribute `{}` at {}.", tag, at))?;
+                Ok((key, attr))
+            }
+        }
+    }
+
+    fn location(&mut self) -> Result<LocationInfo, Report> {
+        if self.keyword("unknown") {
+            return Ok(LocationInfo::Unknown);
+        }
+        if self.keyword("inlined") {
+            self.expect('[')?;
+            return Ok(LocationInfo::InlinedFrom(self.list(']', Self::location)?));
+        }
+        let s = self.string()?;
+        let file = match self.keyword("at") {
+            true => Some(self.string()?),
+            false => None,
+        };
+        self.expect(':')?;
+        let line = self.integer()?;
+        self.expect(':')?;
+        let col = self.integer()?;
+        Ok(match file {
+            None => LocationInfo::FileLineCol(s, line, col),
+            Some(file) => LocationInfo::NameFileLineCol(s, file, line, col),
+        })
+    }
+
+    // Check that `v` is a `Var` of the region which isn't defined yet.
+    fn check_undefined(&self, r: &Region, v: Var) -> Result<(), Report> {
+        if v.get_id() >= r.len() {
+            return Err(self.error(&format!("{} is out of range for the region", v)));
+        }
+        if r.get_op(v).is_some() || r.get_arg_block(v).is_some() {
+            return Err(self.error(&format!("{} is defined twice", v)));
+        }
+        Ok(())
+    }
+
+    fn region(&mut self) -> Result<Region, Report> {
+        let mut r = match self.identifier() {
+            "directed" => Region::Directed(SSACFG::default()),
+            "undirected" => Region::Undirected(Graph::default()),
+            _ => return Err(self.error("Expected `directed` or `undirected`")),
+        };
+        r.reserve(self.integer()?);
+        self.expect('{')?;
+        while !self.eat('}') {
+            self.expect('^')?;
+            let b = r.get_blocks().len();
+            if self.integer()? != b {
+                return Err(self.error(&format!("Expected block {}", b)));
+            }
+            let at = self.position();
+            r.push_block(BasicBlock::default())
+                .wrap_err_with(|| format!("Invalid block at {}.", at))?;
+            self.expect('(')?;
+            for v in self.list(')', Self::var)? {
+                self.check_undefined(&r, v)?;
+                let at = self.position();
+                r.place_arg(v, b)
+                    .wrap_err_with(|| format!("Invalid block argument at {}.", at))?;
+            }
+            self.expect(':')?;
+            self.skip_whitespace();
+            while self.peek() == Some('%') {
+                let v = self.var()?;
+                self.check_undefined(&r, v)?;
+                self.expect('=')?;
+                let op = self.operation()?;
+                let idx = r.get_block(b).get_ops().len();
+                r.place_op(v, b, idx, op);
+                self.skip_whitespace();
+            }
+        }
+        Ok(r)
+    }
+
+    fn operation(&mut self) -> Result<Operation, Report> {
+        self.skip_whitespace();
+        let at = self.position();
+        let id = self.string()?;
+        let intr = match self.registry.intrinsics.get(&id) {
+            None => bail!(format!("Unknown intrinsic `{}` at {}.", id, at)),
+            Some(intr) => intr.clone(),
+        };
+        self.expect('(')?;
+        let operands = self.list(')', Self::var)?;
+        let successors = match self.eat('[') {
+            true => self.list(']', Self::integer)?,
+            false => Vec::new(),
+        };
+        let mut attributes = HashMap::new();
+        if self.eat('{') {
+            attributes.extend(self.list('}', Self::attribute)?);
+        }
+        if !self.keyword("loc") {
+            return Err(self.error("Expected `loc`"));
+        }
+        self.expect('(')?;
+        let location = self.location()?;
+        self.expect(')')?;
+        let regions = match self.eat('(') {
+            true => self.list(')', Self::region)?,
+            false => Vec::new(),
+        };
+        let op = Operation::new(location, intr, operands, attributes, regions, successors);
+        let intr = op.get_intrinsic();
+        intr.verify(intr, &op)
+            .wrap_err_with(|| format!("Invalid {} operation at {}.", id, at))?;
+        Ok(op)
+    }
+}
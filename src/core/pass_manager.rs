@@ -1,8 +1,23 @@
+use crate::core::instrumentation::PassInstrumentation;
 use crate::core::interfaces::*;
-use crate::core::ir::{Intrinsic, Operation, SupportsInterfaceTraits};
-use color_eyre::{eyre::bail, Report};
+use crate::core::ir::{Intrinsic, IsolatedFromAbove, Operation, SupportsInterfaceTraits};
+use crate::core::pass_registry::PassOptions;
+use crate::core::reproducer::CrashReproducer;
+use crate::core::statistics::Statistic;
+use color_eyre::{
+    eyre::{bail, eyre, WrapErr},
+    Report,
+};
 use downcast_rs::{impl_downcast, Downcast};
-use std::{collections::HashMap, hash::Hash, sync::RwLock};
+use std::{
+    any::TypeId,
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex, RwLock},
+};
+use yansi::Paint;
 
 /// A trait object which supports caching of `AnalysisPass` instances
 /// by the `AnalysisManager`. Following [LLVM's pass
@@ -10,6 +25,9 @@ use std::{collections::HashMap, hash::Hash, sync::RwLock};
 /// hashable piece of structured data _about_ an [`Operation`].
 ///
 /// Provides a `to_pass` method which allows conversion/creation of an `AnalysisPass` (which further supports application to the IR).
+///
+/// Keys should register `dyn ObjectEq` and `dyn ObjectHash` with `interfaces!`,
+/// otherwise keys are compared by address (and equal keys don't share cached results).
 pub trait AnalysisKey: Downcast + Object {
     /// Convert an [`AnalysisKey`] to an applicative [`AnalysisPass`] which can be applied
     /// to an immutable reference to an [`Operation`] instance.
@@ -24,6 +42,49 @@ pub trait AnalysisPass: Downcast + Object {
 mopo!(dyn AnalysisPass);
 impl_downcast!(AnalysisPass);
 
+/// The analyses which are still valid after an [`OperationPass`]
+/// is applied. Analyses are identified by the type of their [`AnalysisKey`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PreservedAnalyses {
+    all: bool,
+    preserved: HashSet<TypeId>,
+}
+
+impl PreservedAnalyses {
+    /// No analyses are preserved (the default for passes which mutate the IR).
+    pub fn none() -> PreservedAnalyses {
+        PreservedAnalyses::default()
+    }
+
+    /// All analyses are preserved (e.g. for passes which don't mutate the IR).
+    pub fn all() -> PreservedAnalyses {
+        PreservedAnalyses {
+            all: true,
+            preserved: HashSet::new(),
+        }
+    }
+
+    /// Preserve analyses with keys of type `K`.
+    pub fn preserve<K>(mut self) -> PreservedAnalyses
+    where
+        K: 'static + AnalysisKey,
+    {
+        self.preserved.insert(TypeId::of::<K>());
+        self
+    }
+
+    pub fn is_all(&self) -> bool {
+        self.all
+    }
+
+    pub fn is_preserved(&self, key: &dyn AnalysisKey) -> bool {
+        self.all || self.preserved.contains(&key.as_any().type_id())
+    }
+}
+
+/// The cached analyses of an operation.
+pub type Analyses = HashMap<Box<dyn AnalysisKey>, Box<dyn AnalysisPass>>;
+
 /// `AnalysisManager` is a type which manages
 /// static analyses of operations, often required
 /// for `OperationPass` application.
@@ -32,53 +93,165 @@ impl_downcast!(AnalysisPass);
 /// passes owned by a `PassManager`.
 ///
 /// During `apply` calls, all operations passes are provided
-/// with a `Sender` channel (to place requests for analyses),
-/// as well as a read-write locked `AnalysisManager`,
-/// which the pass can use to ask for the result
+/// with a read-write locked `AnalysisManager`,
+/// which the pass can use to ask for the result.
+///
+/// Results are cached per operation (by [`Operation::get_id`]) and `AnalysisKey`.
+/// After each pass, the pass manager invalidates the cached results
+/// which the pass doesn't preserve (see [`OperationPass::preserved_analyses`]).
 pub struct AnalysisManager {
-    cached: HashMap<Box<dyn AnalysisKey>, Box<dyn AnalysisPass>>,
+    cached: HashMap<usize, Analyses>,
+    instrumentations: Vec<Arc<dyn PassInstrumentation>>,
+}
+
+impl Default for AnalysisManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AnalysisManager {
     pub fn new() -> AnalysisManager {
         AnalysisManager {
             cached: HashMap::new(),
+            instrumentations: Vec::new(),
         }
     }
 
-    pub fn get_cached(&self) -> &HashMap<Box<dyn AnalysisKey>, Box<dyn AnalysisPass>> {
+    /// Create an `AnalysisManager` which calls the analysis hooks of `instrumentations`.
+    pub fn with_instrumentations(
+        instrumentations: Vec<Arc<dyn PassInstrumentation>>,
+    ) -> AnalysisManager {
+        AnalysisManager {
+            cached: HashMap::new(),
+            instrumentations,
+        }
+    }
+
+    pub fn get_cached(&self) -> &HashMap<usize, Analyses> {
         &self.cached
     }
 
+    /// Run the analysis for `key` on `op`, and cache the result
+    /// (replacing any cached result).
     pub fn analyze<T>(&mut self, key: T, op: &Operation) -> Result<(), Report>
     where
         T: 'static + Eq + Hash + AnalysisKey,
     {
+        for instr in self.instrumentations.iter() {
+            instr.run_before_analysis(&key, op);
+        }
         let mut pass = key.to_pass(op);
-        pass.apply(op)?;
-        self.cached.insert(Box::new(key), pass);
+        let res = pass.apply(op);
+        for instr in self.instrumentations.iter() {
+            instr.run_after_analysis(&key, op);
+        }
+        res?;
+        self.cached
+            .entry(op.get_id())
+            .or_default()
+            .insert(Box::new(key), pass);
         Ok(())
     }
 
-    pub fn ask(&self, key: Box<dyn AnalysisKey>) -> Option<&Box<dyn AnalysisPass>> {
-        if !self.cached.contains_key(&key) {
-            return None;
+    /// Cache a result for `key` on `op` which was computed outside
+    /// of the analysis manager (replacing any cached result).
+    pub fn insert<T>(&mut self, key: T, op: &Operation, pass: Box<dyn AnalysisPass>)
+    where
+        T: 'static + Eq + Hash + AnalysisKey,
+    {
+        self.cached
+            .entry(op.get_id())
+            .or_default()
+            .insert(Box::new(key), pass);
+    }
+
+    /// Get the cached result for `key` on `op`, if there is one.
+    #[allow(clippy::borrowed_box)]
+    pub fn ask(&self, key: &dyn AnalysisKey, op: &Operation) -> Option<&Box<dyn AnalysisPass>> {
+        self.cached.get(&op.get_id())?.get(key)
+    }
+
+    /// Get the cached result for `key` on `op`, downcast to its
+    /// concrete type `P`.
+    pub fn get_cached_analysis<P>(&self, key: &dyn AnalysisKey, op: &Operation) -> Option<&P>
+    where
+        P: AnalysisPass,
+    {
+        self.ask(key, op)?.downcast_ref::<P>()
+    }
+
+    /// Get the result for `key` on `op` (running the analysis if there is no
+    /// cached result), downcast to its concrete type `P`.
+    pub fn get_analysis<T, P>(&mut self, key: T, op: &Operation) -> Result<&P, Report>
+    where
+        T: 'static + Clone + Eq + Hash + AnalysisKey,
+        P: AnalysisPass,
+    {
+        if self.ask(&key, op).is_none() {
+            self.analyze(key.clone(), op)?;
+        }
+        match self.get_cached_analysis::<P>(&key, op) {
+            Some(p) => Ok(p),
+            None => bail!(format!(
+                "The analysis of {} is not a {}.",
+                op.get_intrinsic(),
+                Paint::magenta(std::any::type_name::<P>())
+            )),
+        }
+    }
+
+    /// Invalidate the cached results (for every operation)
+    /// which are not preserved.
+    pub fn invalidate(&mut self, preserved: &PreservedAnalyses) {
+        if preserved.is_all() {
+            return;
+        }
+        for analyses in self.cached.values_mut() {
+            analyses.retain(|k, _| preserved.is_preserved(k.as_ref()));
         }
-        return Some(self.cached.get(&key).unwrap());
+        self.cached.retain(|_, analyses| !analyses.is_empty());
+    }
+
+    /// Invalidate all cached results for `op`.
+    pub fn invalidate_op(&mut self, op: &Operation) {
+        self.cached.remove(&op.get_id());
+    }
+
+    pub fn clear(&mut self) {
+        self.cached.clear();
     }
 }
 
 pub trait PassManager
 where
-    Self: std::fmt::Display,
+    Self: std::fmt::Display + Send,
 {
     /// Check if the pass manager can apply passes to operations
     /// of a specific intrinsic type.
     fn check(&self, op: &Operation) -> bool;
 
+    /// Create a fresh copy of the pass manager (and its nested
+    /// pass managers), with passes created by [`OperationPass::reset`].
+    fn reset(&self) -> Box<dyn PassManager>;
+
+    /// Add an instrumentation to the pass manager and its nested pass managers.
+    fn add_instrumentation(&mut self, instr: Arc<dyn PassInstrumentation>);
+
+    fn add_pass(&mut self, pass: Box<dyn OperationPass>) -> Result<(), Report>;
+
+    fn add_manager(&mut self, mgr: Box<dyn PassManager>) -> Result<(), Report>;
+
     /// See the toplevel `Operation` first, and then
     /// moves downwards towards the leaves.
-    fn prewalk(self, op: Operation) -> Result<Operation, Report>;
+    ///
+    /// The passes and nested pass managers are run in the order they were added:
+    /// a pass is applied to `op`, and a nested pass manager is applied (by its own `prewalk`)
+    /// to every operation directly nested in the regions of `op` which it `check`s.
+    /// Consecutive nested pass managers are applied together, operation by operation.
+    /// Nested operations which are [`IsolatedFromAbove`] may be walked concurrently
+    /// by a fixed set of worker threads, each using its own (`reset`) pass managers.
+    fn prewalk(&mut self, op: Operation) -> Result<Operation, Report>;
 }
 
 pub trait OperationPass: Send + Sync + std::fmt::Debug {
@@ -88,6 +261,38 @@ pub trait OperationPass: Send + Sync + std::fmt::Debug {
 
     fn reset(&self) -> Box<dyn OperationPass>;
 
+    /// The name of the pass used in diagnostics and instrumentation,
+    /// by default the name of the implementing type.
+    fn get_name(&self) -> String {
+        let name = std::any::type_name::<Self>();
+        let name = name.split('<').next().unwrap_or(name);
+        name.rsplit("::").next().unwrap_or(name).to_string()
+    }
+
+    /// The argument which names the pass in textual pipelines
+    /// (see [`crate::core::register_pass`]), by default the name of the pass.
+    fn get_argument(&self) -> String {
+        self.get_name()
+    }
+
+    /// The options of the pass in textual pipelines. Options with default
+    /// values can be omitted, unless they were set explicitly - so that
+    /// a printed pipeline parses back to the same text.
+    fn get_options(&self) -> PassOptions {
+        PassOptions::new()
+    }
+
+    /// The analyses which remain valid after the pass is applied,
+    /// all others are invalidated by the pass manager.
+    fn preserved_analyses(&self) -> PreservedAnalyses {
+        PreservedAnalyses::none()
+    }
+
+    /// The counters declared by the pass (see [`crate::core::PassStatistics`]).
+    fn get_statistics(&self) -> Vec<&Statistic> {
+        Vec::new()
+    }
+
     /// Check if the `OperationPass` can be applied to this `Operation`.
     fn check(&self, op: &RwLock<Operation>) -> Result<(), Report>;
 
@@ -104,14 +309,32 @@ pub trait OperationPass: Send + Sync + std::fmt::Debug {
     ) -> Result<(), Report>;
 }
 
+/// An element of the pipeline of an [`OperationPassManager`].
+pub enum PipelineEntry {
+    /// A pass which is applied to the operation itself.
+    Pass(Box<dyn OperationPass>),
+    /// A pass manager which is applied to the operations nested in its regions.
+    Nested(Box<dyn PassManager>),
+}
+
+impl PipelineEntry {
+    fn reset(&self) -> PipelineEntry {
+        match self {
+            PipelineEntry::Pass(p) => PipelineEntry::Pass(p.reset()),
+            PipelineEntry::Nested(m) => PipelineEntry::Nested(m.reset()),
+        }
+    }
+}
+
 pub struct OperationPassManager<T>
 where
     T: Intrinsic,
 {
     intrinsic_tag: T,
-    passes: Vec<Box<dyn OperationPass>>,
-    managers: Vec<Box<dyn PassManager>>,
-    analysis_manager: Option<AnalysisManager>,
+    entries: Vec<PipelineEntry>,
+    instrumentations: Vec<Arc<dyn PassInstrumentation>>,
+    reproducer: Option<Arc<CrashReproducer>>,
+    num_threads: usize,
 }
 
 impl<T> OperationPassManager<T>
@@ -119,39 +342,244 @@ where
     T: Intrinsic,
 {
     pub fn new(intr: T) -> OperationPassManager<T> {
-        let analysis_manager = AnalysisManager::new();
+        let num_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
         OperationPassManager {
             intrinsic_tag: intr,
-            passes: Vec::new(),
-            managers: Vec::new(),
-            analysis_manager: Some(analysis_manager),
+            entries: Vec::new(),
+            instrumentations: Vec::new(),
+            reproducer: None,
+            num_threads,
         }
     }
 
     pub fn get_intrinsic(&self) -> &T {
         &self.intrinsic_tag
     }
+
+    pub fn get_num_threads(&self) -> usize {
+        self.num_threads
+    }
+
+    /// Set the number of threads used to walk nested [`IsolatedFromAbove`]
+    /// operations (by default, the available parallelism of the machine).
+    /// With a single thread, nested operations are walked sequentially.
+    ///
+    /// The threads are shared by the whole walk: operations nested
+    /// below an operation which is walked by a worker thread are walked
+    /// sequentially on that thread.
+    pub fn set_num_threads(&mut self, n: usize) {
+        self.num_threads = n.max(1);
+    }
+}
+
+fn nested_managers(entries: &[PipelineEntry]) -> impl Iterator<Item = &Box<dyn PassManager>> {
+    entries.iter().filter_map(|e| match e {
+        PipelineEntry::Nested(m) => Some(m),
+        PipelineEntry::Pass(_) => None,
+    })
+}
+
+fn walk_nested(managers: &mut [PipelineEntry], mut child: Operation) -> Result<Operation, Report> {
+    for entry in managers.iter_mut() {
+        if let PipelineEntry::Nested(mgr) = entry {
+            if mgr.check(&child) {
+                child = mgr.prewalk(child)?;
+            }
+        }
+    }
+    Ok(child)
+}
+
+thread_local! {
+    // Set on the worker threads of `walk_block`, operations nested
+    // below an operation walked by a worker are walked sequentially.
+    static IN_WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+// Walk the operations of a block with a run of consecutive nested pass managers.
+// Operations which are `IsolatedFromAbove` are walked by a fixed set of (at most)
+// `num_threads` workers, which take operations from a shared queue.
+fn walk_block(
+    managers: &mut [PipelineEntry],
+    ops: Vec<Operation>,
+    num_threads: usize,
+) -> Result<Vec<Operation>, Report> {
+    let isolated = ops
+        .iter()
+        .map(|op| {
+            op.get_intrinsic()
+                .query_ref::<dyn IsolatedFromAbove>()
+                .is_some()
+                && nested_managers(managers).any(|m| m.check(op))
+        })
+        .collect::<Vec<_>>();
+    let count = isolated.iter().filter(|i| **i).count();
+    if num_threads <= 1 || count <= 1 || IN_WORKER.with(|w| w.get()) {
+        return ops
+            .into_iter()
+            .map(|op| walk_nested(managers, op))
+            .collect();
+    }
+
+    let mut walked: Vec<Option<Result<Operation, Report>>> = Vec::with_capacity(ops.len());
+    let mut jobs = Vec::with_capacity(count);
+    for (ind, op) in ops.into_iter().enumerate() {
+        match isolated[ind] {
+            true => {
+                jobs.push((ind, op));
+                walked.push(None);
+            }
+            false => walked.push(Some(walk_nested(managers, op))),
+        }
+    }
+    let queue = Mutex::new(jobs.into_iter());
+    let results = std::thread::scope(|s| {
+        let handles = (0..num_threads.min(count))
+            .map(|_| {
+                let mut local = managers.iter().map(|m| m.reset()).collect::<Vec<_>>();
+                let queue = &queue;
+                s.spawn(move || {
+                    IN_WORKER.with(|w| w.set(true));
+                    let mut results = Vec::new();
+                    loop {
+                        let job = queue.lock().unwrap().next();
+                        match job {
+                            None => return results,
+                            Some((ind, op)) => results.push((ind, walk_nested(&mut local, op))),
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect::<Vec<_>>()
+    });
+    for (ind, res) in results.into_iter() {
+        walked[ind] = Some(res);
+    }
+    walked.into_iter().map(|res| res.unwrap()).collect()
 }
 
 impl<T> PassManager for OperationPassManager<T>
 where
-    T: Intrinsic,
+    T: Intrinsic + Clone,
 {
     fn check(&self, op: &Operation) -> bool {
         op.get_intrinsic().is::<T>()
     }
 
-    fn prewalk(mut self, op: Operation) -> Result<Operation, Report> {
+    fn reset(&self) -> Box<dyn PassManager> {
+        Box::new(OperationPassManager {
+            intrinsic_tag: self.intrinsic_tag.clone(),
+            entries: self.entries.iter().map(|e| e.reset()).collect(),
+            instrumentations: self.instrumentations.to_vec(),
+            reproducer: self.reproducer.clone(),
+            num_threads: self.num_threads,
+        })
+    }
+
+    fn add_instrumentation(&mut self, instr: Arc<dyn PassInstrumentation>) {
+        for entry in self.entries.iter_mut() {
+            if let PipelineEntry::Nested(mgr) = entry {
+                mgr.add_instrumentation(instr.clone());
+            }
+        }
+        self.instrumentations.push(instr);
+    }
+
+    fn add_pass(&mut self, pass: Box<dyn OperationPass>) -> Result<(), Report> {
+        self.push(pass)
+    }
+
+    fn add_manager(&mut self, mgr: Box<dyn PassManager>) -> Result<(), Report> {
+        self.nest(mgr)
+    }
+
+    fn prewalk(&mut self, op: Operation) -> Result<Operation, Report> {
         if !self.check(&op) {
             bail!("Operation intrinsic type is not the same as pass manager.".to_string())
         }
-        let analysis_manager = self.analysis_manager.take().unwrap();
+        let reproducer = match &self.reproducer {
+            None => return self.walk(op),
+            Some(r) => r.clone(),
+        };
+        reproducer.set_input(&format!("{}", self), &op);
+        self.walk(op)
+            .inspect_err(|err| reproducer.write_error(err))
+            .wrap_err_with(|| match reproducer.is_written() {
+                true => format!(
+                    "Crash reproducer written to {}",
+                    Paint::magenta(reproducer.get_path().display())
+                ),
+                false => "Failed to write crash reproducer".to_string(),
+            })
+    }
+}
+
+impl<T> OperationPassManager<T>
+where
+    T: Intrinsic + Clone,
+{
+    /// Write a reproducer file to `path` when a pass fails or panics
+    /// (see [`CrashReproducer`]). With `local`, the reproducer is narrowed to the
+    /// failing pass and the isolated operation it was applied to.
+    pub fn enable_crash_reproducer(&mut self, path: impl AsRef<std::path::Path>, local: bool) {
+        let reproducer = Arc::new(CrashReproducer::new(path, local));
+        self.add_instrumentation(reproducer.clone());
+        self.reproducer = Some(reproducer);
+    }
+
+    // Run the pipeline in order. Each run of consecutive nested pass managers
+    // is applied together, in a single walk over the nested operations.
+    fn walk(&mut self, op: Operation) -> Result<Operation, Report> {
+        let intr = op.get_intrinsic().clone();
+        let loc = op.get_location().clone();
+        let analysis_manager =
+            AnalysisManager::with_instrumentations(self.instrumentations.to_vec());
         let analysis_lock = RwLock::new(analysis_manager);
-        let op_lock = RwLock::new(op);
-        for pass in self.get_passes().iter() {
-            pass.apply(&op_lock, &analysis_lock)?;
+        let mut op = op;
+        let mut start = 0;
+        while start < self.entries.len() {
+            if let PipelineEntry::Pass(pass) = &self.entries[start] {
+                let pass = pass.as_ref();
+                let op_lock = RwLock::new(op);
+                let res = self.run_pass(pass, &op_lock, &analysis_lock);
+                res.wrap_err_with(|| {
+                    format!(
+                        "{} failed on {}{}",
+                        Paint::magenta(pass.get_name()),
+                        intr,
+                        loc
+                    )
+                })?;
+                analysis_lock
+                    .write()
+                    .unwrap()
+                    .invalidate(&pass.preserved_analyses());
+                op = op_lock.into_inner().unwrap();
+                start += 1;
+                continue;
+            }
+            let end = self.entries[start..]
+                .iter()
+                .position(|e| matches!(e, PipelineEntry::Pass(_)))
+                .map_or(self.entries.len(), |n| start + n);
+            let num_threads = self.num_threads;
+            for r in op.get_regions_mut().iter_mut() {
+                for b in 0..r.get_blocks().len() {
+                    let ops = std::mem::take(r.get_block_mut(b).get_ops_mut());
+                    let walked = walk_block(&mut self.entries[start..end], ops, num_threads)?;
+                    *r.get_block_mut(b).get_ops_mut() = walked;
+                }
+            }
+
+            // The nested pass managers may have changed the operation.
+            analysis_lock.write().unwrap().invalidate_op(&op);
+            start = end;
         }
-        Ok(op_lock.into_inner().unwrap())
+        Ok(op)
     }
 }
 
@@ -159,32 +587,116 @@ impl<T> OperationPassManager<T>
 where
     T: Intrinsic,
 {
-    pub fn get_managers(&self) -> &[Box<dyn PassManager>] {
-        &self.managers
+    /// The passes and nested pass managers, in the order they are run.
+    pub fn get_entries(&self) -> &[PipelineEntry] {
+        &self.entries
+    }
+
+    pub fn get_managers(&self) -> impl Iterator<Item = &Box<dyn PassManager>> {
+        nested_managers(&self.entries)
     }
 
-    pub fn get_passes(&self) -> &[Box<dyn OperationPass>] {
-        &self.passes
+    pub fn get_passes(&self) -> impl Iterator<Item = &Box<dyn OperationPass>> {
+        self.entries.iter().filter_map(|e| match e {
+            PipelineEntry::Pass(p) => Some(p),
+            PipelineEntry::Nested(_) => None,
+        })
     }
 
-    pub fn get_passes_mut(&mut self) -> &mut Vec<Box<dyn OperationPass>> {
-        &mut self.passes
+    pub fn get_passes_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn OperationPass>> {
+        self.entries.iter_mut().filter_map(|e| match e {
+            PipelineEntry::Pass(p) => Some(p),
+            PipelineEntry::Nested(_) => None,
+        })
     }
 
     pub fn push(&mut self, pass: Box<dyn OperationPass>) -> Result<(), Report> {
         let intr = pass.target_intrinsic();
         match intr {
-            None => self.passes.push(pass),
+            None => self.entries.push(PipelineEntry::Pass(pass)),
             Some(v) => match v.is::<T>() {
                 false => bail!("Operation pass must operate on same intrinsic as pass manager."),
-                true => self.passes.push(pass),
+                true => self.entries.push(PipelineEntry::Pass(pass)),
             },
         };
         Ok(())
     }
 
-    pub fn nest(&mut self, mgr: Box<dyn PassManager>) -> Result<(), Report> {
-        self.managers.push(mgr);
+    /// Nest a pass manager, which inherits the instrumentations of this pass manager.
+    pub fn nest(&mut self, mut mgr: Box<dyn PassManager>) -> Result<(), Report> {
+        for instr in self.instrumentations.iter() {
+            mgr.add_instrumentation(instr.clone());
+        }
+        self.entries.push(PipelineEntry::Nested(mgr));
         Ok(())
     }
+
+    pub fn get_instrumentations(&self) -> &[Arc<dyn PassInstrumentation>] {
+        &self.instrumentations
+    }
+
+    // Apply a pass, calling the instrumentation hooks around it.
+    fn run_pass(
+        &self,
+        pass: &dyn OperationPass,
+        op_lock: &RwLock<Operation>,
+        analysis_lock: &RwLock<AnalysisManager>,
+    ) -> Result<(), Report> {
+        // If a before-hook fails, the pass isn't run, and the
+        // hooks which already ran are unwound (in reverse order).
+        for (ind, instr) in self.instrumentations.iter().enumerate() {
+            let op = op_lock.read().unwrap();
+            if let Err(err) = instr.run_before_pass(pass, &op) {
+                for instr in self.instrumentations[..ind].iter().rev() {
+                    instr.run_after_pass_failed(pass, &op, &err);
+                }
+                return Err(err);
+            }
+        }
+        let res = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            pass.check(op_lock)
+                .and_then(|_| pass.apply(op_lock, analysis_lock))
+        }));
+
+        // A panicking pass may poison the lock, but the operation
+        // is still passed to the instrumentations before unwinding.
+        let op = op_lock.read().unwrap_or_else(|e| e.into_inner());
+        let res = match res {
+            Ok(res) => res,
+            Err(payload) => {
+                let msg = match (
+                    payload.downcast_ref::<&str>(),
+                    payload.downcast_ref::<String>(),
+                ) {
+                    (Some(s), _) => s.to_string(),
+                    (_, Some(s)) => s.clone(),
+                    _ => "unknown panic".to_string(),
+                };
+                let err = eyre!(format!("{} panicked: {}", pass.get_name(), msg));
+                for instr in self.instrumentations.iter() {
+                    instr.run_after_pass_failed(pass, &op, &err);
+                }
+                std::panic::resume_unwind(payload)
+            }
+        };
+        match res {
+            // Every after-hook is run, and the first error is returned.
+            Ok(()) => {
+                let mut res = Ok(());
+                for instr in self.instrumentations.iter() {
+                    let r = instr.run_after_pass(pass, &op);
+                    if res.is_ok() {
+                        res = r;
+                    }
+                }
+                res
+            }
+            Err(err) => {
+                for instr in self.instrumentations.iter() {
+                    instr.run_after_pass_failed(pass, &op, &err);
+                }
+                Err(err)
+            }
+        }
+    }
 }
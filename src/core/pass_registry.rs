@@ -0,0 +1,368 @@
+use crate::core::ir::Intrinsic;
+use crate::core::pass_manager::{OperationPass, OperationPassManager, PassManager};
+use color_eyre::{eyre::bail, Report};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use yansi::Paint;
+
+/// The options of a pass in a textual pipeline, e.g. `{max-iterations=10}`.
+///
+/// Options are kept as strings (in the order they were given), and converted
+/// to typed values with [`PassOptions::get`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PassOptions {
+    options: Vec<(String, String)>,
+}
+
+impl PassOptions {
+    pub fn new() -> PassOptions {
+        PassOptions::default()
+    }
+
+    pub fn insert(&mut self, key: &str, value: &str) {
+        match self.options.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.options.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.options.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Get the value of an option, converted to `T`.
+    /// Fails if the value can't be converted.
+    pub fn get<T>(&self, key: &str) -> Result<Option<T>, Report>
+    where
+        T: FromStr,
+    {
+        match self.options.iter().find(|(k, _)| k == key) {
+            None => Ok(None),
+            Some((_, v)) => match v.parse::<T>() {
+                Ok(t) => Ok(Some(t)),
+                Err(_) => bail!(format!(
+                    "Invalid value `{}` for option {} (expected {}).",
+                    v,
+                    Paint::magenta(key),
+                    std::any::type_name::<T>()
+                )),
+            },
+        }
+    }
+
+    pub fn get_or<T>(&self, key: &str, default: T) -> Result<T, Report>
+    where
+        T: FromStr,
+    {
+        Ok(self.get(key)?.unwrap_or(default))
+    }
+}
+
+impl fmt::Display for PassOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.options.is_empty() {
+            return Ok(());
+        }
+        let options = self
+            .options
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>();
+        write!(f, "{{{}}}", options.join(" "))
+    }
+}
+
+type PassFactory =
+    Arc<dyn Fn(&PassOptions) -> Result<Box<dyn OperationPass>, Report> + Send + Sync>;
+type PassManagerFactory = Arc<dyn Fn() -> Box<dyn PassManager> + Send + Sync>;
+
+/// A registered pass: the argument which names the pass in
+/// textual pipelines, the options it accepts and a factory.
+#[derive(Clone)]
+pub struct PassInfo {
+    argument: String,
+    description: String,
+    options: Vec<String>,
+    factory: PassFactory,
+}
+
+impl fmt::Debug for PassInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PassInfo({})", self.argument)
+    }
+}
+
+impl PassInfo {
+    pub fn get_argument(&self) -> &str {
+        &self.argument
+    }
+
+    pub fn get_description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn get_options(&self) -> &[String] {
+        &self.options
+    }
+
+    /// Create the pass, failing on options which the pass doesn't accept.
+    pub fn create(&self, options: &PassOptions) -> Result<Box<dyn OperationPass>, Report> {
+        for (k, _) in options.iter() {
+            if !self.options.iter().any(|o| o == k) {
+                bail!(format!(
+                    "Unknown option {} for pass {} (options: {}).",
+                    Paint::magenta(k),
+                    Paint::magenta(&self.argument),
+                    self.options.join(", ")
+                ))
+            }
+        }
+        (self.factory)(options)
+    }
+}
+
+#[derive(Default)]
+struct PassRegistry {
+    passes: HashMap<String, PassInfo>,
+    managers: HashMap<String, PassManagerFactory>,
+}
+
+lazy_static! {
+    static ref PASS_REGISTRY: RwLock<PassRegistry> = RwLock::default();
+}
+
+/// Register a pass under `argument` (e.g. `cse`) for use in textual pipelines.
+/// The pass accepts the options named in `options`, which are passed
+/// to `factory` when the pass is created.
+pub fn register_pass<F>(argument: &str, description: &str, options: &[&str], factory: F)
+where
+    F: Fn(&PassOptions) -> Result<Box<dyn OperationPass>, Report> + Send + Sync + 'static,
+{
+    let info = PassInfo {
+        argument: argument.to_string(),
+        description: description.to_string(),
+        options: options.iter().map(|o| o.to_string()).collect(),
+        factory: Arc::new(factory),
+    };
+    let mut registry = PASS_REGISTRY.write().unwrap();
+    registry.passes.insert(argument.to_string(), info);
+}
+
+/// Register an intrinsic (by its unique id, e.g. `builtin.func`), so that
+/// textual pipelines can nest pass managers on operations of that intrinsic.
+pub fn register_pass_manager<T>(intr: T)
+where
+    T: Intrinsic + Clone,
+{
+    let id = intr.get_unique_id();
+    let factory: PassManagerFactory =
+        Arc::new(move || Box::new(OperationPassManager::new(intr.clone())));
+    let mut registry = PASS_REGISTRY.write().unwrap();
+    registry.managers.insert(id, factory);
+}
+
+pub fn get_pass_info(argument: &str) -> Option<PassInfo> {
+    let registry = PASS_REGISTRY.read().unwrap();
+    registry.passes.get(argument).cloned()
+}
+
+/// Get the registered passes, sorted by argument.
+pub fn get_registered_passes() -> Vec<PassInfo> {
+    let registry = PASS_REGISTRY.read().unwrap();
+    let mut passes = registry.passes.values().cloned().collect::<Vec<_>>();
+    passes.sort_by(|a, b| a.argument.cmp(&b.argument));
+    passes
+}
+
+pub fn create_pass(
+    argument: &str,
+    options: &PassOptions,
+) -> Result<Box<dyn OperationPass>, Report> {
+    match get_pass_info(argument) {
+        None => bail!(format!(
+            "No pass is registered as {}.",
+            Paint::magenta(argument)
+        )),
+        Some(info) => info.create(options),
+    }
+}
+
+fn create_pass_manager(id: &str) -> Result<Box<dyn PassManager>, Report> {
+    let registry = PASS_REGISTRY.read().unwrap();
+    match registry.managers.get(id) {
+        None => bail!(format!(
+            "No pass manager is registered for {}.",
+            Paint::magenta(id)
+        )),
+        Some(factory) => Ok(factory()),
+    }
+}
+
+// A recursive descent parser for textual pipelines:
+//
+//   pipeline := intrinsic-id `(` elements `)`
+//   elements := (element (`,` element)*)?
+//   element  := pipeline | pass-argument options?
+//   options  := `{` (key `=` value)* `}`
+//
+// Options are separated by whitespace or commas.
+struct PipelineParser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> PipelineParser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn error(&self, msg: &str) -> Report {
+        color_eyre::eyre::eyre!(format!(
+            "{} at position {} in pipeline `{}`.",
+            msg, self.pos, self.src
+        ))
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Report> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(d) if d == c => {
+                self.pos += 1;
+                Ok(())
+            }
+            _ => Err(self.error(&format!("Expected `{}`", c))),
+        }
+    }
+
+    fn take_while<F>(&mut self, f: F) -> &'a str
+    where
+        F: Fn(char) -> bool,
+    {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !f(c) {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn identifier(&mut self) -> Result<&'a str, Report> {
+        self.skip_whitespace();
+        let id = self.take_while(|c| c.is_alphanumeric() || "-_.".contains(c));
+        if id.is_empty() {
+            return Err(self.error("Expected a pass or intrinsic name"));
+        }
+        Ok(id)
+    }
+
+    fn options(&mut self) -> Result<PassOptions, Report> {
+        let mut options = PassOptions::new();
+        self.expect('{')?;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(options);
+                }
+                Some(',') => {
+                    self.pos += 1;
+                    continue;
+                }
+                None => return Err(self.error("Expected `}`")),
+                _ => (),
+            }
+            let key = self.identifier()?;
+            self.expect('=')?;
+            self.skip_whitespace();
+            let value = self.take_while(|c| !c.is_whitespace() && c != ',' && c != '}');
+            if value.is_empty() {
+                return Err(self.error(&format!("Expected a value for option `{}`", key)));
+            }
+            options.insert(key, value);
+        }
+    }
+
+    fn elements(&mut self, mgr: &mut dyn PassManager) -> Result<(), Report> {
+        self.skip_whitespace();
+        if self.peek() == Some(')') {
+            return Ok(());
+        }
+        loop {
+            let name = self.identifier()?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some('(') => {
+                    let nested_mgr = self.pipeline_body(name)?;
+                    mgr.add_manager(nested_mgr)?;
+                }
+                _ => {
+                    let options = match self.peek() {
+                        Some('{') => self.options()?,
+                        _ => PassOptions::new(),
+                    };
+                    mgr.add_pass(create_pass(name, &options)?)?;
+                }
+            }
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn pipeline_body(&mut self, id: &str) -> Result<Box<dyn PassManager>, Report> {
+        let mut mgr = create_pass_manager(id)?;
+        self.expect('(')?;
+        self.elements(mgr.as_mut())?;
+        self.expect(')')?;
+        Ok(mgr)
+    }
+}
+
+/// Parse a textual pipeline, e.g.
+/// `builtin.module(populate-symbol-table, builtin.func(cse, canonicalize{max-iterations=10}))`,
+/// into a (nested) pass manager. Passes and pass managers must be registered
+/// with [`register_pass`] and [`register_pass_manager`].
+///
+/// Displaying a pass manager produces the same textual form.
+pub fn parse_pass_pipeline(src: &str) -> Result<Box<dyn PassManager>, Report> {
+    let mut parser = PipelineParser { src, pos: 0 };
+    let id = parser.identifier()?;
+    let mgr = parser.pipeline_body(id)?;
+    parser.skip_whitespace();
+    if parser.pos != src.len() {
+        return Err(parser.error("Unexpected trailing input"));
+    }
+    Ok(mgr)
+}
+
+/// Parse the elements of a textual pipeline (e.g. `cse, builtin.func(dce)`)
+/// and add them to an existing pass manager.
+pub fn parse_pass_pipeline_into(mgr: &mut dyn PassManager, src: &str) -> Result<(), Report> {
+    let mut parser = PipelineParser { src, pos: 0 };
+    parser.elements(mgr)?;
+    parser.skip_whitespace();
+    if parser.pos != src.len() {
+        return Err(parser.error("Unexpected trailing input"));
+    }
+    Ok(())
+}
@@ -0,0 +1,505 @@
+use crate::core::ir::{Intrinsic, Operation, SupportsInterfaceTraits, Var};
+use crate::core::pass_manager::{AnalysisManager, OperationPass};
+use crate::core::rewriter::{IrRewriter, RewriteListener};
+use crate::core::statistics::Statistic;
+use color_eyre::Report;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+
+/// A rewrite rule which can be applied to an [`Operation`]
+/// (referenced by its `Var`) through an [`IrRewriter`].
+///
+/// Patterns are collected into a [`RewritePatternSet`], and
+/// are applied by [`apply_patterns_greedily`] (or
+/// the [`GreedyRewritePass`]).
+pub trait RewritePattern: Send + Sync + std::fmt::Debug {
+    /// The intrinsic which this pattern is rooted at.
+    /// Patterns which return `None` are tried on every operation.
+    fn root_intrinsic(&self) -> Option<Box<dyn Intrinsic>> {
+        None
+    }
+
+    /// The benefit of applying the pattern - when several patterns
+    /// match an operation, patterns with higher benefit are tried first.
+    fn benefit(&self) -> usize {
+        1
+    }
+
+    /// Try to match the operation defining `v`, and rewrite it.
+    /// The rewriter's insertion point is set before `v`.
+    ///
+    /// Returns `true` if the IR was changed. Patterns must not
+    /// change the IR when they return `false`.
+    fn match_and_rewrite(&self, v: Var, rewriter: &mut IrRewriter) -> Result<bool, Report>;
+}
+
+/// A set of [`RewritePattern`] instances, ordered by benefit.
+///
+/// Patterns are shared between clones of the set.
+#[derive(Debug, Default, Clone)]
+pub struct RewritePatternSet {
+    patterns: Vec<Arc<dyn RewritePattern>>,
+}
+
+impl RewritePatternSet {
+    pub fn new() -> RewritePatternSet {
+        RewritePatternSet {
+            patterns: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, p: Box<dyn RewritePattern>) {
+        self.insert(Arc::from(p));
+    }
+
+    /// Add the patterns of `other` to this set.
+    pub fn extend(&mut self, other: &RewritePatternSet) {
+        for p in other.patterns.iter() {
+            self.insert(p.clone());
+        }
+    }
+
+    fn insert(&mut self, p: Arc<dyn RewritePattern>) {
+        let ind = self
+            .patterns
+            .iter()
+            .position(|q| q.benefit() < p.benefit())
+            .unwrap_or(self.patterns.len());
+        self.patterns.insert(ind, p);
+    }
+
+    pub fn get_patterns(&self) -> &[Arc<dyn RewritePattern>] {
+        &self.patterns
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    fn applicable(&self, op: &Operation) -> impl Iterator<Item = &Arc<dyn RewritePattern>> {
+        let id = op.get_intrinsic().get_unique_id();
+        self.patterns
+            .iter()
+            .filter(move |p| match p.root_intrinsic() {
+                None => true,
+                Some(intr) => intr.get_unique_id() == id,
+            })
+    }
+}
+
+/// Configuration for [`apply_patterns_greedily`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GreedyRewriteConfig {
+    /// The maximum number of sweeps over each region.
+    pub max_iterations: usize,
+
+    /// The maximum number of rewrites in each region,
+    /// which bounds patterns that keep revisiting the same operations.
+    pub max_rewrites: Option<usize>,
+
+    /// Whether operations nested in regions are also rewritten.
+    pub recursive: bool,
+}
+
+impl Default for GreedyRewriteConfig {
+    fn default() -> Self {
+        GreedyRewriteConfig {
+            max_iterations: 10,
+            max_rewrites: None,
+            recursive: true,
+        }
+    }
+}
+
+/// The result of applying patterns with [`apply_patterns_greedily`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GreedyRewriteResult {
+    /// Whether any pattern changed the IR.
+    pub changed: bool,
+
+    /// Whether a fixpoint was reached within the iteration limit
+    /// (in every rewritten region).
+    pub converged: bool,
+
+    /// The number of successful pattern applications.
+    pub rewrites: usize,
+}
+
+impl GreedyRewriteResult {
+    fn merge(&mut self, other: GreedyRewriteResult) {
+        self.changed |= other.changed;
+        self.converged &= other.converged;
+        self.rewrites += other.rewrites;
+    }
+}
+
+/// Adds operations which are touched by a rewrite back onto the worklist.
+struct WorklistListener(Rc<RefCell<VecDeque<Var>>>);
+
+impl WorklistListener {
+    fn push(&mut self, v: Var) {
+        let mut worklist = self.0.borrow_mut();
+        if !worklist.contains(&v) {
+            worklist.push_back(v);
+        }
+    }
+}
+
+impl RewriteListener for WorklistListener {
+    fn notify_inserted(&mut self, v: Var, _op: &Operation) {
+        self.push(v);
+    }
+
+    fn notify_modified(&mut self, v: Var, _op: &Operation) {
+        self.push(v);
+    }
+
+    fn notify_erased(&mut self, v: Var, _op: &Operation) {
+        self.0.borrow_mut().retain(|w| *w != v);
+    }
+}
+
+fn get_region_ops(op: &Operation, region: usize) -> Vec<Var> {
+    let r = &op.get_regions()[region];
+    (0..r.get_blocks().len())
+        .flat_map(|ind| r.get_block_iter(ind).map(|(v, _)| v).collect::<Vec<_>>())
+        .collect()
+}
+
+fn apply_region(
+    op: &mut Operation,
+    region: usize,
+    patterns: &RewritePatternSet,
+    config: &GreedyRewriteConfig,
+) -> Result<GreedyRewriteResult, Report> {
+    let mut result = GreedyRewriteResult {
+        changed: false,
+        converged: false,
+        rewrites: 0,
+    };
+    let worklist = Rc::new(RefCell::new(VecDeque::new()));
+    for _ in 0..config.max_iterations {
+        let mut changed = false;
+        worklist.borrow_mut().extend(get_region_ops(op, region));
+        loop {
+            if config.max_rewrites.is_some_and(|m| result.rewrites >= m) {
+                result.changed |= changed;
+                return Ok(result);
+            }
+            let v = match worklist.borrow_mut().pop_front() {
+                None => break,
+                Some(v) => v,
+            };
+            let mut rewriter = IrRewriter::new(op);
+            rewriter.set_region(region)?;
+            let candidates = match rewriter.get_op(v) {
+                None => continue,
+                Some(child) => patterns.applicable(child).collect::<Vec<_>>(),
+            };
+            rewriter.add_listener(Box::new(WorklistListener(worklist.clone())));
+            for p in candidates {
+                rewriter.set_insertion_point_before(v);
+                if p.match_and_rewrite(v, &mut rewriter)? {
+                    changed = true;
+                    result.rewrites += 1;
+                    break;
+                }
+            }
+        }
+        if !changed {
+            result.converged = true;
+            break;
+        }
+        result.changed = true;
+    }
+    Ok(result)
+}
+
+/// Apply a set of patterns to the regions of `op` (and, if configured,
+/// to the regions of nested operations), until a fixpoint is reached
+/// or the iteration limit is exceeded.
+///
+/// Each region is processed with a worklist: every operation is visited
+/// once per sweep, and operations which are created or modified
+/// by a rewrite are revisited within the sweep. Sweeps are repeated
+/// until no pattern applies.
+pub fn apply_patterns_greedily(
+    op: &mut Operation,
+    patterns: &RewritePatternSet,
+    config: &GreedyRewriteConfig,
+) -> Result<GreedyRewriteResult, Report> {
+    let mut result = GreedyRewriteResult {
+        changed: false,
+        converged: true,
+        rewrites: 0,
+    };
+    for region in 0..op.get_regions().len() {
+        if config.recursive {
+            for v in get_region_ops(op, region) {
+                let (_, child) = op.get_regions_mut()[region].get_op_mut(v).unwrap();
+                if !child.get_regions().is_empty() {
+                    result.merge(apply_patterns_greedily(child, patterns, config)?);
+                }
+            }
+        }
+        result.merge(apply_region(op, region, patterns, config)?);
+    }
+    Ok(result)
+}
+
+/// An [`OperationPass`] which applies a [`RewritePatternSet`]
+/// with the greedy driver.
+#[derive(Debug, Clone)]
+pub struct GreedyRewritePass {
+    patterns: Arc<RewritePatternSet>,
+    config: GreedyRewriteConfig,
+    rewrites: Statistic,
+}
+
+fn rewrites_statistic() -> Statistic {
+    Statistic::new("rewrites", "Number of successful pattern applications")
+}
+
+impl GreedyRewritePass {
+    pub fn new(patterns: RewritePatternSet) -> GreedyRewritePass {
+        GreedyRewritePass {
+            patterns: Arc::new(patterns),
+            config: GreedyRewriteConfig::default(),
+            rewrites: rewrites_statistic(),
+        }
+    }
+
+    pub fn with_config(patterns: RewritePatternSet, config: GreedyRewriteConfig) -> Self {
+        GreedyRewritePass {
+            patterns: Arc::new(patterns),
+            config,
+            rewrites: rewrites_statistic(),
+        }
+    }
+
+    pub fn get_config(&self) -> &GreedyRewriteConfig {
+        &self.config
+    }
+
+    pub fn get_patterns(&self) -> &RewritePatternSet {
+        &self.patterns
+    }
+
+    /// Apply `patterns` (instead of the patterns of the pass)
+    /// to `op`, with the configuration of the pass.
+    pub fn apply_patterns(
+        &self,
+        op: &mut Operation,
+        patterns: &RewritePatternSet,
+    ) -> Result<(), Report> {
+        let result = apply_patterns_greedily(op, patterns, &self.config)?;
+        self.rewrites.add(result.rewrites);
+        if !result.converged {
+            tracing::warn!(
+                "Greedy rewriting of {} did not converge in {} iterations.",
+                op.get_intrinsic(),
+                self.config.max_iterations
+            );
+        }
+        Ok(())
+    }
+}
+
+impl OperationPass for GreedyRewritePass {
+    fn reset(&self) -> Box<dyn OperationPass> {
+        Box::new(self.clone())
+    }
+
+    fn get_statistics(&self) -> Vec<&Statistic> {
+        vec![&self.rewrites]
+    }
+
+    fn check(&self, _op: &RwLock<Operation>) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn apply(
+        &self,
+        op_lock: &RwLock<Operation>,
+        _analysis_lock: &RwLock<AnalysisManager>,
+    ) -> Result<(), Report> {
+        let mut op = op_lock.write().unwrap();
+        self.apply_patterns(&mut op, &self.patterns)
+    }
+}
+
+/// A declarative interface for defining [`RewritePattern`] implementors.
+///
+/// The syntax looks like the following:
+/// ```ignore
+/// rewrite!(
+///     /// addi(x, 0) => x
+///     AddiZero: Addi(x, Constant() { "builtin.value": ConstantAttr = ConstantAttr::Integer(0, _) })
+///         => x
+/// );
+///
+/// rewrite!(AndiSelf: Andi(x, y) if x == y => x);
+///
+/// rewrite!(AndiReassociate: Andi(Andi(x, y), z) => Andi(x, Andi(y, z)));
+/// ```
+///
+/// The source pattern is a tree of intrinsics (rooted at the intrinsic
+/// which the pattern applies to). Each operand position is either
+/// a binding (an identifier, which binds the operand `Var`), `_`
+/// (which matches anything), or a nested intrinsic pattern
+/// (which matches the operation defining the operand).
+/// Operand counts must match exactly. Each intrinsic pattern can be followed by
+/// attribute constraints of the form `{ "key": Type = pattern, ... }`, which match
+/// the attribute value (of type `Type`) stored under `key` against `pattern`.
+/// Bindings must be distinct - use an `if` guard to constrain them further.
+///
+/// The replacement is either a binding (uses of the root are replaced by the
+/// bound `Var`) or a tree of intrinsics with bindings or nested intrinsics as operands,
+/// and optional attributes of the form `{ "key": value, ... }`. New operations
+/// are created before the root.
+///
+/// Every intrinsic which is referenced must be in scope and implement [`Intrinsic`],
+/// so misspelled or missing intrinsics are caught at build time.
+/// The benefit of the generated pattern is the number of intrinsics
+/// in the source pattern.
+///
+/// [`Intrinsic`]: crate::core::Intrinsic
+#[macro_export]
+macro_rules! rewrite {
+    (@count) => { 0 };
+    (@count ( $($inner:tt)* ) $($rest:tt)*) => {
+        1 + $crate::rewrite!(@count $($inner)*) + $crate::rewrite!(@count $($rest)*)
+    };
+    (@count { $($inner:tt)* } $($rest:tt)*) => { $crate::rewrite!(@count $($rest)*) };
+    (@count $other:tt $($rest:tt)*) => { $crate::rewrite!(@count $($rest)*) };
+
+    (@attrs $op:ident; $($key:literal : $ty:ty = $pat:pat),* $(,)?) => {
+        $(
+            match $op
+                .get_attributes()
+                .get($key)
+                .and_then(|a| a.query_ref::<dyn $crate::core::AttributeValue<$ty>>())
+            {
+                Some(a) if matches!($crate::core::AttributeValue::get_value(a), $pat) => (),
+                _ => return Ok(false),
+            }
+        )*
+    };
+
+    (@match $rw:ident $var:ident; $intr:ident ( $($args:tt)* ) $({ $($attrs:tt)* })?) => {
+        let _: &dyn $crate::core::Intrinsic = &$intr;
+        let operands = {
+            let op = match $rw.get_op($var) {
+                None => return Ok(false),
+                Some(op) => op,
+            };
+            if !op.get_intrinsic().is::<$intr>() {
+                return Ok(false);
+            }
+            $($crate::rewrite!(@attrs op; $($attrs)*);)?
+            op.get_operands()
+        };
+        let mut operands = operands.into_iter();
+        $crate::rewrite!(@args $rw operands; $($args)*);
+        if operands.next().is_some() {
+            return Ok(false);
+        }
+    };
+
+    (@args $rw:ident $it:ident; ) => {};
+    (@args $rw:ident $it:ident; _ $(, $($rest:tt)*)?) => {
+        if $it.next().is_none() {
+            return Ok(false);
+        }
+        $crate::rewrite!(@args $rw $it; $($($rest)*)?);
+    };
+    (@args $rw:ident $it:ident;
+     $intr:ident ( $($inner:tt)* ) $({ $($attrs:tt)* })? $(, $($rest:tt)*)?) => {
+        let operand = match $it.next() {
+            None => return Ok(false),
+            Some(v) => v,
+        };
+        $crate::rewrite!(@match $rw operand; $intr ( $($inner)* ) $({ $($attrs)* })?);
+        $crate::rewrite!(@args $rw $it; $($($rest)*)?);
+    };
+    (@args $rw:ident $it:ident; $x:ident $(, $($rest:tt)*)?) => {
+        let $x = match $it.next() {
+            None => return Ok(false),
+            Some(v) => v,
+        };
+        $crate::rewrite!(@args $rw $it; $($($rest)*)?);
+    };
+
+    (@build_attrs $b:ident; $($key:literal : $val:expr),* $(,)?) => {
+        $($b.insert_attr($key, Box::new($val));)*
+    };
+
+    (@build $rw:ident $v:ident; $intr:ident ( $($args:tt)* ) $({ $($attrs:tt)* })?) => {{
+        let _: &dyn $crate::core::Intrinsic = &$intr;
+        let mut operands = Vec::new();
+        $crate::rewrite!(@build_args $rw $v operands; $($args)*);
+        let loc = $rw.get_op($v).unwrap().get_location().clone();
+        let mut b = $crate::core::OperationBuilder::default(Box::new($intr), loc);
+        b.set_operands(operands);
+        $($crate::rewrite!(@build_attrs b; $($attrs)*);)?
+        $rw.create(b)?
+    }};
+
+    (@build_args $rw:ident $v:ident $ops:ident; ) => {};
+    (@build_args $rw:ident $v:ident $ops:ident;
+     $intr:ident ( $($inner:tt)* ) $({ $($attrs:tt)* })? $(, $($rest:tt)*)?) => {
+        let operand = $crate::rewrite!(@build $rw $v; $intr ( $($inner)* ) $({ $($attrs)* })?);
+        $ops.push(operand);
+        $crate::rewrite!(@build_args $rw $v $ops; $($($rest)*)?);
+    };
+    (@build_args $rw:ident $v:ident $ops:ident; $x:ident $(, $($rest:tt)*)?) => {
+        $ops.push($x);
+        $crate::rewrite!(@build_args $rw $v $ops; $($($rest)*)?);
+    };
+
+    (@rewrite $rw:ident $v:ident; $x:ident) => {
+        $rw.replace_op($v, $x)?;
+    };
+    (@rewrite $rw:ident $v:ident; $intr:ident ( $($args:tt)* ) $({ $($attrs:tt)* })?) => {
+        let new = $crate::rewrite!(@build $rw $v; $intr ( $($args)* ) $({ $($attrs)* })?);
+        $rw.replace_op($v, new)?;
+    };
+
+    ($(#[$attr:meta])* $name:ident:
+     $root:ident ( $($args:tt)* ) $({ $($attrs:tt)* })?
+     $(if $guard:expr)? => $($rhs:tt)+) => {
+        $(#[$attr])*
+        #[derive(Debug)]
+        pub struct $name;
+
+        impl $crate::core::RewritePattern for $name {
+            fn root_intrinsic(&self) -> Option<Box<dyn $crate::core::Intrinsic>> {
+                Some(Box::new($root))
+            }
+
+            fn benefit(&self) -> usize {
+                1 + $crate::rewrite!(@count $($args)*)
+            }
+
+            #[allow(unused_variables)]
+            fn match_and_rewrite(
+                &self,
+                v: $crate::core::Var,
+                rewriter: &mut $crate::core::IrRewriter,
+            ) -> Result<bool, $crate::Report> {
+                $crate::rewrite!(@match rewriter v; $root ( $($args)* ) $({ $($attrs)* })?);
+                $(if !($guard) {
+                    return Ok(false);
+                })?
+                $crate::rewrite!(@rewrite rewriter v; $($rhs)+);
+                Ok(true)
+            }
+        }
+    };
+}
@@ -1,26 +1,29 @@
 use crate::core::ir::{BasicBlock, Operation, Var};
 use color_eyre::{eyre::bail, Report};
 
-#[derive(Debug, Hash)]
+#[derive(Debug, Default, Hash)]
 pub struct Graph {
     defs: Vec<(i32, i32)>,
     blocks: Vec<BasicBlock>,
 }
 
-impl Default for Graph {
-    fn default() -> Graph {
-        Graph {
-            defs: Vec::new(),
-            blocks: Vec::new(),
-        }
-    }
-}
-
 impl Graph {
     pub fn len(&self) -> usize {
         self.defs.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.defs.is_empty()
+    }
+
+    /// Extend the `Var` instances of the region to `len` (leaving them undefined),
+    /// used to read IR which refers to `Var` instances by index.
+    pub(crate) fn reserve(&mut self, len: usize) {
+        if self.defs.len() < len {
+            self.defs.resize(len, (-1, -1));
+        }
+    }
+
     /// Get the block index and SSA index for `v: Var`.
     fn get_var_blockidx(&self, v: Var) -> Option<(usize, i32)> {
         let (b, i) = self.defs.get(v.get_id()).unwrap_or(&(-1, -1));
@@ -43,10 +46,7 @@ impl Graph {
     }
 
     pub fn has_block(&self) -> bool {
-        match self.blocks.len() {
-            0 => false,
-            _ => true,
-        }
+        !self.blocks.is_empty()
     }
 
     pub fn push_block(&mut self, blk: BasicBlock) -> usize {
@@ -82,28 +82,82 @@ impl Graph {
         self.defs.push((0_i32, len as i32));
         arg
     }
+
+    pub fn get_op_mut(&mut self, id: Var) -> Option<(Var, &mut Operation)> {
+        match self.get_var_blockidx(id) {
+            None => None,
+            Some((b, i)) => {
+                let bb = &mut self.blocks[b];
+                let inst = &mut bb.get_ops_mut()[i as usize];
+                Some((id, inst))
+            }
+        }
+    }
+
+    /// Get the position of the operation defining `v` in the block.
+    pub fn get_op_position(&self, v: Var) -> Option<usize> {
+        self.get_var_blockidx(v).map(|(_, i)| i as usize)
+    }
+
+    /// Insert an operation at position `idx` in the block.
+    /// Returns a new `Var` reference to that operation.
+    pub fn insert_op(&mut self, idx: usize, v: Operation) -> Var {
+        let arg = Var::new(self.defs.len());
+        self.defs.push((-1, -1));
+        self.place_op(arg, idx, v);
+        arg
+    }
+
+    /// Remove the operation defining `v` from the block.
+    /// The `Var` is left undefined.
+    pub fn remove_op(&mut self, v: Var) -> Option<Operation> {
+        let (b, i) = self.get_var_blockidx(v)?;
+        let op = self.blocks[b].get_ops_mut().remove(i as usize);
+        for d in self.defs.iter_mut() {
+            if d.0 == b as i32 && d.1 > i {
+                d.1 -= 1;
+            }
+        }
+        self.defs[v.get_id()] = (-1, -1);
+        Some(op)
+    }
+
+    /// Place an operation at position `idx` in the block,
+    /// defining the (currently undefined) `v`.
+    pub fn place_op(&mut self, v: Var, idx: usize, op: Operation) {
+        for d in self.defs.iter_mut() {
+            if d.0 == 0 && d.1 >= idx as i32 {
+                d.1 += 1;
+            }
+        }
+        self.blocks[0].get_ops_mut().insert(idx, op);
+        self.defs[v.get_id()] = (0, idx as i32);
+    }
 }
 
-#[derive(Debug, Hash)]
+#[derive(Debug, Default, Hash)]
 pub struct SSACFG {
     defs: Vec<(i32, i32)>,
     blocks: Vec<BasicBlock>,
 }
 
-impl Default for SSACFG {
-    fn default() -> SSACFG {
-        SSACFG {
-            defs: Vec::new(),
-            blocks: Vec::new(),
-        }
-    }
-}
-
 impl SSACFG {
     pub fn len(&self) -> usize {
         self.defs.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.defs.is_empty()
+    }
+
+    /// Extend the `Var` instances of the region to `len` (leaving them undefined),
+    /// used to read IR which refers to `Var` instances by index.
+    pub(crate) fn reserve(&mut self, len: usize) {
+        if self.defs.len() < len {
+            self.defs.resize(len, (-1, -1));
+        }
+    }
+
     pub fn get_operands(&self) -> &[Var] {
         self.blocks[0].get_operands()
     }
@@ -119,6 +173,12 @@ impl SSACFG {
         arg
     }
 
+    /// Define the (currently undefined) `v` as the next argument of block `blk`.
+    pub(crate) fn place_arg(&mut self, v: Var, blk: usize) {
+        self.defs[v.get_id()] = (blk as i32, -1);
+        self.blocks[blk].get_operands_mut().push(v);
+    }
+
     pub fn get_block(&self, ind: usize) -> &BasicBlock {
         &self.blocks[ind]
     }
@@ -161,7 +221,7 @@ impl SSACFG {
         arg
     }
 
-    fn get_op_mut(&mut self, id: Var) -> Option<(Var, &mut Operation)> {
+    pub fn get_op_mut(&mut self, id: Var) -> Option<(Var, &mut Operation)> {
         match self.get_var_blockidx(id) {
             None => None,
             Some((b, i)) => {
@@ -172,6 +232,82 @@ impl SSACFG {
         }
     }
 
+    /// Get the block index and position of the operation defining `v`.
+    pub fn get_op_position(&self, v: Var) -> Option<(usize, usize)> {
+        self.get_var_blockidx(v).map(|(b, i)| (b, i as usize))
+    }
+
+    /// Get the index of the block which `v` is an argument of.
+    pub fn get_arg_block(&self, v: Var) -> Option<usize> {
+        match self.defs.get(v.get_id()) {
+            Some((b, -1)) if *b >= 0 => Some(*b as usize),
+            _ => None,
+        }
+    }
+
+    /// Insert an operation at position `idx` in block `blk`.
+    /// Returns a new `Var` reference to that operation.
+    pub fn insert_op(&mut self, blk: usize, idx: usize, v: Operation) -> Var {
+        let arg = Var::new(self.defs.len());
+        self.defs.push((-1, -1));
+        self.place_op(arg, blk, idx, v);
+        arg
+    }
+
+    /// Remove the operation defining `v` from its block.
+    /// The `Var` is left undefined.
+    pub fn remove_op(&mut self, v: Var) -> Option<Operation> {
+        let (b, i) = self.get_var_blockidx(v)?;
+        let op = self.blocks[b].get_ops_mut().remove(i as usize);
+        for d in self.defs.iter_mut() {
+            if d.0 == b as i32 && d.1 > i {
+                d.1 -= 1;
+            }
+        }
+        self.defs[v.get_id()] = (-1, -1);
+        Some(op)
+    }
+
+    /// Place an operation at position `idx` in block `blk`,
+    /// defining the (currently undefined) `v`.
+    pub fn place_op(&mut self, v: Var, blk: usize, idx: usize, op: Operation) {
+        for d in self.defs.iter_mut() {
+            if d.0 == blk as i32 && d.1 >= idx as i32 {
+                d.1 += 1;
+            }
+        }
+        self.blocks[blk].get_ops_mut().insert(idx, op);
+        self.defs[v.get_id()] = (blk as i32, idx as i32);
+    }
+
+    /// Remove block `ind`, leaving the `Var` instances defined
+    /// in the block (including its arguments) undefined.
+    /// Later blocks are renumbered, and the successors of operations in
+    /// the region are updated - successors which refer to the removed
+    /// block itself are left unchanged, so the caller must
+    /// ensure that the block is no longer branched to.
+    pub fn remove_block(&mut self, ind: usize) -> BasicBlock {
+        let blk = self.blocks.remove(ind);
+        for d in self.defs.iter_mut() {
+            if d.0 == ind as i32 {
+                *d = (-1, -1);
+            } else if d.0 > ind as i32 {
+                d.0 -= 1;
+            }
+        }
+        for b in self.blocks.iter_mut() {
+            for op in b.get_ops_mut().iter_mut() {
+                let succs = op
+                    .get_successors()
+                    .iter()
+                    .map(|s| if *s > ind { s - 1 } else { *s })
+                    .collect::<Vec<_>>();
+                op.set_successors(succs);
+            }
+        }
+        blk
+    }
+
     /// Get the vector of `Var` which index into block with index `id`.
     pub fn get_block_vars(&self, id: usize) -> Vec<Var> {
         let v = self
@@ -198,12 +334,9 @@ impl SSACFG {
         let mut v: Vec<Var> = Vec::new();
         for ind in 0..self.defs.len() {
             let r = Var::new(ind);
-            match self.get_var_blockidx(r) {
-                None => (),
-                Some(b) => {
-                    if b.0 == blockidx && self.defs[ind].1 >= 0 {
-                        v.push(r);
-                    }
+            if let Some(b) = self.get_var_blockidx(r) {
+                if b.0 == blockidx && self.defs[ind].1 >= 0 {
+                    v.push(r);
                 }
             }
         }
@@ -230,6 +363,10 @@ impl Region {
         }
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn push_arg(&mut self, ind: usize) -> Result<Var, Report> {
         match self {
             Region::Directed(ssacfg) => Ok(ssacfg.push_arg(ind)),
@@ -239,6 +376,25 @@ impl Region {
         }
     }
 
+    pub(crate) fn reserve(&mut self, len: usize) {
+        match self {
+            Region::Directed(ssacfg) => ssacfg.reserve(len),
+            Region::Undirected(graph) => graph.reserve(len),
+        }
+    }
+
+    pub(crate) fn place_arg(&mut self, v: Var, blk: usize) -> Result<(), Report> {
+        match self {
+            Region::Directed(ssacfg) => {
+                ssacfg.place_arg(v, blk);
+                Ok(())
+            }
+            Region::Undirected(_graph) => {
+                bail!("Can't place argument in `Graph` region.")
+            }
+        }
+    }
+
     pub fn push_op(&mut self, blk: usize, op: Operation) -> Var {
         match self {
             Region::Directed(ssacfg) => ssacfg.push_op(blk, op),
@@ -253,6 +409,78 @@ impl Region {
         }
     }
 
+    pub fn get_op_mut(&mut self, id: Var) -> Option<(Var, &mut Operation)> {
+        match self {
+            Region::Directed(ssacfg) => ssacfg.get_op_mut(id),
+            Region::Undirected(graph) => graph.get_op_mut(id),
+        }
+    }
+
+    /// Get the block index and position of the operation defining `v`.
+    pub fn get_op_position(&self, v: Var) -> Option<(usize, usize)> {
+        match self {
+            Region::Directed(ssacfg) => ssacfg.get_op_position(v),
+            Region::Undirected(graph) => graph.get_op_position(v).map(|i| (0, i)),
+        }
+    }
+
+    /// Get the block which `v` is an argument of.
+    pub fn get_arg_block(&self, v: Var) -> Option<usize> {
+        match self {
+            Region::Directed(ssacfg) => ssacfg.get_arg_block(v),
+            Region::Undirected(_graph) => None,
+        }
+    }
+
+    pub fn insert_op(&mut self, blk: usize, idx: usize, op: Operation) -> Var {
+        match self {
+            Region::Directed(ssacfg) => ssacfg.insert_op(blk, idx, op),
+            Region::Undirected(graph) => graph.insert_op(idx, op),
+        }
+    }
+
+    pub fn remove_op(&mut self, v: Var) -> Option<Operation> {
+        match self {
+            Region::Directed(ssacfg) => ssacfg.remove_op(v),
+            Region::Undirected(graph) => graph.remove_op(v),
+        }
+    }
+
+    pub fn place_op(&mut self, v: Var, blk: usize, idx: usize, op: Operation) {
+        match self {
+            Region::Directed(ssacfg) => ssacfg.place_op(v, blk, idx, op),
+            Region::Undirected(graph) => graph.place_op(v, idx, op),
+        }
+    }
+
+    /// Get the operations (by `Var`) in the region which use `v` as an operand.
+    pub fn get_uses(&self, v: Var) -> Vec<Var> {
+        let mut uses = Vec::new();
+        for ind in 0..self.get_blocks().len() {
+            for (u, op) in self.get_block_iter(ind) {
+                if op.get_operands().contains(&v) {
+                    uses.push(u);
+                }
+            }
+        }
+        uses
+    }
+
+    /// Replace every use of `from` in the region with `to`.
+    /// Returns the operations (by `Var`) which were modified.
+    pub fn replace_all_uses(&mut self, from: Var, to: Var) -> Vec<Var> {
+        let uses = self.get_uses(from);
+        for u in uses.iter() {
+            let (_, op) = self.get_op_mut(*u).unwrap();
+            for operand in op.get_operands_mut().iter_mut() {
+                if *operand == from {
+                    *operand = to;
+                }
+            }
+        }
+        uses
+    }
+
     pub fn push_block(&mut self, b: BasicBlock) -> Result<(), Report> {
         match self {
             Region::Directed(ssacfg) => {
@@ -270,6 +498,19 @@ impl Region {
         }
     }
 
+    /// Remove a block from a `SSACFG` region, see [`SSACFG::remove_block`].
+    pub fn remove_block(&mut self, ind: usize) -> Result<BasicBlock, Report> {
+        match self {
+            Region::Directed(ssacfg) if ind < ssacfg.get_blocks().len() => {
+                Ok(ssacfg.remove_block(ind))
+            }
+            Region::Directed(_) => bail!(format!("Block {} does not exist in region.", ind)),
+            Region::Undirected(_graph) => {
+                bail!("Can't remove block from `Graph` region.")
+            }
+        }
+    }
+
     pub fn get_blocks(&self) -> &[BasicBlock] {
         match self {
             Region::Directed(ssacfg) => ssacfg.get_blocks(),
@@ -290,6 +531,14 @@ impl Region {
             Region::Undirected(graph) => graph.get_block_mut(),
         }
     }
+    /// Get the successors of block `ind`, given by the
+    /// successors of its last operation (the terminator).
+    pub fn get_block_successors(&self, ind: usize) -> Vec<usize> {
+        match self.get_block(ind).get_ops().last() {
+            None => Vec::new(),
+            Some(term) => term.get_successors().to_vec(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -314,7 +563,7 @@ impl<'b> Iterator for ImmutableBlockIterator<'b> {
 
 impl Region {
     /// Get an immutable iterator over basic blocks.
-    pub fn get_block_iter(&self, id: usize) -> ImmutableBlockIterator {
+    pub fn get_block_iter(&self, id: usize) -> ImmutableBlockIterator<'_> {
         let ks = match self {
             Region::Directed(ssacfg) => ssacfg.get_block_vars(id),
             Region::Undirected(graph) => graph.get_block_vars(),
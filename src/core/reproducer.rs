@@ -0,0 +1,224 @@
+use crate::core::instrumentation::PassInstrumentation;
+use crate::core::ir::{IsolatedFromAbove, Operation, SupportsInterfaceTraits};
+use crate::core::parser::{parse_generic, print_generic};
+use crate::core::pass_manager::OperationPass;
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Report,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread::ThreadId;
+use yansi::Paint;
+
+const PIPELINE_PREFIX: &str = "// configuration: --pass-pipeline=";
+
+/// Writes a reproducer file when a pass fails (or panics), containing the
+/// pipeline specification (see [`crate::core::parse_pass_pipeline`]) and the
+/// input operation in the generic textual form (see [`crate::core::print_generic`]),
+/// which [`read_reproducer_input`] reads back to replay the failure.
+///
+/// Enabled with [`crate::core::OperationPassManager::enable_crash_reproducer`].
+/// A local reproducer is narrowed to the failing pass, and the
+/// (`IsolatedFromAbove`) operation it was applied to, as it was before the pass ran.
+/// If the failing pass was applied to an operation which isn't isolated,
+/// the full pipeline and input are written instead. The same holds for failures
+/// which aren't reported to the reproducer as a failing pass, e.g. when the
+/// `run_after_pass` hook of another instrumentation (like `VerifyEach`) fails.
+///
+/// The generic form requires every intrinsic and attribute of the input to be registered
+/// (e.g. with [`crate::dialects::builtin::register_builtin_dialect`]). Otherwise,
+/// the pretty-printed IR is written as comments, and the reproducer can't be replayed.
+#[derive(Debug)]
+pub struct CrashReproducer {
+    path: PathBuf,
+    local: bool,
+    input: Mutex<Option<(String, String)>>,
+    snapshots: Mutex<HashMap<ThreadId, (String, String)>>,
+    written: Mutex<bool>,
+}
+
+// Printed IR (and error messages) may contain terminal color codes.
+fn strip_colors(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        for d in chars.by_ref() {
+            if d.is_ascii_alphabetic() {
+                break;
+            }
+        }
+    }
+    out
+}
+
+// The IR of a reproducer, in the generic form if possible.
+fn print_ir(op: &Operation) -> String {
+    match print_generic(op) {
+        Ok(ir) => ir,
+        Err(err) => format!(
+            "// note: the IR can't be read back: {}\n// {}",
+            err,
+            format!("{}", op).replace('\n', "\n// ")
+        ),
+    }
+}
+
+fn is_isolated(op: &Operation) -> bool {
+    op.get_intrinsic()
+        .query_ref::<dyn IsolatedFromAbove>()
+        .is_some()
+}
+
+impl CrashReproducer {
+    pub fn new(path: impl AsRef<Path>, local: bool) -> CrashReproducer {
+        CrashReproducer {
+            path: path.as_ref().to_path_buf(),
+            local,
+            input: Mutex::new(None),
+            snapshots: Mutex::new(HashMap::new()),
+            written: Mutex::new(false),
+        }
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_local(&self) -> bool {
+        self.local
+    }
+
+    /// Record the pipeline and its input operation,
+    /// called by the top-level pass manager before running.
+    pub fn set_input(&self, pipeline: &str, op: &Operation) {
+        *self.input.lock().unwrap() = Some((pipeline.to_string(), print_ir(op)));
+        *self.written.lock().unwrap() = false;
+        self.snapshots.lock().unwrap().clear();
+    }
+
+    /// Whether a reproducer was written for the last run.
+    pub fn is_written(&self) -> bool {
+        *self.written.lock().unwrap()
+    }
+
+    /// Write a reproducer with the full pipeline and input for a failed run,
+    /// if none was written when the failure was reported to the instrumentation
+    /// (e.g. when a `run_after_pass` hook of another instrumentation fails).
+    pub(crate) fn write_error(&self, err: &Report) {
+        let note = err.chain().map(|e| e.to_string()).collect::<Vec<_>>();
+        self.write(None, &note.join(": "));
+    }
+
+    fn write(&self, snapshot: Option<(String, String)>, note: &str) {
+        let mut written = self.written.lock().unwrap();
+        if *written {
+            return;
+        }
+        let (pipeline, ir) = match snapshot.or_else(|| self.input.lock().unwrap().clone()) {
+            None => return,
+            Some(v) => v,
+        };
+        let contents = format!(
+            "{}'{}'\n// note: {}\n{}\n",
+            PIPELINE_PREFIX,
+            pipeline,
+            strip_colors(note).replace('\n', "\n// "),
+            strip_colors(&ir)
+        );
+        match std::fs::write(&self.path, contents) {
+            Ok(()) => *written = true,
+            Err(e) => tracing::error!(
+                "Failed to write crash reproducer to {}: {}",
+                self.path.display(),
+                e
+            ),
+        }
+    }
+}
+
+impl PassInstrumentation for CrashReproducer {
+    fn run_before_pass(&self, pass: &dyn OperationPass, op: &Operation) -> Result<(), Report> {
+        if self.local && is_isolated(op) {
+            let pipeline = format!(
+                "{}({}{})",
+                op.get_intrinsic().get_unique_id(),
+                pass.get_argument(),
+                pass.get_options()
+            );
+            self.snapshots
+                .lock()
+                .unwrap()
+                .insert(std::thread::current().id(), (pipeline, print_ir(op)));
+        }
+        Ok(())
+    }
+
+    fn run_after_pass(&self, _pass: &dyn OperationPass, _op: &Operation) -> Result<(), Report> {
+        if self.local {
+            self.snapshots
+                .lock()
+                .unwrap()
+                .remove(&std::thread::current().id());
+        }
+        Ok(())
+    }
+
+    fn run_after_pass_failed(&self, pass: &dyn OperationPass, _op: &Operation, err: &Report) {
+        let snapshot = match self.local {
+            true => self
+                .snapshots
+                .lock()
+                .unwrap()
+                .remove(&std::thread::current().id()),
+            false => None,
+        };
+        self.write(snapshot, &format!("{} failed: {}", pass.get_name(), err));
+    }
+}
+
+fn read_reproducer(path: &Path) -> Result<String, Report> {
+    match std::fs::read_to_string(path) {
+        Ok(v) => Ok(v),
+        Err(e) => bail!(format!(
+            "Failed to read reproducer {}: {}",
+            Paint::magenta(path.display()),
+            e
+        )),
+    }
+}
+
+/// Read the pipeline specification from a reproducer file
+/// written by [`CrashReproducer`].
+pub fn read_reproducer_pipeline(path: impl AsRef<Path>) -> Result<String, Report> {
+    let path = path.as_ref();
+    let contents = read_reproducer(path)?;
+    match contents
+        .lines()
+        .find_map(|l| l.strip_prefix(PIPELINE_PREFIX))
+    {
+        Some(v) => Ok(v.trim_matches('\'').to_string()),
+        None => bail!(format!(
+            "No pipeline configuration found in reproducer {}.",
+            Paint::magenta(path.display())
+        )),
+    }
+}
+
+/// Read the input operation from a reproducer file written by [`CrashReproducer`].
+/// The intrinsics and attributes of the operation must be registered.
+pub fn read_reproducer_input(path: impl AsRef<Path>) -> Result<Operation, Report> {
+    let path = path.as_ref();
+    let contents = read_reproducer(path)?;
+    parse_generic(&contents).wrap_err_with(|| {
+        format!(
+            "Failed to read the input of reproducer {}.",
+            Paint::magenta(path.display())
+        )
+    })
+}
@@ -0,0 +1,281 @@
+use crate::core::builder::OperationBuilder;
+use crate::core::ir::{Operation, SupportsInterfaceTraits, Var};
+use crate::core::region::Region;
+use color_eyre::{eyre::bail, Report};
+
+/// A location in a [`Region`] where an [`IrRewriter`]
+/// will place newly created operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertionPoint {
+    BlockStart(usize),
+    BlockEnd(usize),
+    Before(Var),
+    After(Var),
+}
+
+/// Callbacks which are notified when an [`IrRewriter`]
+/// changes the IR. All methods default to doing nothing.
+pub trait RewriteListener {
+    fn notify_inserted(&mut self, _v: Var, _op: &Operation) {}
+    fn notify_replaced(&mut self, _v: Var, _with: Var) {}
+    fn notify_erased(&mut self, _v: Var, _op: &Operation) {}
+    fn notify_modified(&mut self, _v: Var, _op: &Operation) {}
+}
+
+/// A rewriting interface which mutates the regions of an existing [`Operation`].
+///
+/// Unlike [`OperationBuilder`], which constructs a fresh operation
+/// and can only append, the rewriter tracks an [`InsertionPoint`]
+/// and supports replacing, erasing and moving operations in place.
+/// Every change is reported to the registered [`RewriteListener`] instances.
+pub struct IrRewriter<'a> {
+    op: &'a mut Operation,
+    region: usize,
+    insertion_point: Option<InsertionPoint>,
+    listeners: Vec<Box<dyn RewriteListener + 'a>>,
+}
+
+impl<'a> IrRewriter<'a> {
+    pub fn new(op: &'a mut Operation) -> IrRewriter<'a> {
+        IrRewriter {
+            op,
+            region: 0,
+            insertion_point: None,
+            listeners: Vec::new(),
+        }
+    }
+
+    pub fn add_listener(&mut self, l: Box<dyn RewriteListener + 'a>) {
+        self.listeners.push(l);
+    }
+
+    pub fn get_operation(&self) -> &Operation {
+        self.op
+    }
+
+    pub fn get_operation_mut(&mut self) -> &mut Operation {
+        self.op
+    }
+
+    /// Select the region (by index) which the rewriter operates on.
+    /// This clears the insertion point.
+    pub fn set_region(&mut self, ind: usize) -> Result<(), Report> {
+        if ind >= self.op.get_regions().len() {
+            bail!(format!(
+                "{} has no region with index {}.",
+                self.op.get_intrinsic(),
+                ind
+            ))
+        }
+        self.region = ind;
+        self.insertion_point = None;
+        Ok(())
+    }
+
+    pub fn get_region_index(&self) -> usize {
+        self.region
+    }
+
+    pub fn get_region(&self) -> &Region {
+        &self.op.get_regions()[self.region]
+    }
+
+    fn get_region_mut(&mut self) -> &mut Region {
+        &mut self.op.get_regions_mut()[self.region]
+    }
+
+    pub fn get_op(&self, v: Var) -> Option<&Operation> {
+        self.get_region().get_op(v).map(|(_, op)| op)
+    }
+
+    /// Mutable access to an operation, e.g. to construct
+    /// a nested `IrRewriter` over its regions.
+    ///
+    /// Changes made through this reference are not reported to listeners,
+    /// prefer [`IrRewriter::modify_op`] for in-place updates.
+    pub fn get_op_mut(&mut self, v: Var) -> Option<&mut Operation> {
+        self.get_region_mut().get_op_mut(v).map(|(_, op)| op)
+    }
+
+    pub fn set_insertion_point(&mut self, ip: InsertionPoint) {
+        self.insertion_point = Some(ip);
+    }
+
+    pub fn get_insertion_point(&self) -> Option<InsertionPoint> {
+        self.insertion_point
+    }
+
+    pub fn set_insertion_point_to_start(&mut self, blk: usize) {
+        self.set_insertion_point(InsertionPoint::BlockStart(blk));
+    }
+
+    pub fn set_insertion_point_to_end(&mut self, blk: usize) {
+        self.set_insertion_point(InsertionPoint::BlockEnd(blk));
+    }
+
+    pub fn set_insertion_point_before(&mut self, v: Var) {
+        self.set_insertion_point(InsertionPoint::Before(v));
+    }
+
+    pub fn set_insertion_point_after(&mut self, v: Var) {
+        self.set_insertion_point(InsertionPoint::After(v));
+    }
+
+    /// Resolve an insertion point to a `(block, position)` pair.
+    fn resolve(&self, ip: InsertionPoint) -> Result<(usize, usize), Report> {
+        let r = self.get_region();
+        let nblocks = r.get_blocks().len();
+        match ip {
+            InsertionPoint::BlockStart(b) | InsertionPoint::BlockEnd(b) if b >= nblocks => {
+                bail!(format!("Block {} does not exist in region.", b))
+            }
+            InsertionPoint::BlockStart(b) => Ok((b, 0)),
+            InsertionPoint::BlockEnd(b) => Ok((b, r.get_block(b).get_ops().len())),
+            InsertionPoint::Before(v) => match r.get_op_position(v) {
+                None => bail!(format!("{} is not defined by an operation in region.", v)),
+                Some(pos) => Ok(pos),
+            },
+            InsertionPoint::After(v) => match r.get_op_position(v) {
+                None => bail!(format!("{} is not defined by an operation in region.", v)),
+                Some((b, i)) => Ok((b, i + 1)),
+            },
+        }
+    }
+
+    /// Insert an operation at the current insertion point.
+    /// The insertion point is moved so that subsequent operations
+    /// are inserted after this one.
+    pub fn insert(&mut self, op: Operation) -> Result<Var, Report> {
+        let ip = match self.insertion_point {
+            None => bail!("Rewriter insertion point is not set."),
+            Some(ip) => ip,
+        };
+        let (blk, idx) = self.resolve(ip)?;
+        let v = self.get_region_mut().insert_op(blk, idx, op);
+        match ip {
+            InsertionPoint::BlockStart(_) | InsertionPoint::After(_) => {
+                self.insertion_point = Some(InsertionPoint::After(v))
+            }
+            InsertionPoint::BlockEnd(_) | InsertionPoint::Before(_) => (),
+        };
+        let (_, op) = self.op.get_regions()[self.region].get_op(v).unwrap();
+        for l in self.listeners.iter_mut() {
+            l.notify_inserted(v, op);
+        }
+        Ok(v)
+    }
+
+    /// Finish (and verify) an [`OperationBuilder`], and insert the
+    /// resulting operation at the current insertion point.
+    pub fn create(&mut self, b: OperationBuilder) -> Result<Var, Report> {
+        let op = b.finish()?;
+        self.insert(op)
+    }
+
+    /// Replace all uses of `from` with `to`.
+    pub fn replace_all_uses_with(&mut self, from: Var, to: Var) {
+        let users = self.get_region_mut().replace_all_uses(from, to);
+        for u in users {
+            self.notify_modified(u);
+        }
+    }
+
+    /// Replace all uses of the operation defining `v` with `with`,
+    /// and erase it.
+    pub fn replace_op(&mut self, v: Var, with: Var) -> Result<(), Report> {
+        if v == with {
+            bail!(format!("Can't replace {} with itself.", v))
+        }
+        for l in self.listeners.iter_mut() {
+            l.notify_replaced(v, with);
+        }
+        self.replace_all_uses_with(v, with);
+        self.erase_op(v)
+    }
+
+    /// Create a new operation before `v`, replace all uses of `v` with it,
+    /// and erase `v`. Returns the `Var` of the new operation.
+    pub fn replace_op_with_new(&mut self, v: Var, b: OperationBuilder) -> Result<Var, Report> {
+        let ip = self.insertion_point;
+        self.set_insertion_point_before(v);
+        let new = self.create(b);
+        self.insertion_point = ip;
+        let new = new?;
+        self.replace_op(v, new)?;
+        Ok(new)
+    }
+
+    /// Erase the operation defining `v`. The operation must not have uses.
+    pub fn erase_op(&mut self, v: Var) -> Result<(), Report> {
+        let uses = self.get_region().get_uses(v);
+        if !uses.is_empty() {
+            bail!(format!(
+                "Can't erase {}, which is still used by {:?}.",
+                v, uses
+            ))
+        }
+        match self.get_region_mut().remove_op(v) {
+            None => bail!(format!("{} is not defined by an operation in region.", v)),
+            Some(op) => {
+                if self.insertion_point == Some(InsertionPoint::Before(v))
+                    || self.insertion_point == Some(InsertionPoint::After(v))
+                {
+                    self.insertion_point = None;
+                }
+                for l in self.listeners.iter_mut() {
+                    l.notify_erased(v, &op);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Move the operation defining `v` to the current insertion point.
+    /// The `Var` of the operation is preserved.
+    pub fn move_op(&mut self, v: Var) -> Result<(), Report> {
+        let ip = match self.insertion_point {
+            None => bail!("Rewriter insertion point is not set."),
+            Some(ip) => ip,
+        };
+        if ip == InsertionPoint::Before(v) || ip == InsertionPoint::After(v) {
+            return Ok(());
+        }
+        // Resolve the insertion point before removing the operation,
+        // so that a failure leaves the region unchanged.
+        let (b, i) = match self.get_region().get_op_position(v) {
+            None => bail!(format!("{} is not defined by an operation in region.", v)),
+            Some(pos) => pos,
+        };
+        let (blk, mut idx) = self.resolve(ip)?;
+        if blk == b && idx > i {
+            idx -= 1;
+        }
+        let op = self.get_region_mut().remove_op(v).unwrap();
+        self.get_region_mut().place_op(v, blk, idx, op);
+        if let InsertionPoint::BlockStart(_) | InsertionPoint::After(_) = ip {
+            self.insertion_point = Some(InsertionPoint::After(v));
+        }
+        self.notify_modified(v);
+        Ok(())
+    }
+
+    /// Modify the operation defining `v` in place, notifying listeners.
+    pub fn modify_op<F>(&mut self, v: Var, f: F) -> Result<(), Report>
+    where
+        F: FnOnce(&mut Operation),
+    {
+        match self.get_op_mut(v) {
+            None => bail!(format!("{} is not defined by an operation in region.", v)),
+            Some(op) => f(op),
+        };
+        self.notify_modified(v);
+        Ok(())
+    }
+
+    fn notify_modified(&mut self, v: Var) {
+        let (_, op) = self.op.get_regions()[self.region].get_op(v).unwrap();
+        for l in self.listeners.iter_mut() {
+            l.notify_modified(v, op);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use crate::core::instrumentation::PassInstrumentation;
+use crate::core::ir::Operation;
+use crate::core::pass_manager::OperationPass;
+use color_eyre::Report;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use yansi::Paint;
+
+/// A named counter, declared as a field of an [`OperationPass`]
+/// and returned from [`OperationPass::get_statistics`].
+///
+/// Counters are collected (and reset) after each run of the pass
+/// by [`PassStatistics`].
+#[derive(Debug)]
+pub struct Statistic {
+    name: &'static str,
+    description: &'static str,
+    value: AtomicUsize,
+}
+
+impl Statistic {
+    pub const fn new(name: &'static str, description: &'static str) -> Statistic {
+        Statistic {
+            name,
+            description,
+            value: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn get_description(&self) -> &'static str {
+        self.description
+    }
+
+    pub fn add(&self, n: usize) {
+        self.value.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn inc(&self) {
+        self.add(1)
+    }
+
+    pub fn get(&self) -> usize {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    /// Get the value, and reset the counter to zero.
+    pub fn take(&self) -> usize {
+        self.value.swap(0, Ordering::Relaxed)
+    }
+}
+
+impl Clone for Statistic {
+    fn clone(&self) -> Self {
+        Statistic {
+            name: self.name,
+            description: self.description,
+            value: AtomicUsize::new(self.get()),
+        }
+    }
+}
+
+/// The `(name, description, value)` of each counter of a pass.
+pub type StatisticValues = Vec<(&'static str, &'static str, usize)>;
+
+/// Aggregates the [`Statistic`]s of passes (by [`OperationPass::get_name`])
+/// across every operation the pass is applied to, including runs in
+/// nested pass managers and on other threads.
+///
+/// Displaying `PassStatistics` prints a table,
+/// [`PassStatistics::to_json`] prints JSON.
+#[derive(Debug, Default)]
+pub struct PassStatistics {
+    stats: Mutex<Vec<(String, StatisticValues)>>,
+}
+
+impl PassStatistics {
+    pub fn new() -> PassStatistics {
+        PassStatistics::default()
+    }
+
+    /// Get the statistics of each pass, in the order the passes were first run.
+    pub fn get_statistics(&self) -> Vec<(String, StatisticValues)> {
+        self.stats.lock().unwrap().to_vec()
+    }
+
+    pub fn get(&self, pass: &str, name: &str) -> Option<usize> {
+        let stats = self.stats.lock().unwrap();
+        let (_, counters) = stats.iter().find(|(p, _)| p == pass)?;
+        counters
+            .iter()
+            .find(|(n, _, _)| *n == name)
+            .map(|(_, _, v)| *v)
+    }
+
+    fn collect(&self, pass: &dyn OperationPass) {
+        let counters = pass.get_statistics();
+        if counters.is_empty() {
+            return;
+        }
+        let name = pass.get_name();
+        let mut stats = self.stats.lock().unwrap();
+        let ind = match stats.iter().position(|(p, _)| *p == name) {
+            Some(ind) => ind,
+            None => {
+                stats.push((name, Vec::new()));
+                stats.len() - 1
+            }
+        };
+        let entry = &mut stats[ind].1;
+        for stat in counters.iter() {
+            let v = stat.take();
+            match entry.iter_mut().find(|(n, _, _)| *n == stat.get_name()) {
+                Some((_, _, total)) => *total += v,
+                None => entry.push((stat.get_name(), stat.get_description(), v)),
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        fn escape(s: &str) -> String {
+            s.replace('\\', "\\\\").replace('"', "\\\"")
+        }
+        let passes = self
+            .get_statistics()
+            .iter()
+            .map(|(pass, counters)| {
+                let counters = counters
+                    .iter()
+                    .map(|(n, d, v)| {
+                        format!(
+                            "{{\"name\": \"{}\", \"description\": \"{}\", \"value\": {}}}",
+                            escape(n),
+                            escape(d),
+                            v
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                format!(
+                    "{{\"pass\": \"{}\", \"statistics\": [{}]}}",
+                    escape(pass),
+                    counters.join(", ")
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("[{}]", passes.join(", "))
+    }
+}
+
+impl PassInstrumentation for PassStatistics {
+    fn run_after_pass(&self, pass: &dyn OperationPass, _op: &Operation) -> Result<(), Report> {
+        self.collect(pass);
+        Ok(())
+    }
+
+    fn run_after_pass_failed(&self, pass: &dyn OperationPass, _op: &Operation, _err: &Report) {
+        self.collect(pass);
+    }
+}
+
+impl fmt::Display for PassStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "({})", Paint::green("PassStatistics").bold())?;
+        for (pass, counters) in self.get_statistics().iter() {
+            writeln!(f, "  {}", Paint::magenta(pass))?;
+            let width = counters.iter().map(|(n, _, _)| n.len()).max().unwrap_or(0);
+            for (name, description, v) in counters.iter() {
+                writeln!(
+                    f,
+                    "    {:>8} {:width$} - {}",
+                    v,
+                    name,
+                    description,
+                    width = width
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
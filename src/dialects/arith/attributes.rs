@@ -0,0 +1,101 @@
+use crate::core::*;
+use crate::dialects::arith::intrinsics::wrap;
+use crate::*;
+use std::fmt;
+use yansi::Paint;
+
+/// The comparison predicate for [`Cmpi`](crate::dialects::arith::Cmpi).
+///
+/// Signed predicates interpret integer operands in two's complement,
+/// unsigned predicates interpret them as unsigned integers of the operand width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpiPredicate {
+    Eq,
+    Ne,
+    Slt,
+    Sle,
+    Sgt,
+    Sge,
+    Ult,
+    Ule,
+    Ugt,
+    Uge,
+}
+
+impl CmpiPredicate {
+    /// Evaluate the predicate on two integers of width `width`.
+    pub fn evaluate(&self, l: i64, r: i64, width: usize) -> bool {
+        let (l, r) = (wrap(l, width), wrap(r, width));
+        let mask = match width {
+            0 | 64.. => u64::MAX,
+            w => (1 << w) - 1,
+        };
+        let (ul, ur) = (l as u64 & mask, r as u64 & mask);
+        match self {
+            CmpiPredicate::Eq => l == r,
+            CmpiPredicate::Ne => l != r,
+            CmpiPredicate::Slt => l < r,
+            CmpiPredicate::Sle => l <= r,
+            CmpiPredicate::Sgt => l > r,
+            CmpiPredicate::Sge => l >= r,
+            CmpiPredicate::Ult => ul < ur,
+            CmpiPredicate::Ule => ul <= ur,
+            CmpiPredicate::Ugt => ul > ur,
+            CmpiPredicate::Uge => ul >= ur,
+        }
+    }
+}
+
+impl CmpiPredicate {
+    const ALL: [CmpiPredicate; 10] = [
+        CmpiPredicate::Eq,
+        CmpiPredicate::Ne,
+        CmpiPredicate::Slt,
+        CmpiPredicate::Sle,
+        CmpiPredicate::Sgt,
+        CmpiPredicate::Sge,
+        CmpiPredicate::Ult,
+        CmpiPredicate::Ule,
+        CmpiPredicate::Ugt,
+        CmpiPredicate::Uge,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            CmpiPredicate::Eq => "eq",
+            CmpiPredicate::Ne => "ne",
+            CmpiPredicate::Slt => "slt",
+            CmpiPredicate::Sle => "sle",
+            CmpiPredicate::Sgt => "sgt",
+            CmpiPredicate::Sge => "sge",
+            CmpiPredicate::Ult => "ult",
+            CmpiPredicate::Ule => "ule",
+            CmpiPredicate::Ugt => "ugt",
+            CmpiPredicate::Uge => "uge",
+        }
+    }
+}
+
+impl fmt::Display for CmpiPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Paint::blue(self.as_str()).bold())
+    }
+}
+
+impl GenericAttribute for CmpiPredicate {
+    fn print_generic(&self) -> String {
+        self.as_str().to_string()
+    }
+
+    fn parse_generic(src: &str) -> Result<Self, Report> {
+        match CmpiPredicate::ALL.iter().find(|p| p.as_str() == src) {
+            None => bail!(format!("Unknown comparison predicate `{}`.", src)),
+            Some(p) => Ok(*p),
+        }
+    }
+}
+
+attribute! {
+    CmpiPredicate: "arith.predicate",
+    trait: ProvidesCmpiPredicateAttr
+}
@@ -0,0 +1,154 @@
+use crate::core::*;
+use crate::dialects::arith::attributes::ProvidesCmpiPredicateAttr;
+use crate::dialects::arith::intrinsics::*;
+use crate::*;
+use std::sync::Once;
+
+// Booleans are integers of width 1.
+fn get_integer(v: &RuntimeValue) -> Option<(i64, usize)> {
+    match v {
+        RuntimeValue::Bool(b) => Some((*b as i64, 1)),
+        RuntimeValue::Integer(v, w) => Some((*v, *w)),
+        _ => None,
+    }
+}
+
+fn integer(v: i64, width: usize) -> RuntimeValue {
+    match width {
+        1 => RuntimeValue::Bool(v & 1 != 0),
+        w => RuntimeValue::Integer(wrap(v, w), w),
+    }
+}
+
+// Two integer operands of the same width.
+fn get_integers(op: &Operation, args: &[&RuntimeValue]) -> Result<(i64, i64, usize), Report> {
+    match args {
+        [l, r] => match (get_integer(l), get_integer(r)) {
+            (Some((l, w)), Some((r, v))) if w == v => Ok((l, r, w)),
+            _ => bail!(format!(
+                "{} requires integer operands of the same width, got ({}, {}).",
+                op.get_intrinsic(),
+                l,
+                r
+            )),
+        },
+        _ => bail!(format!("{} requires 2 operands.", op.get_intrinsic())),
+    }
+}
+
+// Two float operands of the same width.
+fn get_floats(op: &Operation, args: &[&RuntimeValue]) -> Result<(f64, f64, usize), Report> {
+    match args {
+        [RuntimeValue::Float(l, w), RuntimeValue::Float(r, v)] if w == v => Ok((*l, *r, *w)),
+        [l, r] => bail!(format!(
+            "{} requires float operands of the same width, got ({}, {}).",
+            op.get_intrinsic(),
+            l,
+            r
+        )),
+        _ => bail!(format!("{} requires 2 operands.", op.get_intrinsic())),
+    }
+}
+
+impl ConcreteSemantics for Addf {
+    fn evaluate(
+        &self,
+        op: &Operation,
+        args: Vec<&RuntimeValue>,
+        _heap: &mut Heap,
+    ) -> Result<Option<RuntimeValue>, Report> {
+        let (l, r, w) = get_floats(op, &args)?;
+        Ok(Some(RuntimeValue::Float(round(l + r, w), w)))
+    }
+}
+
+impl ConcreteSemantics for Addi {
+    fn evaluate(
+        &self,
+        op: &Operation,
+        args: Vec<&RuntimeValue>,
+        _heap: &mut Heap,
+    ) -> Result<Option<RuntimeValue>, Report> {
+        let (l, r, w) = get_integers(op, &args)?;
+        Ok(Some(integer(l.wrapping_add(r), w)))
+    }
+}
+
+impl ConcreteSemantics for Andi {
+    fn evaluate(
+        &self,
+        op: &Operation,
+        args: Vec<&RuntimeValue>,
+        _heap: &mut Heap,
+    ) -> Result<Option<RuntimeValue>, Report> {
+        let (l, r, w) = get_integers(op, &args)?;
+        Ok(Some(integer(l & r, w)))
+    }
+}
+
+// Only integers and floats of width 32 (or 64) can be cast.
+impl ConcreteSemantics for Bitcast {
+    fn evaluate(
+        &self,
+        op: &Operation,
+        args: Vec<&RuntimeValue>,
+        _heap: &mut Heap,
+    ) -> Result<Option<RuntimeValue>, Report> {
+        let v = match args.as_slice() {
+            [RuntimeValue::Integer(v, 64)] => RuntimeValue::Float(f64::from_bits(*v as u64), 64),
+            [RuntimeValue::Integer(v, 32)] => {
+                RuntimeValue::Float(f32::from_bits(*v as u32) as f64, 32)
+            }
+            [RuntimeValue::Float(v, 64)] => RuntimeValue::Integer(v.to_bits() as i64, 64),
+            [RuntimeValue::Float(v, 32)] => {
+                RuntimeValue::Integer((*v as f32).to_bits() as i32 as i64, 32)
+            }
+            _ => bail!(format!(
+                "{} requires an integer (or float) operand of width 32 or 64.",
+                op.get_intrinsic()
+            )),
+        };
+        Ok(Some(v))
+    }
+}
+
+impl ConcreteSemantics for Cmpi {
+    fn evaluate(
+        &self,
+        op: &Operation,
+        args: Vec<&RuntimeValue>,
+        _heap: &mut Heap,
+    ) -> Result<Option<RuntimeValue>, Report> {
+        let (l, r, w) = get_integers(op, &args)?;
+        let b = self.get_value(op).evaluate(l, r, w);
+        Ok(Some(RuntimeValue::Bool(b)))
+    }
+}
+
+impl ConcreteSemantics for Divf {
+    fn evaluate(
+        &self,
+        op: &Operation,
+        args: Vec<&RuntimeValue>,
+        _heap: &mut Heap,
+    ) -> Result<Option<RuntimeValue>, Report> {
+        let (l, r, w) = get_floats(op, &args)?;
+        Ok(Some(RuntimeValue::Float(round(l / r, w), w)))
+    }
+}
+
+/// Registers the [`ConcreteSemantics`] of the intrinsics
+/// of this dialect (as dynamic interfaces).
+pub fn register_concrete_semantics() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        dynamic_interfaces! {
+            Addf: dyn ConcreteSemantics;
+            Addi: dyn ConcreteSemantics;
+            Andi: dyn ConcreteSemantics;
+            Bitcast: dyn ConcreteSemantics;
+            Cmpi: dyn ConcreteSemantics;
+            Divf: dyn ConcreteSemantics;
+        }
+    });
+}
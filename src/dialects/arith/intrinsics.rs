@@ -1,14 +1,42 @@
 use crate::core::*;
+use crate::dialects::arith::attributes::*;
 use crate::dialects::arith::traits::*;
-use crate::dialects::builtin::NonVariadic;
+use crate::dialects::builtin::{ConstantAttr, FoldResult, Foldable, MemoryEffects, NonVariadic};
 use crate::*;
+use std::sync::Once;
+
+// Wrap an integer to `width` bits, sign extending the result.
+pub(crate) fn wrap(v: i64, width: usize) -> i64 {
+    match width {
+        0 | 64.. => v,
+        w => (v << (64 - w)) >> (64 - w),
+    }
+}
+
+// Whether every bit of an integer constant is set (`true` for width 1).
+fn all_ones(c: &ConstantAttr) -> bool {
+    match c {
+        ConstantAttr::Integer(v, w) => {
+            ConstantAttr::integer(*v, *w) == ConstantAttr::integer(-1, *w)
+        }
+        _ => false,
+    }
+}
+
+// Round a float to the precision of a float of `width` bits.
+pub(crate) fn round(v: f64, width: usize) -> f64 {
+    match width {
+        32 => v as f32 as f64,
+        _ => v,
+    }
+}
 
 intrinsic! {
     /// Floating point addition operation.
     /// Supports elementwise mapping over rank matching tensors.
     Addf: ["arith", "addf"],
-    [Elementwise],
-    extern: [NonVariadic]
+    [Elementwise, MemoryEffects],
+    extern: [NonVariadic, Foldable]
 }
 
 impl NonVariadic for Addf {
@@ -36,10 +64,25 @@ impl Addf {
     }
 }
 
+impl Foldable for Addf {
+    fn fold(
+        &self,
+        _op: &dyn SupportsInterfaceTraits,
+        operands: &[Option<&ConstantAttr>],
+    ) -> Option<FoldResult> {
+        match operands {
+            [Some(ConstantAttr::Float(l, w)), Some(ConstantAttr::Float(r, v))] if w == v => Some(
+                FoldResult::Constant(ConstantAttr::Float(round(l + r, *w), *w)),
+            ),
+            _ => None,
+        }
+    }
+}
+
 intrinsic! {
     Addi: ["arith", "addi"],
-    [Elementwise, Commutative],
-    extern: [NonVariadic]
+    [Elementwise, Commutative, MemoryEffects],
+    extern: [NonVariadic, Foldable]
 }
 
 impl NonVariadic for Addi {
@@ -67,32 +110,210 @@ impl Addi {
     }
 }
 
+impl Foldable for Addi {
+    fn fold(
+        &self,
+        op: &dyn SupportsInterfaceTraits,
+        operands: &[Option<&ConstantAttr>],
+    ) -> Option<FoldResult> {
+        match operands {
+            [Some(ConstantAttr::Integer(l, w)), Some(ConstantAttr::Integer(r, v))] if w == v => {
+                let c = ConstantAttr::integer(l.wrapping_add(*r), *w);
+                Some(FoldResult::Constant(c))
+            }
+            [_, Some(ConstantAttr::Integer(0, _))] => Some(FoldResult::Var(op.get_operands()[0])),
+            [Some(ConstantAttr::Integer(0, _)), _] => Some(FoldResult::Var(op.get_operands()[1])),
+            _ => None,
+        }
+    }
+}
+
 intrinsic! {
     Andi: ["arith", "andi"],
-    [Elementwise, Commutative],
-    extern: []
+    [Elementwise, Commutative, MemoryEffects],
+    extern: [Foldable]
+}
+
+impl Foldable for Andi {
+    fn fold(
+        &self,
+        op: &dyn SupportsInterfaceTraits,
+        operands: &[Option<&ConstantAttr>],
+    ) -> Option<FoldResult> {
+        let (x, y) = match op.get_operands() {
+            [x, y] => (*x, *y),
+            _ => return None,
+        };
+        match operands {
+            [Some(ConstantAttr::Integer(l, w)), Some(ConstantAttr::Integer(r, v))] if w == v => {
+                Some(FoldResult::Constant(ConstantAttr::integer(l & r, *w)))
+            }
+            [_, Some(ConstantAttr::Integer(0, w))] | [Some(ConstantAttr::Integer(0, w)), _] => {
+                Some(FoldResult::Constant(ConstantAttr::Integer(0, *w)))
+            }
+            [_, Some(c)] if all_ones(c) => Some(FoldResult::Var(x)),
+            [Some(c), _] if all_ones(c) => Some(FoldResult::Var(y)),
+            _ if x == y => Some(FoldResult::Var(x)),
+            _ => None,
+        }
+    }
+}
+
+impl Andi {
+    pub fn get_builder(
+        &self,
+        operands: Vec<Var>,
+        loc: LocationInfo,
+    ) -> Result<OperationBuilder, Report> {
+        let intr = Box::new(Andi);
+        let mut b = OperationBuilder::default(intr, loc);
+        b.set_operands(operands);
+        Ok(b)
+    }
 }
 
 intrinsic! {
+    /// Reinterprets the bits of an integer as a float
+    /// (or vice versa) of the same width.
     Bitcast: ["arith", "bitcast"],
-    [Elementwise],
-    extern: []
+    [Elementwise, MemoryEffects],
+    extern: [Foldable]
+}
+
+impl Foldable for Bitcast {
+    fn fold(
+        &self,
+        _op: &dyn SupportsInterfaceTraits,
+        operands: &[Option<&ConstantAttr>],
+    ) -> Option<FoldResult> {
+        let c = match operands {
+            [Some(ConstantAttr::Integer(v, 64))] => {
+                ConstantAttr::Float(f64::from_bits(*v as u64), 64)
+            }
+            [Some(ConstantAttr::Integer(v, 32))] => {
+                ConstantAttr::Float(f32::from_bits(*v as u32) as f64, 32)
+            }
+            [Some(ConstantAttr::Float(v, 64))] => ConstantAttr::Integer(v.to_bits() as i64, 64),
+            [Some(ConstantAttr::Float(v, 32))] => {
+                ConstantAttr::Integer((*v as f32).to_bits() as i32 as i64, 32)
+            }
+            _ => return None,
+        };
+        Some(FoldResult::Constant(c))
+    }
+}
+
+impl Bitcast {
+    pub fn get_builder(&self, operand: Var, loc: LocationInfo) -> Result<OperationBuilder, Report> {
+        let intr = Box::new(Bitcast);
+        let mut b = OperationBuilder::default(intr, loc);
+        b.push_operand(operand);
+        Ok(b)
+    }
 }
 
 intrinsic! {
     Cmpf: ["arith", "cmpf"],
-    [Elementwise],
+    [Elementwise, MemoryEffects],
     extern: []
 }
 
 intrinsic! {
+    /// Integer comparison, the comparison is selected by
+    /// a [`CmpiPredicate`] attribute. Produces an integer of width 1.
     Cmpi: ["arith", "cmpi"],
-    [Elementwise],
-    extern: []
+    [Elementwise, MemoryEffects, ProvidesCmpiPredicateAttr],
+    extern: [Foldable]
+}
+
+impl Foldable for Cmpi {
+    fn fold(
+        &self,
+        op: &dyn SupportsInterfaceTraits,
+        operands: &[Option<&ConstantAttr>],
+    ) -> Option<FoldResult> {
+        let pred = self.get_value(op);
+        let b = match (op.get_operands(), operands) {
+            (_, [Some(ConstantAttr::Integer(l, w)), Some(ConstantAttr::Integer(r, v))])
+                if w == v =>
+            {
+                pred.evaluate(*l, *r, *w)
+            }
+            ([x, y], _) if x == y => matches!(
+                pred,
+                CmpiPredicate::Eq
+                    | CmpiPredicate::Sle
+                    | CmpiPredicate::Sge
+                    | CmpiPredicate::Ule
+                    | CmpiPredicate::Uge
+            ),
+            _ => return None,
+        };
+        Some(FoldResult::Constant(ConstantAttr::integer(b as i64, 1)))
+    }
+}
+
+impl Cmpi {
+    pub fn get_builder(
+        &self,
+        pred: CmpiPredicate,
+        operands: Vec<Var>,
+        loc: LocationInfo,
+    ) -> Result<OperationBuilder, Report> {
+        let intr = Box::new(Cmpi);
+        let mut b = OperationBuilder::default(intr, loc);
+        b.set_operands(operands);
+        b.insert_attr("arith.predicate", Box::new(pred));
+        Ok(b)
+    }
 }
 
 intrinsic! {
     Divf: ["arith", "divf"],
-    [Elementwise],
-    extern: []
+    [Elementwise, MemoryEffects],
+    extern: [Foldable]
+}
+
+impl Foldable for Divf {
+    fn fold(
+        &self,
+        _op: &dyn SupportsInterfaceTraits,
+        operands: &[Option<&ConstantAttr>],
+    ) -> Option<FoldResult> {
+        match operands {
+            [Some(ConstantAttr::Float(l, w)), Some(ConstantAttr::Float(r, v))] if w == v => Some(
+                FoldResult::Constant(ConstantAttr::Float(round(l / r, *w), *w)),
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl Divf {
+    pub fn get_builder(
+        &self,
+        operands: Vec<Var>,
+        loc: LocationInfo,
+    ) -> Result<OperationBuilder, Report> {
+        let intr = Box::new(Divf);
+        let mut b = OperationBuilder::default(intr, loc);
+        b.set_operands(operands);
+        Ok(b)
+    }
+}
+
+/// Registers the intrinsics and attributes of this dialect,
+/// so that its operations can be read by [`parse_generic`].
+pub fn register_arith_dialect() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        register_intrinsic(Box::new(Addf));
+        register_intrinsic(Box::new(Addi));
+        register_intrinsic(Box::new(Andi));
+        register_intrinsic(Box::new(Bitcast));
+        register_intrinsic(Box::new(Cmpf));
+        register_intrinsic(Box::new(Cmpi));
+        register_intrinsic(Box::new(Divf));
+        register_attribute::<CmpiPredicate>("arith.predicate");
+    });
 }
@@ -0,0 +1,350 @@
+use crate::core::*;
+use crate::dialects::arith::attributes::{CmpiPredicate, ProvidesCmpiPredicateAttr};
+use crate::dialects::arith::intrinsics::*;
+use crate::dialects::builtin::{BuiltinLattice, ConstantLattice, TypeLattice};
+use crate::*;
+use std::sync::Once;
+
+macro_rules! constant_semantics {
+    ($($intr:ident),*) => {
+        $(impl LatticeSemantics<ConstantLattice> for $intr {
+            fn propagate(
+                &self,
+                op: &Operation,
+                vtypes: Vec<&ConstantLattice>,
+            ) -> Result<ConstantLattice, Report> {
+                Ok(ConstantLattice::fold(self, op, vtypes))
+            }
+        })*
+
+        /// Registers the [`ConstantLattice`] propagation rules for
+        /// the intrinsics of this dialect (as dynamic interfaces).
+        pub fn register_constant_semantics() {
+            static REGISTER: Once = Once::new();
+            REGISTER.call_once(|| {
+                dynamic_interfaces! {
+                    $($intr: dyn LatticeSemantics<ConstantLattice>;)*
+                }
+            });
+        }
+    };
+}
+
+constant_semantics!(Addf, Addi, Andi, Bitcast, Cmpi, Divf);
+
+// Defines the propagation rules (given the operation and the values
+// of its operands) for a lattice, and a function which registers them.
+macro_rules! lattice_semantics {
+    ($(#[$doc:meta])* $register:ident, $lattice:ty, { $($intr:ident => $rule:expr),* $(,)? }) => {
+        $(impl LatticeSemantics<$lattice> for $intr {
+            fn propagate(&self, op: &Operation, vtypes: Vec<&$lattice>) -> Result<$lattice, Report> {
+                let rule: fn(&Operation, &[&$lattice]) -> Result<$lattice, Report> = $rule;
+                rule(op, &vtypes)
+            }
+        })*
+
+        $(#[$doc])*
+        pub fn $register() {
+            static REGISTER: Once = Once::new();
+            REGISTER.call_once(|| {
+                dynamic_interfaces! {
+                    $($intr: dyn LatticeSemantics<$lattice>;)*
+                }
+            });
+        }
+    };
+}
+
+// Operations don't record the width of their integers, so the sum includes
+// the (wrapping) sum at every width which both operands may have.
+fn add_at_any_width<L: LatticeJoin>(
+    a: &L,
+    b: &L,
+    bottom: L,
+    has_width: fn(&L, usize) -> bool,
+    add: fn(&L, &L, usize) -> L,
+) -> L {
+    (1..=64)
+        .filter(|w| has_width(a, *w) && has_width(b, *w))
+        .fold(bottom, |v, w| v.join(&add(a, b, w)))
+}
+
+fn unary<'a, L>(op: &Operation, vtypes: &[&'a L]) -> Result<&'a L, Report> {
+    match vtypes {
+        [a] => Ok(*a),
+        _ => bail!(format!(
+            "{} supports a fixed number (1) of operands.",
+            op.get_intrinsic()
+        )),
+    }
+}
+
+fn binary<'a, L>(op: &Operation, vtypes: &[&'a L]) -> Result<(&'a L, &'a L), Report> {
+    match vtypes {
+        [a, b] => Ok((*a, *b)),
+        _ => bail!(format!(
+            "{} supports a fixed number (2) of operands.",
+            op.get_intrinsic()
+        )),
+    }
+}
+
+// The result of an integer comparison (`0` or `1`), given the intervals of the operands.
+fn compare(pred: &CmpiPredicate, a: &Interval, b: &Interval) -> Interval {
+    // Unsigned comparisons agree with signed comparisons on non-negative integers.
+    let unsigned = match (a, b) {
+        (Interval::Range(a, _), Interval::Range(c, _)) => *a >= 0 && *c >= 0,
+        _ => return Interval::Bottom,
+    };
+    let disjoint = a.less_than(b) == Some(true) || b.less_than(a) == Some(true);
+    let r = match pred {
+        CmpiPredicate::Eq => match (a.get_constant(), b.get_constant()) {
+            (Some(l), Some(r)) if l == r => Some(true),
+            _ => disjoint.then_some(false),
+        },
+        CmpiPredicate::Ne => match (a.get_constant(), b.get_constant()) {
+            (Some(l), Some(r)) if l == r => Some(false),
+            _ => disjoint.then_some(true),
+        },
+        CmpiPredicate::Slt => a.less_than(b),
+        CmpiPredicate::Sle => b.less_than(a).map(|b| !b),
+        CmpiPredicate::Sgt => b.less_than(a),
+        CmpiPredicate::Sge => a.less_than(b).map(|b| !b),
+        CmpiPredicate::Ult if unsigned => a.less_than(b),
+        CmpiPredicate::Ule if unsigned => b.less_than(a).map(|b| !b),
+        CmpiPredicate::Ugt if unsigned => b.less_than(a),
+        CmpiPredicate::Uge if unsigned => a.less_than(b).map(|b| !b),
+        _ => None,
+    };
+    match r {
+        Some(r) => Interval::constant(r as i64),
+        None => Interval::Range(0, 1),
+    }
+}
+
+lattice_semantics!(
+    /// Registers the [`Sign`] propagation rules for
+    /// the intrinsics of this dialect (as dynamic interfaces).
+    register_sign_semantics, Sign, {
+    Addf => |op, vtypes| {
+        let (a, b) = binary(op, vtypes)?;
+        Ok(a.add(b))
+    },
+    Addi => |op, vtypes| {
+        let (a, b) = binary(op, vtypes)?;
+        Ok(add_at_any_width(a, b, Sign::Bottom, Sign::has_width, Sign::add_wrapping))
+    },
+    Andi => |op, vtypes| {
+        let (a, b) = binary(op, vtypes)?;
+        Ok(a.and(b))
+    },
+    Bitcast => |op, vtypes| match unary(op, vtypes)? {
+        Sign::Bottom => Ok(Sign::Bottom),
+        _ => Ok(Sign::Top),
+    },
+    Cmpi => |op, vtypes| {
+        let (a, b) = binary(op, vtypes)?;
+        let pred = Cmpi.get_value(op);
+        Ok(compare(pred, &a.convert(), &b.convert()).convert())
+    },
+    Divf => |op, vtypes| match binary(op, vtypes)? {
+        (Sign::Bottom, _) | (_, Sign::Bottom) => Ok(Sign::Bottom),
+        _ => Ok(Sign::Top),
+    },
+});
+
+lattice_semantics!(
+    /// Registers the [`Interval`] propagation rules for
+    /// the intrinsics of this dialect (as dynamic interfaces).
+    /// Floating point values are not tracked (their interval is the top element),
+    /// and integer additions which may overflow (at 64 bits) are the top element.
+    register_interval_semantics, Interval, {
+    Addf => |op, vtypes| match binary(op, vtypes)? {
+        (Interval::Bottom, _) | (_, Interval::Bottom) => Ok(Interval::Bottom),
+        _ => Ok(Interval::top()),
+    },
+    Addi => |op, vtypes| {
+        let (a, b) = binary(op, vtypes)?;
+        Ok(add_at_any_width(a, b, Interval::Bottom, Interval::has_width, Interval::add))
+    },
+    Andi => |op, vtypes| {
+        let (a, b) = binary(op, vtypes)?;
+        Ok(a.and(b))
+    },
+    Bitcast => |op, vtypes| match unary(op, vtypes)? {
+        Interval::Bottom => Ok(Interval::Bottom),
+        _ => Ok(Interval::top()),
+    },
+    Cmpi => |op, vtypes| {
+        let (a, b) = binary(op, vtypes)?;
+        Ok(compare(Cmpi.get_value(op), a, b))
+    },
+    Divf => |op, vtypes| match binary(op, vtypes)? {
+        (Interval::Bottom, _) | (_, Interval::Bottom) => Ok(Interval::Bottom),
+        _ => Ok(Interval::top()),
+    },
+});
+
+lattice_semantics!(
+    /// Registers the [`KnownBits`] propagation rules for
+    /// the intrinsics of this dialect (as dynamic interfaces).
+    /// Floating point values are tracked by their bits (so bitcasts preserve known bits).
+    register_known_bits_semantics, KnownBits, {
+    Addf => |op, vtypes| match binary(op, vtypes)? {
+        (KnownBits::Bottom, _) | (_, KnownBits::Bottom) => Ok(KnownBits::Bottom),
+        _ => Ok(KnownBits::top()),
+    },
+    Addi => |op, vtypes| {
+        let (a, b) = binary(op, vtypes)?;
+        Ok(add_at_any_width(
+            a,
+            b,
+            KnownBits::Bottom,
+            KnownBits::has_width,
+            KnownBits::add_wrapping,
+        ))
+    },
+    Andi => |op, vtypes| {
+        let (a, b) = binary(op, vtypes)?;
+        Ok(a.and(b))
+    },
+    Bitcast => |op, vtypes| Ok(*unary(op, vtypes)?),
+    Cmpi => |op, vtypes| match binary(op, vtypes)? {
+        (KnownBits::Bottom, _) | (_, KnownBits::Bottom) => Ok(KnownBits::Bottom),
+        (a, b) => match (a.get_constant(), b.get_constant()) {
+            (Some(l), Some(r)) => {
+                let c = compare(Cmpi.get_value(op), &Interval::constant(l), &Interval::constant(r));
+                match c.get_constant() {
+                    Some(c) => Ok(KnownBits::constant(c)),
+                    None => Ok(KnownBits::Known { zeros: !1, ones: 0 }),
+                }
+            }
+            _ => Ok(KnownBits::Known { zeros: !1, ones: 0 }),
+        },
+    },
+    Divf => |op, vtypes| match binary(op, vtypes)? {
+        (KnownBits::Bottom, _) | (_, KnownBits::Bottom) => Ok(KnownBits::Bottom),
+        _ => Ok(KnownBits::top()),
+    },
+});
+
+lattice_semantics!(
+    /// Registers the [`Nullness`] propagation rules for
+    /// the intrinsics of this dialect (as dynamic interfaces).
+    /// Integers are null when they are zero, and floating point values
+    /// when their bits are zero.
+    register_nullness_semantics, Nullness, {
+    Addf => |op, vtypes| match binary(op, vtypes)? {
+        (Nullness::Bottom, _) | (_, Nullness::Bottom) => Ok(Nullness::Bottom),
+        _ => Ok(Nullness::Top),
+    },
+    Addi => |op, vtypes| match binary(op, vtypes)? {
+        (Nullness::Bottom, _) | (_, Nullness::Bottom) => Ok(Nullness::Bottom),
+        (Nullness::Null, v) | (v, Nullness::Null) => Ok(*v),
+        _ => Ok(Nullness::Top),
+    },
+    Andi => |op, vtypes| match binary(op, vtypes)? {
+        (Nullness::Bottom, _) | (_, Nullness::Bottom) => Ok(Nullness::Bottom),
+        (Nullness::Null, _) | (_, Nullness::Null) => Ok(Nullness::Null),
+        _ => Ok(Nullness::Top),
+    },
+    Bitcast => |op, vtypes| Ok(*unary(op, vtypes)?),
+    Cmpi => |op, vtypes| match binary(op, vtypes)? {
+        (Nullness::Bottom, _) | (_, Nullness::Bottom) => Ok(Nullness::Bottom),
+        _ => Ok(Nullness::Top),
+    },
+    Divf => |op, vtypes| match binary(op, vtypes)? {
+        (Nullness::Bottom, _) | (_, Nullness::Bottom) => Ok(Nullness::Bottom),
+        _ => Ok(Nullness::Top),
+    },
+});
+
+fn is_integer(t: &BuiltinLattice) -> bool {
+    matches!(
+        t,
+        BuiltinLattice::Int1 | BuiltinLattice::Int32 | BuiltinLattice::Int64
+    )
+}
+
+fn is_float(t: &BuiltinLattice) -> bool {
+    matches!(t, BuiltinLattice::Float32 | BuiltinLattice::Float64)
+}
+
+// The type of a binary operation on operands of the same type,
+// where `expected` checks the type of the operands.
+fn binary_type(
+    op: &Operation,
+    vtypes: &[&TypeLattice],
+    expected: fn(&BuiltinLattice) -> bool,
+) -> Result<TypeLattice, Report> {
+    let (a, b) = binary(op, vtypes)?;
+    for t in [a, b].iter().filter_map(|t| t.get_type()) {
+        if !expected(t) {
+            bail!(format!(
+                "{} does not support operands of type {}.",
+                op.get_intrinsic(),
+                t
+            ));
+        }
+    }
+    match (a, b) {
+        (TypeLattice::Type(l), TypeLattice::Type(r)) if l != r => bail!(format!(
+            "{} requires operands of the same type (got {} and {}).",
+            op.get_intrinsic(),
+            l,
+            r
+        )),
+        _ => Ok(a.join(b)),
+    }
+}
+
+lattice_semantics!(
+    /// Registers the [`TypeLattice`] propagation rules for
+    /// the intrinsics of this dialect (as dynamic interfaces).
+    /// Operands of unsupported (or different) types are reported as errors.
+    register_type_semantics, TypeLattice, {
+    Addf => |op, vtypes| binary_type(op, vtypes, is_float),
+    Addi => |op, vtypes| binary_type(op, vtypes, is_integer),
+    Andi => |op, vtypes| binary_type(op, vtypes, is_integer),
+    Bitcast => |op, vtypes| match unary(op, vtypes)? {
+        TypeLattice::Type(t) => match t {
+            BuiltinLattice::Int32 => Ok(TypeLattice::Type(BuiltinLattice::Float32)),
+            BuiltinLattice::Int64 => Ok(TypeLattice::Type(BuiltinLattice::Float64)),
+            BuiltinLattice::Float32 => Ok(TypeLattice::Type(BuiltinLattice::Int32)),
+            BuiltinLattice::Float64 => Ok(TypeLattice::Type(BuiltinLattice::Int64)),
+            t => bail!(format!(
+                "{} does not support operands of type {}.",
+                op.get_intrinsic(),
+                t
+            )),
+        },
+        v => Ok(v.clone()),
+    },
+    Cmpi => |op, vtypes| match binary_type(op, vtypes, is_integer)? {
+        TypeLattice::Bottom => Ok(TypeLattice::Bottom),
+        _ => Ok(TypeLattice::Type(BuiltinLattice::Int1)),
+    },
+    Divf => |op, vtypes| binary_type(op, vtypes, is_float),
+});
+
+/// Registers the propagation rules of a lattice combinator
+/// (e.g. [`Product`]) for the intrinsics of this dialect (as dynamic interfaces).
+/// The rules of its components must be registered separately.
+pub fn register_lattice_semantics<L: 'static>()
+where
+    Addf: LatticeSemantics<L>,
+    Addi: LatticeSemantics<L>,
+    Andi: LatticeSemantics<L>,
+    Bitcast: LatticeSemantics<L>,
+    Cmpi: LatticeSemantics<L>,
+    Divf: LatticeSemantics<L>,
+{
+    dynamic_interfaces! {
+        Addf: dyn LatticeSemantics<L>;
+        Addi: dyn LatticeSemantics<L>;
+        Andi: dyn LatticeSemantics<L>;
+        Bitcast: dyn LatticeSemantics<L>;
+        Cmpi: dyn LatticeSemantics<L>;
+        Divf: dyn LatticeSemantics<L>;
+    }
+}
@@ -1,4 +1,17 @@
+mod attributes;
+mod concrete;
 mod intrinsics;
+mod lattice;
 mod traits;
 
-pub use self::{intrinsics::*, traits::*};
+pub use self::{
+    attributes::*,
+    concrete::register_concrete_semantics,
+    intrinsics::*,
+    lattice::{
+        register_constant_semantics, register_interval_semantics, register_known_bits_semantics,
+        register_lattice_semantics, register_nullness_semantics, register_sign_semantics,
+        register_type_semantics,
+    },
+    traits::*,
+};
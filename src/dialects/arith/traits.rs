@@ -1,11 +1,7 @@
 use crate::core::SupportsInterfaceTraits;
 use crate::Report;
 
-pub trait Commutative {
-    fn verify(&self, _op: &dyn SupportsInterfaceTraits) -> Result<(), Report> {
-        Ok(())
-    }
-}
+pub use crate::dialects::builtin::Commutative;
 
 pub trait Elementwise {
     fn verify(&self, _op: &dyn SupportsInterfaceTraits) -> Result<(), Report> {
@@ -0,0 +1,42 @@
+use crate::core::*;
+use crate::dialects::base::intrinsics::{ConditionalBranch, Constant};
+use crate::dialects::builtin::ProvidesConstantAttr;
+use crate::*;
+use std::sync::Once;
+
+impl ConcreteSemantics for Constant {
+    fn evaluate(
+        &self,
+        op: &Operation,
+        _args: Vec<&RuntimeValue>,
+        _heap: &mut Heap,
+    ) -> Result<Option<RuntimeValue>, Report> {
+        Ok(Some(self.get_value(op).to_runtime_value()))
+    }
+}
+
+// The first successor is taken when the condition is true (or a non-zero integer).
+impl ConcreteBranchSemantics for ConditionalBranch {
+    fn get_successor(&self, op: &Operation, args: Vec<&RuntimeValue>) -> Result<usize, Report> {
+        match args.first().and_then(|c| c.as_bool()) {
+            None => bail!(format!(
+                "{} requires a boolean (or integer) condition.",
+                op.get_intrinsic()
+            )),
+            Some(true) => Ok(0),
+            Some(false) => Ok(1),
+        }
+    }
+}
+
+/// Registers the [`ConcreteSemantics`] (and [`ConcreteBranchSemantics`])
+/// of the intrinsics of this dialect (as dynamic interfaces).
+pub fn register_concrete_semantics() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        dynamic_interfaces! {
+            Constant: dyn ConcreteSemantics;
+            ConditionalBranch: dyn ConcreteBranchSemantics;
+        }
+    });
+}
@@ -1,14 +1,16 @@
 use crate::core::*;
 use crate::dialects::builtin::*;
 use crate::*;
+use std::sync::Once;
 
 intrinsic! {
     Constant: ["base", "constant"],
-    [ProvidesConstantAttr],
+    [MemoryEffects, ProvidesConstantAttr],
     extern: []
 }
 
 impl Constant {
+    /// Integer constants are stored in the canonical form of [`ConstantAttr::integer`].
     pub fn get_builder(
         &self,
         val: ConstantAttr,
@@ -16,7 +18,11 @@ impl Constant {
     ) -> Result<OperationBuilder, Report> {
         let intr = Box::new(Constant);
         let mut b = OperationBuilder::default(intr, loc);
-        b.insert_attr("value", Box::new(val));
+        let val = match val {
+            ConstantAttr::Integer(v, w) => ConstantAttr::integer(v, w),
+            val => val,
+        };
+        b.insert_attr("builtin.value", Box::new(val));
         Ok(b)
     }
 }
@@ -24,7 +30,27 @@ impl Constant {
 intrinsic! {
     Call: ["base", "call"],
     [ProvidesSymbolAttr],
-    extern: []
+    extern: [MemoryEffects, CallLike]
+}
+
+impl CallLike for Call {
+    fn get_callee(&self, op: &dyn SupportsInterfaceTraits) -> Option<String> {
+        let attr = op.get_attributes().get("builtin.symbol")?;
+        attr.query_ref::<dyn AttributeValue<SymbolAttr>>()
+            .map(|sym| sym.get_value().get_name().to_string())
+    }
+}
+
+impl MemoryEffects for Call {
+    fn get_effects(
+        &self,
+        _op: &dyn SupportsInterfaceTraits,
+    ) -> Vec<(MemoryEffect, EffectResource)> {
+        vec![
+            (MemoryEffect::Read, EffectResource::Default),
+            (MemoryEffect::Write, EffectResource::Default),
+        ]
+    }
 }
 
 impl Call {
@@ -65,7 +91,16 @@ impl Return {
 intrinsic! {
     Branch: ["base", "branch"],
     [Terminator],
-    extern: []
+    extern: [SuccessorOperands]
+}
+
+impl SuccessorOperands for Branch {
+    fn get_successor_operands(&self, _r: &Region, op: &Operation) -> Vec<Vec<Var>> {
+        op.get_successors()
+            .iter()
+            .map(|_| op.get_operands())
+            .collect()
+    }
 }
 
 impl Branch {
@@ -86,7 +121,41 @@ impl Branch {
 intrinsic! {
     ConditionalBranch: ["base", "br"],
     [Terminator],
-    extern: []
+    extern: [SuccessorOperands, FoldSuccessor]
+}
+
+// The operands are the condition, followed by the arguments
+// of the first successor, and then the arguments of the second.
+impl SuccessorOperands for ConditionalBranch {
+    fn get_successor_operands(&self, r: &Region, op: &Operation) -> Vec<Vec<Var>> {
+        let (succs, operands) = (op.get_successors(), op.get_operands());
+        if succs.len() != 2 || operands.is_empty() {
+            return succs.iter().map(|_| Vec::new()).collect();
+        }
+        let ntrue = r.get_block(succs[0]).get_operands().len();
+        let (t, f) = operands[1..].split_at(ntrue.min(operands.len() - 1));
+        vec![t.to_vec(), f.to_vec()]
+    }
+}
+
+impl FoldSuccessor for ConditionalBranch {
+    fn fold_successor(
+        &self,
+        r: &Region,
+        op: &Operation,
+        ind: usize,
+    ) -> Result<OperationBuilder, Report> {
+        let succs = op.get_successors();
+        let mut operands = self.get_successor_operands(r, op);
+        if ind >= succs.len() || ind >= operands.len() {
+            bail!(format!("{} has no successor {}.", op.get_intrinsic(), ind))
+        }
+        Branch.get_builder(
+            operands.swap_remove(ind),
+            vec![succs[ind]],
+            op.get_location().clone(),
+        )
+    }
 }
 
 impl ConditionalBranch {
@@ -103,3 +172,16 @@ impl ConditionalBranch {
         Ok(b)
     }
 }
+
+/// Registers the intrinsics of this dialect,
+/// so that its operations can be read by [`parse_generic`].
+pub fn register_base_dialect() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        register_intrinsic(Box::new(Constant));
+        register_intrinsic(Box::new(Call));
+        register_intrinsic(Box::new(Return));
+        register_intrinsic(Box::new(Branch));
+        register_intrinsic(Box::new(ConditionalBranch));
+    });
+}
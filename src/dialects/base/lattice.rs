@@ -0,0 +1,205 @@
+use crate::core::*;
+use crate::dialects::base::intrinsics::{ConditionalBranch, Constant, Return};
+use crate::dialects::builtin::{ConstantAttr, ConstantLattice, ProvidesConstantAttr, TypeLattice};
+use crate::*;
+use std::sync::Once;
+
+impl LatticeSemantics<ConstantLattice> for Constant {
+    fn propagate(
+        &self,
+        op: &Operation,
+        _vtypes: Vec<&ConstantLattice>,
+    ) -> Result<ConstantLattice, Report> {
+        Ok(ConstantLattice::Constant(self.get_value(op).clone()))
+    }
+}
+
+// The value of a return is the value it returns.
+impl LatticeSemantics<ConstantLattice> for Return {
+    fn propagate(
+        &self,
+        _op: &Operation,
+        vtypes: Vec<&ConstantLattice>,
+    ) -> Result<ConstantLattice, Report> {
+        match vtypes[..] {
+            [v] => Ok(v.clone()),
+            _ => Ok(ConstantLattice::Overdefined),
+        }
+    }
+}
+
+// A conditional branch on a known condition only takes one successor.
+impl LatticeBranchSemantics<ConstantLattice> for ConditionalBranch {
+    fn get_feasible_successors(
+        &self,
+        _op: &Operation,
+        vtypes: Vec<&ConstantLattice>,
+    ) -> Result<Vec<usize>, Report> {
+        match vtypes.first() {
+            Some(ConstantLattice::Undefined) => Ok(Vec::new()),
+            Some(ConstantLattice::Constant(ConstantAttr::Integer(0, _))) => Ok(vec![1]),
+            Some(ConstantLattice::Constant(ConstantAttr::Integer(_, _))) => Ok(vec![0]),
+            _ => Ok(vec![0, 1]),
+        }
+    }
+}
+
+// Known values are materialized as `base.constant` operations.
+impl LatticeMaterialize for ConstantLattice {
+    fn materialize(&self, loc: LocationInfo) -> Result<Option<OperationBuilder>, Report> {
+        match self.get_constant() {
+            None => Ok(None),
+            Some(c) => Ok(Some(Constant.get_builder(c.clone(), loc)?)),
+        }
+    }
+}
+
+/// Registers the [`ConstantLattice`] propagation rules for
+/// the intrinsics of this dialect (as dynamic interfaces).
+pub fn register_constant_semantics() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        dynamic_interfaces! {
+            Constant: dyn LatticeSemantics<ConstantLattice>;
+            Return: dyn LatticeSemantics<ConstantLattice>;
+            ConditionalBranch: dyn LatticeBranchSemantics<ConstantLattice>;
+        }
+    });
+}
+
+// Defines the propagation rules for constants (given the value of the constant), returns,
+// and conditional branches (given whether the condition may be zero, or nonzero)
+// for a lattice, and a function which registers them.
+macro_rules! lattice_semantics {
+    ($(#[$doc:meta])* $register:ident, $lattice:ty, $constant:expr, $top:expr, $zero:expr, $nonzero:expr) => {
+        impl LatticeSemantics<$lattice> for Constant {
+            fn propagate(&self, op: &Operation, _vtypes: Vec<&$lattice>) -> Result<$lattice, Report> {
+                let constant: fn(&ConstantAttr) -> $lattice = $constant;
+                Ok(constant(self.get_value(op)))
+            }
+        }
+
+        impl LatticeSemantics<$lattice> for Return {
+            fn propagate(&self, _op: &Operation, vtypes: Vec<&$lattice>) -> Result<$lattice, Report> {
+                match vtypes[..] {
+                    [v] => Ok(v.clone()),
+                    _ => Ok($top),
+                }
+            }
+        }
+
+        impl LatticeBranchSemantics<$lattice> for ConditionalBranch {
+            fn get_feasible_successors(
+                &self,
+                _op: &Operation,
+                vtypes: Vec<&$lattice>,
+            ) -> Result<Vec<usize>, Report> {
+                let (zero, nonzero): (fn(&$lattice) -> bool, fn(&$lattice) -> bool) = ($zero, $nonzero);
+                match vtypes.first() {
+                    None => Ok(vec![0, 1]),
+                    Some(v) => Ok([(0, nonzero(v)), (1, zero(v))]
+                        .iter()
+                        .filter_map(|(s, feasible)| feasible.then_some(*s))
+                        .collect()),
+                }
+            }
+        }
+
+        $(#[$doc])*
+        pub fn $register() {
+            static REGISTER: Once = Once::new();
+            REGISTER.call_once(|| {
+                dynamic_interfaces! {
+                    Constant: dyn LatticeSemantics<$lattice>;
+                    Return: dyn LatticeSemantics<$lattice>;
+                    ConditionalBranch: dyn LatticeBranchSemantics<$lattice>;
+                }
+            });
+        }
+    };
+}
+
+lattice_semantics!(
+    /// Registers the [`Sign`] propagation rules for
+    /// the intrinsics of this dialect (as dynamic interfaces).
+    register_sign_semantics,
+    Sign,
+    |c| match c {
+        ConstantAttr::Integer(v, _) => Sign::from_integer(*v),
+        ConstantAttr::Float(v, _) => Sign::from_float(*v),
+    },
+    Sign::Top,
+    |v| v.may_be_zero(),
+    |v| v.may_be_nonzero()
+);
+
+lattice_semantics!(
+    /// Registers the [`Interval`] propagation rules for
+    /// the intrinsics of this dialect (as dynamic interfaces).
+    register_interval_semantics,
+    Interval,
+    |c| match c {
+        ConstantAttr::Integer(v, _) => Interval::constant(*v),
+        ConstantAttr::Float(_, _) => Interval::top(),
+    },
+    Interval::top(),
+    |v| v.contains(0),
+    |v| *v != Interval::Bottom && *v != Interval::constant(0)
+);
+
+lattice_semantics!(
+    /// Registers the [`KnownBits`] propagation rules for
+    /// the intrinsics of this dialect (as dynamic interfaces).
+    register_known_bits_semantics,
+    KnownBits,
+    |c| match c {
+        ConstantAttr::Integer(v, _) => KnownBits::constant(*v),
+        ConstantAttr::Float(v, 32) => KnownBits::constant((*v as f32).to_bits() as i32 as i64),
+        ConstantAttr::Float(v, _) => KnownBits::constant(v.to_bits() as i64),
+    },
+    KnownBits::top(),
+    |v| v.may_be_zero(),
+    |v| v.may_be_nonzero()
+);
+
+lattice_semantics!(
+    /// Registers the [`Nullness`] propagation rules for
+    /// the intrinsics of this dialect (as dynamic interfaces).
+    register_nullness_semantics,
+    Nullness,
+    |c| match c {
+        ConstantAttr::Integer(0, _) => Nullness::Null,
+        ConstantAttr::Float(v, _) if v.to_bits() == 0 => Nullness::Null,
+        _ => Nullness::NonNull,
+    },
+    Nullness::Top,
+    |v| matches!(v, Nullness::Null | Nullness::Top),
+    |v| matches!(v, Nullness::NonNull | Nullness::Top)
+);
+
+lattice_semantics!(
+    /// Registers the [`TypeLattice`] propagation rules for
+    /// the intrinsics of this dialect (as dynamic interfaces).
+    register_type_semantics,
+    TypeLattice,
+    TypeLattice::from_constant,
+    TypeLattice::Top,
+    |v| *v != TypeLattice::Bottom,
+    |v| *v != TypeLattice::Bottom
+);
+
+/// Registers the propagation rules of a lattice combinator
+/// (e.g. [`Product`]) for the intrinsics of this dialect (as dynamic interfaces).
+/// The rules of its components must be registered separately.
+pub fn register_lattice_semantics<L: 'static>()
+where
+    Constant: LatticeSemantics<L>,
+    Return: LatticeSemantics<L>,
+    ConditionalBranch: LatticeBranchSemantics<L>,
+{
+    dynamic_interfaces! {
+        Constant: dyn LatticeSemantics<L>;
+        Return: dyn LatticeSemantics<L>;
+        ConditionalBranch: dyn LatticeBranchSemantics<L>;
+    }
+}
@@ -1,3 +1,15 @@
+mod concrete;
 mod intrinsics;
+mod lattice;
+mod passes;
 
-pub use self::intrinsics::{Call, Constant, Return};
+pub use self::{
+    concrete::register_concrete_semantics,
+    intrinsics::{register_base_dialect, Branch, Call, ConditionalBranch, Constant, Return},
+    lattice::{
+        register_constant_semantics, register_interval_semantics, register_known_bits_semantics,
+        register_lattice_semantics, register_nullness_semantics, register_sign_semantics,
+        register_type_semantics,
+    },
+    passes::{register_base_passes, CanonicalizePass, FoldPattern, LICMPass, SCCPPass},
+};
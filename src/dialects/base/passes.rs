@@ -0,0 +1,534 @@
+use crate::core::*;
+use crate::dialects::base::*;
+use crate::dialects::builtin::*;
+use crate::*;
+use std::collections::HashSet;
+use std::sync::{Once, RwLock};
+
+/// A [`RewritePattern`] which folds [`Foldable`] operations.
+///
+/// Operand values are provided to [`Foldable::fold`] when the operand
+/// is defined by a [`Constant`] in the same region. Folded constants
+/// are materialized as new [`Constant`] operations,
+/// and [`Constant`] operations without uses are erased.
+#[derive(Debug)]
+pub struct FoldPattern;
+
+impl RewritePattern for FoldPattern {
+    fn match_and_rewrite(&self, v: Var, rewriter: &mut IrRewriter) -> Result<bool, Report> {
+        let op = match rewriter.get_op(v) {
+            None => return Ok(false),
+            Some(op) => op,
+        };
+        let intr = op.get_intrinsic();
+        if intr.is::<Constant>() {
+            if rewriter.get_region().get_uses(v).is_empty() {
+                rewriter.erase_op(v)?;
+                return Ok(true);
+            }
+            return Ok(false);
+        }
+        let folder = match intr.query_ref::<dyn Foldable>() {
+            None => return Ok(false),
+            Some(folder) => folder,
+        };
+        let region = rewriter.get_region();
+        let operands = op
+            .get_operands()
+            .iter()
+            .map(|o| match region.get_op(*o) {
+                Some((_, def)) if def.get_intrinsic().is::<Constant>() => {
+                    let intr = def.get_intrinsic();
+                    let trt = intr.query_ref::<dyn ProvidesConstantAttr>().unwrap();
+                    Some(trt.get_value(def))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let loc = op.get_location().clone();
+        match folder.fold(op, &operands) {
+            None => Ok(false),
+            Some(FoldResult::Var(w)) if w == v => Ok(false),
+            Some(FoldResult::Var(w)) => {
+                rewriter.replace_op(v, w)?;
+                Ok(true)
+            }
+            Some(FoldResult::Constant(c)) => {
+                rewriter.replace_op_with_new(v, Constant.get_builder(c, loc)?)?;
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// Canonicalizes operations by folding [`Foldable`] operations
+/// and applying registered canonicalization patterns, until a fixpoint is reached.
+///
+/// Canonicalization patterns are collected (when the pass is applied) from every
+/// intrinsic which implements [`HasCanonicalizationPatterns`] and occurs in the operation,
+/// and are applied together with the patterns which the pass was constructed with.
+///
+/// The options which were set explicitly in a textual pipeline are kept,
+/// so that the pass is printed with them (even if they equal their default).
+#[derive(Debug, Clone)]
+pub struct CanonicalizePass(GreedyRewritePass, PassOptions);
+
+// Collect the canonicalization patterns of the intrinsics which occur in `op`.
+fn collect_canonicalization_patterns(
+    op: &Operation,
+    seen: &mut HashSet<String>,
+    patterns: &mut RewritePatternSet,
+) {
+    let intr = op.get_intrinsic();
+    if seen.insert(intr.get_unique_id()) {
+        if let Some(trt) = intr.query_ref::<dyn HasCanonicalizationPatterns>() {
+            trt.get_canonicalization_patterns(patterns);
+        }
+    }
+    for r in op.get_regions() {
+        for ind in 0..r.get_blocks().len() {
+            for (_, child) in r.get_block_iter(ind) {
+                collect_canonicalization_patterns(child, seen, patterns);
+            }
+        }
+    }
+}
+
+impl CanonicalizePass {
+    pub fn new(patterns: RewritePatternSet) -> CanonicalizePass {
+        CanonicalizePass::with_config(patterns, GreedyRewriteConfig::default())
+    }
+
+    pub fn with_config(
+        mut patterns: RewritePatternSet,
+        config: GreedyRewriteConfig,
+    ) -> CanonicalizePass {
+        patterns.add(Box::new(FoldPattern));
+        CanonicalizePass(
+            GreedyRewritePass::with_config(patterns, config),
+            PassOptions::new(),
+        )
+    }
+
+    fn from_options(options: &PassOptions) -> Result<CanonicalizePass, Report> {
+        let default = GreedyRewriteConfig::default();
+        let max_rewrites = match options.get::<String>("max-rewrites")?.as_deref() {
+            None | Some("none") => default.max_rewrites,
+            Some(_) => options.get::<usize>("max-rewrites")?,
+        };
+        let config = GreedyRewriteConfig {
+            max_iterations: options.get_or("max-iterations", default.max_iterations)?,
+            max_rewrites,
+            recursive: options.get_or("recursive", default.recursive)?,
+        };
+        let mut pass = CanonicalizePass::with_config(RewritePatternSet::new(), config);
+        pass.1 = options.clone();
+        Ok(pass)
+    }
+}
+
+impl Default for CanonicalizePass {
+    fn default() -> Self {
+        CanonicalizePass::new(RewritePatternSet::new())
+    }
+}
+
+impl OperationPass for CanonicalizePass {
+    fn reset(&self) -> Box<dyn OperationPass> {
+        Box::new(self.clone())
+    }
+
+    fn get_argument(&self) -> String {
+        "canonicalize".to_string()
+    }
+
+    fn get_statistics(&self) -> Vec<&Statistic> {
+        self.0.get_statistics()
+    }
+
+    fn get_options(&self) -> PassOptions {
+        let (config, default) = (self.0.get_config(), GreedyRewriteConfig::default());
+        let explicit = |key: &str| self.1.iter().any(|(k, _)| k == key);
+        let mut options = PassOptions::new();
+        if explicit("max-iterations") || config.max_iterations != default.max_iterations {
+            options.insert("max-iterations", &config.max_iterations.to_string());
+        }
+        if explicit("max-rewrites") || config.max_rewrites != default.max_rewrites {
+            let max_rewrites = config
+                .max_rewrites
+                .map_or("none".to_string(), |n| n.to_string());
+            options.insert("max-rewrites", &max_rewrites);
+        }
+        if explicit("recursive") || config.recursive != default.recursive {
+            options.insert("recursive", &config.recursive.to_string());
+        }
+        options
+    }
+
+    fn check(&self, op: &RwLock<Operation>) -> Result<(), Report> {
+        self.0.check(op)
+    }
+
+    fn apply(
+        &self,
+        op_lock: &RwLock<Operation>,
+        _analysis_lock: &RwLock<AnalysisManager>,
+    ) -> Result<(), Report> {
+        let mut op = op_lock.write().unwrap();
+        let mut patterns = self.0.get_patterns().clone();
+        collect_canonicalization_patterns(&op, &mut HashSet::new(), &mut patterns);
+        self.0.apply_patterns(&mut op, &patterns)
+    }
+}
+
+/// Loop-invariant code motion.
+///
+/// Hoists pure operations (see [`MemoryEffects`]) whose operands are all defined
+/// outside of a loop into the loop preheader - the unique block outside the loop
+/// which branches (only) to the loop header. Loops are natural loops in the
+/// control flow graph formed by [`Branch`] and [`ConditionalBranch`] (see [`LoopInfo`]).
+/// If a loop has no preheader, a new block is created which forwards its arguments
+/// to the header with a [`Branch`], and branches into the loop from outside are redirected
+/// to it. Loops headed by the entry block of a region are skipped.
+///
+/// Inner loops are processed before outer loops, so operations can be hoisted
+/// out of a loop nest one level at a time. Nested regions are processed recursively.
+#[derive(Debug, Clone)]
+pub struct LICMPass {
+    hoisted: Statistic,
+}
+
+impl Default for LICMPass {
+    fn default() -> Self {
+        LICMPass {
+            hoisted: Statistic::new("ops-hoisted", "Number of loop-invariant operations hoisted"),
+        }
+    }
+}
+
+fn get_preheader(r: &Region, dom: &DominanceInfo, l: &Loop) -> Option<usize> {
+    let h = l.get_header();
+    let outside = dom
+        .get_predecessors(h)
+        .iter()
+        .filter(|p| !l.contains(**p))
+        .collect::<Vec<_>>();
+    match outside[..] {
+        [p] if r.get_block_successors(*p).iter().all(|s| *s == h) => Some(*p),
+        _ => None,
+    }
+}
+
+fn insert_preheader(r: &mut Region, dom: &DominanceInfo, l: &Loop) -> Result<usize, Report> {
+    let h = l.get_header();
+    let nargs = r.get_block(h).get_operands().len();
+    let ssacfg = match r {
+        Region::Directed(ssacfg) => ssacfg,
+        Region::Undirected(_) => bail!("Can't insert a preheader block into `Graph` region."),
+    };
+    let p = ssacfg.push_block(BasicBlock::default());
+    let args = (0..nargs).map(|_| ssacfg.push_arg(p)).collect::<Vec<_>>();
+    let br = Branch.get_builder(args, vec![h], LocationInfo::Unknown)?;
+    ssacfg.push_op(p, br.finish()?);
+    for o in dom.get_predecessors(h).iter().filter(|o| !l.contains(**o)) {
+        let term = ssacfg.get_block_mut(*o).get_ops_mut().last_mut().unwrap();
+        let succs = term
+            .get_successors()
+            .iter()
+            .map(|s| if *s == h { p } else { *s })
+            .collect::<Vec<_>>();
+        term.set_successors(succs);
+    }
+    Ok(p)
+}
+
+fn is_loop_invariant(r: &Region, l: &Loop, op: &Operation) -> bool {
+    let pure = op
+        .get_intrinsic()
+        .query_ref::<dyn MemoryEffects>()
+        .is_some_and(|e| e.is_pure(op));
+    pure && op.get_regions().is_empty()
+        && op.get_successors().is_empty()
+        && op.get_operands().iter().all(|o| {
+            let blk = match r.get_op_position(*o) {
+                Some((b, _)) => Some(b),
+                None => r.get_arg_block(*o),
+            };
+            blk.is_some_and(|b| !l.contains(b))
+        })
+}
+
+fn licm_region(r: &mut Region) -> Result<usize, Report> {
+    let mut count = 0;
+    if let Region::Directed(_) = r {
+        // Every loop must have a preheader - the control flow graph
+        // changes when a preheader is inserted, so start over.
+        loop {
+            let dom = DominanceInfo::new(r);
+            let loops = LoopInfo::new(&dom);
+            let missing = loops
+                .get_loops()
+                .iter()
+                .find(|l| l.get_header() != 0 && get_preheader(r, &dom, l).is_none());
+            match missing {
+                None => break,
+                Some(l) => insert_preheader(r, &dom, l)?,
+            };
+        }
+        let dom = DominanceInfo::new(r);
+        let mut loops = LoopInfo::new(&dom).get_loops().to_vec();
+        loops.sort_by_key(|l| l.get_blocks().len());
+        for l in loops.iter() {
+            let p = match get_preheader(r, &dom, l) {
+                None => continue,
+                Some(p) => p,
+            };
+            for b in dom.get_preorder().into_iter().filter(|b| l.contains(*b)) {
+                for v in r.get_block_iter(b).map(|(v, _)| v).collect::<Vec<_>>() {
+                    let (_, op) = r.get_op(v).unwrap();
+                    if is_loop_invariant(r, l, op) {
+                        let op = r.remove_op(v).unwrap();
+                        let idx = r.get_block(p).get_ops().len() - 1;
+                        r.place_op(v, p, idx, op);
+                        count += 1;
+                    }
+                }
+            }
+        }
+    }
+    for b in 0..r.get_blocks().len() {
+        for v in r.get_block_iter(b).map(|(v, _)| v).collect::<Vec<_>>() {
+            let (_, op) = r.get_op_mut(v).unwrap();
+            for nested in op.get_regions_mut().iter_mut() {
+                count += licm_region(nested)?;
+            }
+        }
+    }
+    Ok(count)
+}
+
+impl OperationPass for LICMPass {
+    fn reset(&self) -> Box<dyn OperationPass> {
+        Box::new(LICMPass::default())
+    }
+
+    fn get_argument(&self) -> String {
+        "licm".to_string()
+    }
+
+    fn get_statistics(&self) -> Vec<&Statistic> {
+        vec![&self.hoisted]
+    }
+
+    fn check(&self, _op: &RwLock<Operation>) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn apply(
+        &self,
+        op_lock: &RwLock<Operation>,
+        _analysis_lock: &RwLock<AnalysisManager>,
+    ) -> Result<(), Report> {
+        let mut op = op_lock.write().unwrap();
+        let mut count = 0;
+        for r in op.get_regions_mut().iter_mut() {
+            count += licm_region(r)?;
+        }
+        self.hoisted.add(count);
+        tracing::debug!("Hoisted {} loop-invariant operations.", count);
+        Ok(())
+    }
+}
+
+/// Sparse conditional constant propagation.
+///
+/// Interprets the (first) `SSACFG` region of each operation with an
+/// [`Interpreter`] over the [`ConstantLattice`], using the
+/// `LatticeSemantics<ConstantLattice>` propagation rules of the intrinsics
+/// (operations without a rule, and the results of calls, are `Overdefined`). The interpreter
+/// only follows control flow edges which may be taken (see [`LatticeBranchSemantics`]) -
+/// e.g. if the condition of a [`ConditionalBranch`] is a known constant,
+/// only one successor is executable.
+///
+/// Afterwards, pure operations and block arguments with constant values
+/// are replaced with [`Constant`] operations, and branches which only take one
+/// successor are replaced with the branch built by [`FoldSuccessor`].
+/// Blocks which become unreachable are left for the `DCEPass`.
+///
+/// The propagation rules of the `base` and `arith` dialects
+/// are registered when the pass is applied.
+#[derive(Debug, Clone)]
+pub struct SCCPPass {
+    propagated: Statistic,
+    folded: Statistic,
+}
+
+impl Default for SCCPPass {
+    fn default() -> Self {
+        SCCPPass {
+            propagated: Statistic::new("constants-propagated", "Number of constants propagated"),
+            folded: Statistic::new("branches-folded", "Number of branches folded"),
+        }
+    }
+}
+
+// Interpret the first region of `op` to a fixpoint, where the arguments of the
+// entry block and the results of calls are unknown (`Overdefined`).
+fn sccp_interpret(op: &Operation) -> Result<Interpreter<ConstantLattice>, Report> {
+    let nargs = op.get_regions()[0].get_block(0).get_operands().len();
+    let mut interp = Interpreter::new(op, vec![Some(ConstantLattice::Overdefined); nargs])
+        .with_fallback(ConstantLattice::Overdefined);
+    interp.run(op)?;
+    while interp.get_waiting().is_some() {
+        interp.resolve(Some(ConstantLattice::Overdefined))?;
+        interp.run(op)?;
+    }
+    Ok(interp)
+}
+
+// Returns the number of constants propagated, and of branches folded.
+fn sccp_rewrite(
+    r: &mut Region,
+    interp: &Interpreter<ConstantLattice>,
+) -> Result<(usize, usize), Report> {
+    let frame = interp.clone_frame()?;
+    let (mut count, mut folded) = (0, 0);
+    for b in (0..r.get_blocks().len()).filter(|b| frame.is_executable(*b)) {
+        // Fold branches which only take one successor.
+        let term = r.get_block_iter(b).last();
+        if let Some((v, op)) = term {
+            let fold = op.get_intrinsic().query_ref::<dyn FoldSuccessor>();
+            if let (true, Some(fold)) = (op.get_successors().len() > 1, fold) {
+                let feasible = interp.get_feasible_successors(op)?;
+                let taken = (0..feasible.len())
+                    .filter(|s| feasible[*s])
+                    .collect::<Vec<_>>();
+                if let [s] = taken[..] {
+                    let br = fold.fold_successor(r, op, s)?.finish()?;
+                    let (_, idx) = r.get_op_position(v).unwrap();
+                    r.remove_op(v);
+                    r.place_op(v, b, idx, br);
+                    folded += 1;
+                }
+            }
+        }
+
+        // Materialize constant block arguments and operations.
+        let args = r.get_block(b).get_operands().to_vec();
+        let ops = r.get_block_iter(b).map(|(v, _)| v).collect::<Vec<_>>();
+        for v in args.into_iter().chain(ops) {
+            let c = match frame.get(v).and_then(|l| l.get_constant()) {
+                None => continue,
+                Some(c) => c.clone(),
+            };
+            let (idx, loc) = match r.get_op(v) {
+                None if r.get_uses(v).is_empty() => continue,
+                None => (0, LocationInfo::Unknown),
+                Some((_, op)) => {
+                    let pure = op
+                        .get_intrinsic()
+                        .query_ref::<dyn MemoryEffects>()
+                        .is_some_and(|e| e.is_pure(op));
+                    if op.get_intrinsic().is::<Constant>()
+                        || !pure
+                        || !op.get_successors().is_empty()
+                    {
+                        continue;
+                    }
+                    (r.get_op_position(v).unwrap().1, op.get_location().clone())
+                }
+            };
+            let new = r.insert_op(b, idx, Constant.get_builder(c, loc)?.finish()?);
+            r.replace_all_uses(v, new);
+            if r.get_op(v).is_some() {
+                r.remove_op(v);
+            }
+            count += 1;
+        }
+    }
+    Ok((count, folded))
+}
+
+fn sccp_operation(op: &mut Operation) -> Result<(usize, usize), Report> {
+    let (mut count, mut folded) = (0, 0);
+    if let Some(Region::Directed(ssacfg)) = op.get_regions().first() {
+        if !ssacfg.get_blocks().is_empty() {
+            let interp = sccp_interpret(op)?;
+            (count, folded) = sccp_rewrite(&mut op.get_regions_mut()[0], &interp)?;
+        }
+    }
+    for r in op.get_regions_mut().iter_mut() {
+        for b in 0..r.get_blocks().len() {
+            for v in r.get_block_iter(b).map(|(v, _)| v).collect::<Vec<_>>() {
+                let (_, child) = r.get_op_mut(v).unwrap();
+                let (c, f) = sccp_operation(child)?;
+                count += c;
+                folded += f;
+            }
+        }
+    }
+    Ok((count, folded))
+}
+
+impl OperationPass for SCCPPass {
+    fn reset(&self) -> Box<dyn OperationPass> {
+        Box::new(SCCPPass::default())
+    }
+
+    fn get_argument(&self) -> String {
+        "sccp".to_string()
+    }
+
+    fn get_statistics(&self) -> Vec<&Statistic> {
+        vec![&self.propagated, &self.folded]
+    }
+
+    fn check(&self, _op: &RwLock<Operation>) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn apply(
+        &self,
+        op_lock: &RwLock<Operation>,
+        _analysis_lock: &RwLock<AnalysisManager>,
+    ) -> Result<(), Report> {
+        register_constant_semantics();
+        #[cfg(feature = "arith")]
+        crate::dialects::arith::register_constant_semantics();
+        let mut op = op_lock.write().unwrap();
+        let (count, folded) = sccp_operation(&mut op)?;
+        self.propagated.add(count);
+        self.folded.add(folded);
+        tracing::debug!(
+            "Propagated {} constants, and folded {} branches.",
+            count,
+            folded
+        );
+        Ok(())
+    }
+}
+
+/// Registers the passes of this dialect for use in textual pipelines.
+pub fn register_base_passes() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        register_pass(
+            "canonicalize",
+            "Fold operations and apply canonicalization patterns until a fixpoint is reached.",
+            &["max-iterations", "max-rewrites", "recursive"],
+            |options| Ok(Box::new(CanonicalizePass::from_options(options)?)),
+        );
+        register_pass(
+            "licm",
+            "Hoist loop-invariant operations into loop preheaders.",
+            &[],
+            |_| Ok(Box::new(LICMPass::default())),
+        );
+        register_pass(
+            "sccp",
+            "Sparse conditional constant propagation.",
+            &[],
+            |_| Ok(Box::new(SCCPPass::default())),
+        );
+    });
+}
@@ -5,7 +5,9 @@ use std::collections::HashMap;
 use std::fmt;
 use yansi::Paint;
 
-#[derive(Debug)]
+/// A constant integer (or float) of a given width. Integers
+/// are stored in the canonical form built by [`ConstantAttr::integer`].
+#[derive(Debug, Clone, PartialEq)]
 pub enum ConstantAttr {
     Integer(i64, usize),
     Float(f64, usize),
@@ -20,12 +22,73 @@ impl fmt::Display for ConstantAttr {
     }
 }
 
+impl ConstantAttr {
+    /// An integer constant of `width` bits in canonical form: integers of
+    /// width 1 (booleans) are `0` or `1`, wider integers are sign extended.
+    pub fn integer(v: i64, width: usize) -> ConstantAttr {
+        let v = match width {
+            0 | 64.. => v,
+            1 => v & 1,
+            w => (v << (64 - w)) >> (64 - w),
+        };
+        ConstantAttr::Integer(v, width)
+    }
+
+    /// Convert to a value of the [`ConcreteInterpreter`],
+    /// integers of width 1 are booleans.
+    pub fn to_runtime_value(&self) -> RuntimeValue {
+        match self {
+            ConstantAttr::Integer(v, 1) => RuntimeValue::Bool(*v != 0),
+            ConstantAttr::Integer(v, w) => RuntimeValue::Integer(*v, *w),
+            ConstantAttr::Float(v, w) => RuntimeValue::Float(*v, *w),
+        }
+    }
+
+    pub fn from_runtime_value(v: &RuntimeValue) -> Option<ConstantAttr> {
+        match v {
+            RuntimeValue::Bool(b) => Some(ConstantAttr::Integer(*b as i64, 1)),
+            RuntimeValue::Integer(v, w) => Some(ConstantAttr::Integer(*v, *w)),
+            RuntimeValue::Float(v, w) => Some(ConstantAttr::Float(*v, *w)),
+            RuntimeValue::MemRef(_) => None,
+        }
+    }
+}
+
+impl GenericAttribute for ConstantAttr {
+    fn print_generic(&self) -> String {
+        match self {
+            ConstantAttr::Integer(v, w) => format!("{} : i{}", v, w),
+            ConstantAttr::Float(v, w) => format!("{:?} : f{}", v, w),
+        }
+    }
+
+    fn parse_generic(src: &str) -> Result<Self, Report> {
+        let invalid = || format!("Invalid constant `{}`.", src);
+        let (v, t) = match src.rsplit_once(':') {
+            None => bail!(invalid()),
+            Some((v, t)) => (v.trim(), t.trim()),
+        };
+        let width = |w: &str| w.parse::<usize>().ok();
+        match (t.strip_prefix('i'), t.strip_prefix('f')) {
+            (Some(w), _) => match (v.parse::<i64>().ok(), width(w)) {
+                (Some(v), Some(w)) => Ok(ConstantAttr::integer(v, w)),
+                _ => bail!(invalid()),
+            },
+            (_, Some(w)) => match (v.parse::<f64>().ok(), width(w)) {
+                (Some(v), Some(w)) => Ok(ConstantAttr::Float(v, w)),
+                _ => bail!(invalid()),
+            },
+            _ => bail!(invalid()),
+        }
+    }
+}
+
 attribute! {
     ConstantAttr: "builtin.value",
     trait: ProvidesConstantAttr
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum LinkageAttr {
     Private,
     External,
@@ -40,14 +103,131 @@ impl fmt::Display for LinkageAttr {
     }
 }
 
+impl GenericAttribute for LinkageAttr {
+    fn print_generic(&self) -> String {
+        match self {
+            LinkageAttr::Private => "private".to_string(),
+            LinkageAttr::External => "external".to_string(),
+        }
+    }
+
+    fn parse_generic(src: &str) -> Result<Self, Report> {
+        match src {
+            "private" => Ok(LinkageAttr::Private),
+            "external" => Ok(LinkageAttr::External),
+            _ => bail!(format!("Unknown linkage `{}`.", src)),
+        }
+    }
+}
+
 attribute! {
     LinkageAttr: "builtin.linkage",
     trait: ProvidesLinkageAttr
 }
 
+// The generic form of a type, e.g. `memref<f64>` or `(i64, i64) -> i1`.
+fn print_type(t: &BuiltinLattice) -> String {
+    match t {
+        BuiltinLattice::Float32 => "f32".to_string(),
+        BuiltinLattice::Float64 => "f64".to_string(),
+        BuiltinLattice::Int1 => "i1".to_string(),
+        BuiltinLattice::Int32 => "i32".to_string(),
+        BuiltinLattice::Int64 => "i64".to_string(),
+        BuiltinLattice::MemRef(t) => format!("memref<{}>", print_type(t)),
+        BuiltinLattice::Function(argts, rett) => {
+            let argts = argts.iter().map(print_type).collect::<Vec<_>>();
+            format!("({}) -> {}", argts.join(", "), print_type(rett))
+        }
+        BuiltinLattice::Tensor(shape, t) => {
+            let shape = shape.iter().map(|d| d.to_string()).collect::<Vec<_>>();
+            format!("tensor<[{}], {}>", shape.join(", "), print_type(t))
+        }
+    }
+}
+
+fn strip<'a>(src: &'a str, token: &str) -> Result<&'a str, Report> {
+    match src.trim_start().strip_prefix(token) {
+        None => bail!(format!("Expected `{}` in type `{}`.", token, src.trim())),
+        Some(rest) => Ok(rest),
+    }
+}
+
+// Parse a type from the start of `src`, returning the type and the rest of `src`.
+fn parse_type(src: &str) -> Result<(BuiltinLattice, &str), Report> {
+    let src = src.trim_start();
+    if let Some(rest) = src.strip_prefix("memref<") {
+        let (t, rest) = parse_type(rest)?;
+        return Ok((BuiltinLattice::MemRef(Box::new(t)), strip(rest, ">")?));
+    }
+    if let Some(rest) = src.strip_prefix("tensor<[") {
+        let (shape, rest) = match rest.split_once(']') {
+            None => bail!(format!("Expected `]` in type `{}`.", src)),
+            Some(v) => v,
+        };
+        let shape = shape
+            .split(',')
+            .filter(|d| !d.trim().is_empty())
+            .map(|d| d.trim().parse::<usize>())
+            .collect::<Result<Vec<_>, _>>();
+        let shape = match shape {
+            Err(_) => bail!(format!("Invalid shape in type `{}`.", src)),
+            Ok(shape) => shape,
+        };
+        let (t, rest) = parse_type(strip(rest, ",")?)?;
+        return Ok((
+            BuiltinLattice::Tensor(shape, Box::new(t)),
+            strip(rest, ">")?,
+        ));
+    }
+    if let Some(mut rest) = src.strip_prefix('(') {
+        let mut argts = Vec::new();
+        if let Ok(r) = strip(rest, ")") {
+            rest = r;
+        } else {
+            loop {
+                let (t, r) = parse_type(rest)?;
+                argts.push(t);
+                match strip(r, ",") {
+                    Ok(r) => rest = r,
+                    Err(_) => {
+                        rest = strip(r, ")")?;
+                        break;
+                    }
+                }
+            }
+        }
+        let (rett, rest) = parse_type(strip(rest, "->")?)?;
+        return Ok((BuiltinLattice::Function(argts, Box::new(rett)), rest));
+    }
+    for (name, t) in [
+        ("f32", BuiltinLattice::Float32),
+        ("f64", BuiltinLattice::Float64),
+        ("i32", BuiltinLattice::Int32),
+        ("i64", BuiltinLattice::Int64),
+        ("i1", BuiltinLattice::Int1),
+    ] {
+        if let Some(rest) = src.strip_prefix(name) {
+            return Ok((t, rest));
+        }
+    }
+    bail!(format!("Invalid type `{}`.", src))
+}
+
+fn parse_type_exact(src: &str) -> Result<BuiltinLattice, Report> {
+    let (t, rest) = parse_type(src)?;
+    if !rest.trim().is_empty() {
+        bail!(format!(
+            "Unexpected `{}` after type in `{}`.",
+            rest.trim(),
+            src
+        ))
+    }
+    Ok(t)
+}
+
 /// Allows static type annotations for function-like operations
 /// which provide external linkage.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SignatureAttr {
     argts: Vec<BuiltinLattice>,
     rett: BuiltinLattice,
@@ -68,12 +248,75 @@ impl fmt::Display for SignatureAttr {
     }
 }
 
+impl SignatureAttr {
+    pub fn new(argts: Vec<BuiltinLattice>, rett: BuiltinLattice) -> SignatureAttr {
+        SignatureAttr { argts, rett }
+    }
+
+    pub fn get_argts(&self) -> &[BuiltinLattice] {
+        &self.argts
+    }
+
+    pub fn get_rett(&self) -> &BuiltinLattice {
+        &self.rett
+    }
+}
+
+impl GenericAttribute for SignatureAttr {
+    fn print_generic(&self) -> String {
+        let argts = self.argts.iter().map(print_type).collect::<Vec<_>>();
+        format!("({}) -> {}", argts.join(", "), print_type(&self.rett))
+    }
+
+    fn parse_generic(src: &str) -> Result<Self, Report> {
+        match parse_type_exact(src)? {
+            BuiltinLattice::Function(argts, rett) => Ok(SignatureAttr::new(argts, *rett)),
+            _ => bail!(format!("Invalid signature `{}`.", src)),
+        }
+    }
+}
+
 attribute! {
     SignatureAttr: "builtin.signature",
     trait: ProvidesSignatureAttr
 }
 
-#[derive(Debug)]
+/// A type annotation, e.g. the target type of a cast.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeAttr(BuiltinLattice);
+
+impl fmt::Display for TypeAttr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TypeAttr {
+    pub fn new(t: BuiltinLattice) -> TypeAttr {
+        TypeAttr(t)
+    }
+
+    pub fn get_type(&self) -> &BuiltinLattice {
+        &self.0
+    }
+}
+
+impl GenericAttribute for TypeAttr {
+    fn print_generic(&self) -> String {
+        print_type(&self.0)
+    }
+
+    fn parse_generic(src: &str) -> Result<Self, Report> {
+        Ok(TypeAttr(parse_type_exact(src)?))
+    }
+}
+
+attribute! {
+    TypeAttr: "builtin.type",
+    trait: ProvidesTypeAttr
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct SymbolTableAttr(HashMap<String, Var>);
 
 impl fmt::Display for SymbolTableAttr {
@@ -91,22 +334,70 @@ impl fmt::Display for SymbolTableAttr {
     }
 }
 
+impl Default for SymbolTableAttr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SymbolTableAttr {
     pub fn insert(&mut self, s: String, v: Var) {
         self.0.insert(s, v);
     }
 
+    pub fn get(&self, s: &str) -> Option<Var> {
+        self.0.get(s).copied()
+    }
+
     pub fn new() -> SymbolTableAttr {
         SymbolTableAttr(HashMap::new())
     }
 }
 
+impl GenericAttribute for SymbolTableAttr {
+    fn print_generic(&self) -> String {
+        let mut entries = self
+            .0
+            .iter()
+            .map(|(s, v)| format!("{} = {}", s, v))
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries.join(", ")
+    }
+
+    fn parse_generic(src: &str) -> Result<Self, Report> {
+        let mut tbl = SymbolTableAttr::new();
+        for entry in src.split(',').filter(|e| !e.trim().is_empty()) {
+            let v = entry
+                .rsplit_once('=')
+                .and_then(|(s, v)| Some((s.trim(), v.trim().strip_prefix('%')?)))
+                .and_then(|(s, v)| Some((s, v.parse::<usize>().ok()?)));
+            match v {
+                None => bail!(format!("Invalid symbol table entry `{}`.", entry.trim())),
+                Some((s, v)) => tbl.insert(s.to_string(), Var::new(v)),
+            }
+        }
+        Ok(tbl)
+    }
+}
+
 attribute! {
     SymbolTableAttr: "builtin.symbols",
     trait: ProvidesSymbolTableAttr
 }
 
-#[derive(Debug)]
+/// Get the operation which defines the symbol `name` in the symbol table
+/// of `op` (see [`crate::dialects::builtin::PopulateSymbolTablePass`]).
+pub fn lookup_symbol<'a>(op: &'a Operation, name: &str) -> Option<&'a Operation> {
+    let tbl = op
+        .get_intrinsic()
+        .query_ref::<dyn ProvidesSymbolTableAttr>()?;
+    tbl.verify(op).ok()?;
+    let v = tbl.get_value(op).get(name)?;
+    op.get_regions().first()?.get_op(v).map(|(_, o)| o)
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum SymbolVisibility {
     Public,
     Private,
@@ -123,7 +414,7 @@ impl fmt::Display for SymbolVisibility {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SymbolAttr(String, SymbolVisibility);
 
 impl fmt::Display for SymbolAttr {
@@ -136,6 +427,35 @@ impl SymbolAttr {
     pub fn new(s: &str) -> SymbolAttr {
         SymbolAttr(s.to_string(), SymbolVisibility::Public)
     }
+
+    pub fn get_name(&self) -> &str {
+        &self.0
+    }
+
+    pub fn get_visibility(&self) -> &SymbolVisibility {
+        &self.1
+    }
+}
+
+impl GenericAttribute for SymbolAttr {
+    fn print_generic(&self) -> String {
+        let visibility = match self.1 {
+            SymbolVisibility::Public => "public",
+            SymbolVisibility::Private => "private",
+            SymbolVisibility::Nested => "nested",
+        };
+        format!("{} {}", visibility, self.0)
+    }
+
+    fn parse_generic(src: &str) -> Result<Self, Report> {
+        let visibility = match src.split_once(' ') {
+            Some(("public", s)) => (s, SymbolVisibility::Public),
+            Some(("private", s)) => (s, SymbolVisibility::Private),
+            Some(("nested", s)) => (s, SymbolVisibility::Nested),
+            _ => bail!(format!("Invalid symbol `{}`.", src)),
+        };
+        Ok(SymbolAttr(visibility.0.to_string(), visibility.1))
+    }
 }
 
 attribute! {
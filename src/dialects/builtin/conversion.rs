@@ -0,0 +1,590 @@
+use crate::core::*;
+use crate::dialects::builtin::*;
+use crate::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use yansi::Paint;
+
+type LegalityFn = Arc<dyn Fn(&Operation) -> bool + Send + Sync>;
+
+/// The legality of an intrinsic (or namespace) in a [`ConversionTarget`].
+#[derive(Clone)]
+pub enum Legality {
+    Legal,
+    Illegal,
+
+    /// Operations are legal if the function returns `true`.
+    Dynamic(LegalityFn),
+}
+
+impl fmt::Debug for Legality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Legality::Legal => write!(f, "Legal"),
+            Legality::Illegal => write!(f, "Illegal"),
+            Legality::Dynamic(_) => write!(f, "Dynamic"),
+        }
+    }
+}
+
+/// Describes which operations are legal after a dialect conversion.
+///
+/// Legality is declared for individual intrinsics, or for whole
+/// namespaces (dialects). The legality of an intrinsic takes
+/// precedence over the legality of its namespace.
+#[derive(Debug, Clone, Default)]
+pub struct ConversionTarget {
+    intrinsics: HashMap<String, Legality>,
+    namespaces: HashMap<String, Legality>,
+}
+
+impl ConversionTarget {
+    pub fn new() -> ConversionTarget {
+        ConversionTarget::default()
+    }
+
+    pub fn add_legal_intrinsic(&mut self, intr: &dyn Intrinsic) {
+        self.intrinsics
+            .insert(intr.get_unique_id(), Legality::Legal);
+    }
+
+    pub fn add_illegal_intrinsic(&mut self, intr: &dyn Intrinsic) {
+        self.intrinsics
+            .insert(intr.get_unique_id(), Legality::Illegal);
+    }
+
+    pub fn add_dynamically_legal_intrinsic<F>(&mut self, intr: &dyn Intrinsic, f: F)
+    where
+        F: Fn(&Operation) -> bool + Send + Sync + 'static,
+    {
+        self.intrinsics
+            .insert(intr.get_unique_id(), Legality::Dynamic(Arc::new(f)));
+    }
+
+    pub fn add_legal_namespace(&mut self, namespace: &str) {
+        self.namespaces
+            .insert(namespace.to_string(), Legality::Legal);
+    }
+
+    pub fn add_illegal_namespace(&mut self, namespace: &str) {
+        self.namespaces
+            .insert(namespace.to_string(), Legality::Illegal);
+    }
+
+    pub fn get_legality(&self, op: &Operation) -> Option<&Legality> {
+        let intr = op.get_intrinsic();
+        self.intrinsics
+            .get(&intr.get_unique_id())
+            .or_else(|| self.namespaces.get(intr.get_namespace()))
+    }
+
+    /// Returns `None` if the legality of the operation is unknown.
+    pub fn is_legal(&self, op: &Operation) -> Option<bool> {
+        self.get_legality(op).map(|l| match l {
+            Legality::Legal => true,
+            Legality::Illegal => false,
+            Legality::Dynamic(f) => f(op),
+        })
+    }
+}
+
+type ConversionFn = Arc<dyn Fn(&BuiltinLattice) -> Option<BuiltinLattice> + Send + Sync>;
+type MaterializationFn =
+    Arc<dyn Fn(&mut IrRewriter, Var, &BuiltinLattice) -> Result<Option<Var>, Report> + Send + Sync>;
+
+/// Converts [`BuiltinLattice`] types during dialect conversion,
+/// and materializes casts between original and converted values.
+///
+/// Conversions are tried in reverse order of registration - the first
+/// conversion which returns `Some` is used. Types which no conversion
+/// applies to are left unchanged.
+#[derive(Clone, Default)]
+pub struct TypeConverter {
+    conversions: Vec<ConversionFn>,
+    materializations: Vec<MaterializationFn>,
+}
+
+impl fmt::Debug for TypeConverter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "TypeConverter({} conversions, {} materializations)",
+            self.conversions.len(),
+            self.materializations.len()
+        )
+    }
+}
+
+impl TypeConverter {
+    pub fn new() -> TypeConverter {
+        TypeConverter::default()
+    }
+
+    pub fn add_conversion<F>(&mut self, f: F)
+    where
+        F: Fn(&BuiltinLattice) -> Option<BuiltinLattice> + Send + Sync + 'static,
+    {
+        self.conversions.push(Arc::new(f));
+    }
+
+    /// Register a materialization, which creates an operation (at the
+    /// insertion point of the rewriter) casting a value to a type. Materializations
+    /// return `None` if they don't apply, in which case an
+    /// [`UnrealizedConversionCast`] is created.
+    pub fn add_materialization<F>(&mut self, f: F)
+    where
+        F: Fn(&mut IrRewriter, Var, &BuiltinLattice) -> Result<Option<Var>, Report>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.materializations.push(Arc::new(f));
+    }
+
+    pub fn convert_type(&self, t: &BuiltinLattice) -> BuiltinLattice {
+        self.conversions
+            .iter()
+            .rev()
+            .find_map(|f| f(t))
+            .unwrap_or_else(|| t.clone())
+    }
+
+    pub fn is_legal_type(&self, t: &BuiltinLattice) -> bool {
+        self.convert_type(t) == *t
+    }
+
+    pub fn convert_signature(&self, sig: &SignatureAttr) -> SignatureAttr {
+        SignatureAttr::new(
+            sig.get_argts()
+                .iter()
+                .map(|t| self.convert_type(t))
+                .collect(),
+            self.convert_type(sig.get_rett()),
+        )
+    }
+
+    pub fn is_legal_signature(&self, sig: &SignatureAttr) -> bool {
+        sig.get_argts().iter().all(|t| self.is_legal_type(t)) && self.is_legal_type(sig.get_rett())
+    }
+
+    /// Create an operation which casts `v` to type `t`,
+    /// at the insertion point of the rewriter.
+    pub fn materialize(
+        &self,
+        rewriter: &mut IrRewriter,
+        v: Var,
+        t: &BuiltinLattice,
+    ) -> Result<Var, Report> {
+        for f in self.materializations.iter().rev() {
+            if let Some(cast) = f(rewriter, v, t)? {
+                return Ok(cast);
+            }
+        }
+        let loc = match rewriter.get_op(v) {
+            None => LocationInfo::Unknown,
+            Some(op) => op.get_location().clone(),
+        };
+        rewriter.create(UnrealizedConversionCast.get_builder(v, t.clone(), loc)?)
+    }
+}
+
+/// A rewrite rule used by dialect conversion, which (unlike
+/// [`RewritePattern`]) has access to the [`TypeConverter`].
+pub trait ConversionPattern: Send + Sync + std::fmt::Debug {
+    fn root_intrinsic(&self) -> Option<Box<dyn Intrinsic>> {
+        None
+    }
+
+    fn benefit(&self) -> usize {
+        1
+    }
+
+    /// Try to convert the operation defining `v`.
+    /// The rewriter's insertion point is set before `v`.
+    fn match_and_rewrite(
+        &self,
+        v: Var,
+        rewriter: &mut IrRewriter,
+        converter: &TypeConverter,
+    ) -> Result<bool, Report>;
+}
+
+// Allows a `RewritePattern` to be used for conversion.
+#[derive(Debug)]
+struct RewriteConversionPattern(Box<dyn RewritePattern>);
+
+impl ConversionPattern for RewriteConversionPattern {
+    fn root_intrinsic(&self) -> Option<Box<dyn Intrinsic>> {
+        self.0.root_intrinsic()
+    }
+
+    fn benefit(&self) -> usize {
+        self.0.benefit()
+    }
+
+    fn match_and_rewrite(
+        &self,
+        v: Var,
+        rewriter: &mut IrRewriter,
+        _converter: &TypeConverter,
+    ) -> Result<bool, Report> {
+        self.0.match_and_rewrite(v, rewriter)
+    }
+}
+
+/// A set of [`ConversionPattern`] instances, ordered by benefit.
+#[derive(Debug, Default)]
+pub struct ConversionPatternSet {
+    patterns: Vec<Box<dyn ConversionPattern>>,
+}
+
+impl ConversionPatternSet {
+    pub fn new() -> ConversionPatternSet {
+        ConversionPatternSet::default()
+    }
+
+    pub fn add(&mut self, p: Box<dyn ConversionPattern>) {
+        let ind = self
+            .patterns
+            .iter()
+            .position(|q| q.benefit() < p.benefit())
+            .unwrap_or(self.patterns.len());
+        self.patterns.insert(ind, p);
+    }
+
+    /// Add a [`RewritePattern`] (e.g. one defined with `rewrite!`),
+    /// which doesn't use the type converter.
+    pub fn add_rewrite_pattern(&mut self, p: Box<dyn RewritePattern>) {
+        self.add(Box::new(RewriteConversionPattern(p)));
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    fn applicable(&self, op: &Operation) -> impl Iterator<Item = &Box<dyn ConversionPattern>> {
+        let id = op.get_intrinsic().get_unique_id();
+        self.patterns
+            .iter()
+            .filter(move |p| match p.root_intrinsic() {
+                None => true,
+                Some(intr) => intr.get_unique_id() == id,
+            })
+    }
+}
+
+/// Converts the signature (stored under `builtin.signature`) of function-like operations
+/// with the [`TypeConverter`]. Casts from the converted argument types back
+/// to the original types are materialized at the start of the entry block.
+#[derive(Debug)]
+pub struct SignatureConversionPattern;
+
+impl ConversionPattern for SignatureConversionPattern {
+    fn match_and_rewrite(
+        &self,
+        v: Var,
+        rewriter: &mut IrRewriter,
+        converter: &TypeConverter,
+    ) -> Result<bool, Report> {
+        let op = rewriter.get_op(v).unwrap();
+        let sig = match op.get_attributes().get("builtin.signature") {
+            None => return Ok(false),
+            Some(attr) => match attr.query_ref::<dyn AttributeValue<SignatureAttr>>() {
+                None => return Ok(false),
+                Some(sig) => sig.get_value().clone(),
+            },
+        };
+        if converter.is_legal_signature(&sig) {
+            return Ok(false);
+        }
+        let new_sig = converter.convert_signature(&sig);
+        let op = rewriter.get_op_mut(v).unwrap();
+        if let Some(Region::Directed(ssacfg)) = op.get_regions().first() {
+            let args = ssacfg.get_operands().to_vec();
+            let mut nested = IrRewriter::new(op);
+            nested.set_insertion_point_to_start(0);
+            for (arg, (old, new)) in args
+                .iter()
+                .zip(sig.get_argts().iter().zip(new_sig.get_argts().iter()))
+            {
+                if old == new {
+                    continue;
+                }
+                let cast = converter.materialize(&mut nested, *arg, old)?;
+                nested.replace_all_uses_with(*arg, cast);
+                nested.modify_op(cast, |c| {
+                    for o in c.get_operands_mut().iter_mut() {
+                        if *o == cast {
+                            *o = *arg;
+                        }
+                    }
+                })?;
+            }
+        }
+        rewriter.modify_op(v, |op| {
+            op.get_attributes_mut()
+                .insert("builtin.signature".to_string(), Box::new(new_sig));
+        })?;
+        Ok(true)
+    }
+}
+
+/// The mode of a dialect conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionMode {
+    /// Every operation which is explicitly illegal must be converted,
+    /// operations with unknown legality are left as is.
+    Partial,
+
+    /// Every operation must be legal after the conversion.
+    Full,
+}
+
+impl fmt::Display for ConversionMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionMode::Partial => write!(f, "Partial"),
+            ConversionMode::Full => write!(f, "Full"),
+        }
+    }
+}
+
+/// Configuration for [`apply_conversion`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConversionConfig {
+    pub mode: ConversionMode,
+
+    /// The maximum number of sweeps over each region - operations created
+    /// by a conversion may require conversion themselves, so regions are swept
+    /// until no pattern applies.
+    pub max_iterations: usize,
+}
+
+impl Default for ConversionConfig {
+    fn default() -> Self {
+        ConversionConfig {
+            mode: ConversionMode::Partial,
+            max_iterations: 10,
+        }
+    }
+}
+
+impl ConversionConfig {
+    pub fn new(mode: ConversionMode) -> ConversionConfig {
+        ConversionConfig {
+            mode,
+            ..ConversionConfig::default()
+        }
+    }
+}
+
+fn needs_conversion(op: &Operation, target: &ConversionTarget, mode: ConversionMode) -> bool {
+    match target.is_legal(op) {
+        Some(legal) => !legal,
+        None => mode == ConversionMode::Full,
+    }
+}
+
+fn convert_region(
+    op: &mut Operation,
+    region: usize,
+    target: &ConversionTarget,
+    patterns: &ConversionPatternSet,
+    converter: &TypeConverter,
+    config: &ConversionConfig,
+) -> Result<(), Report> {
+    let mode = config.mode;
+    let mut converged = false;
+    for _ in 0..config.max_iterations {
+        let mut changed = false;
+        let r = &op.get_regions()[region];
+        let vs = (0..r.get_blocks().len())
+            .flat_map(|b| r.get_block_iter(b).map(|(v, _)| v).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        for v in vs {
+            let mut rewriter = IrRewriter::new(op);
+            rewriter.set_region(region)?;
+            let candidates = match rewriter.get_op(v) {
+                Some(child) if needs_conversion(child, target, mode) => {
+                    patterns.applicable(child).collect::<Vec<_>>()
+                }
+                _ => continue,
+            };
+            for p in candidates {
+                rewriter.set_insertion_point_before(v);
+                if p.match_and_rewrite(v, &mut rewriter, converter)? {
+                    changed = true;
+                    break;
+                }
+            }
+        }
+        if !changed {
+            converged = true;
+            break;
+        }
+    }
+    let r = &op.get_regions()[region];
+    let pending = (0..r.get_blocks().len())
+        .flat_map(|b| r.get_block_iter(b))
+        .any(|(_, child)| needs_conversion(child, target, mode));
+    if !converged && pending {
+        bail!(format!(
+            "{} conversion of region {} in {} did not converge in {} iterations.",
+            mode,
+            region,
+            op.get_intrinsic(),
+            config.max_iterations
+        ))
+    }
+    let r = &op.get_regions()[region];
+    let vs = (0..r.get_blocks().len())
+        .flat_map(|b| r.get_block_iter(b).map(|(v, _)| v).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    for v in vs {
+        let (_, child) = op.get_regions_mut()[region].get_op_mut(v).unwrap();
+        for ind in 0..child.get_regions().len() {
+            convert_region(child, ind, target, patterns, converter, config)?;
+        }
+    }
+    Ok(())
+}
+
+fn collect_illegal(
+    op: &Operation,
+    target: &ConversionTarget,
+    mode: ConversionMode,
+    illegal: &mut Vec<String>,
+) {
+    for r in op.get_regions().iter() {
+        for b in 0..r.get_blocks().len() {
+            for (v, child) in r.get_block_iter(b) {
+                if needs_conversion(child, target, mode) {
+                    illegal.push(format!(
+                        "  {} at ({}, {}) in {}",
+                        child.get_intrinsic(),
+                        Paint::white(format!("{}", b)).bold(),
+                        v,
+                        op.get_intrinsic()
+                    ));
+                }
+                collect_illegal(child, target, mode, illegal);
+            }
+        }
+    }
+}
+
+/// Convert the operations nested in `op` (but not `op` itself) by applying
+/// conversion patterns to operations which are not legal for `target`.
+///
+/// Fails with a diagnostic listing the operations which remain illegal, or
+/// if a region still has operations which need conversion after
+/// `config.max_iterations` sweeps. The IR is not restored when the conversion fails.
+pub fn apply_conversion(
+    op: &mut Operation,
+    target: &ConversionTarget,
+    patterns: &ConversionPatternSet,
+    converter: &TypeConverter,
+    config: &ConversionConfig,
+) -> Result<(), Report> {
+    let mode = config.mode;
+    for ind in 0..op.get_regions().len() {
+        convert_region(op, ind, target, patterns, converter, config)?;
+    }
+    let mut illegal = Vec::new();
+    collect_illegal(op, target, mode, &mut illegal);
+    if !illegal.is_empty() {
+        bail!(format!(
+            "{} conversion of {} failed to legalize {} operation(s):\n{}",
+            mode,
+            op.get_intrinsic(),
+            illegal.len(),
+            illegal.join("\n")
+        ))
+    }
+    Ok(())
+}
+
+pub fn apply_partial_conversion(
+    op: &mut Operation,
+    target: &ConversionTarget,
+    patterns: &ConversionPatternSet,
+    converter: &TypeConverter,
+) -> Result<(), Report> {
+    let config = ConversionConfig::new(ConversionMode::Partial);
+    apply_conversion(op, target, patterns, converter, &config)
+}
+
+pub fn apply_full_conversion(
+    op: &mut Operation,
+    target: &ConversionTarget,
+    patterns: &ConversionPatternSet,
+    converter: &TypeConverter,
+) -> Result<(), Report> {
+    let config = ConversionConfig::new(ConversionMode::Full);
+    apply_conversion(op, target, patterns, converter, &config)
+}
+
+/// An [`OperationPass`] which applies a dialect conversion.
+#[derive(Debug, Clone)]
+pub struct ConversionPass {
+    target: Arc<ConversionTarget>,
+    patterns: Arc<ConversionPatternSet>,
+    converter: Arc<TypeConverter>,
+    config: ConversionConfig,
+}
+
+impl ConversionPass {
+    pub fn new(
+        target: ConversionTarget,
+        patterns: ConversionPatternSet,
+        converter: TypeConverter,
+        mode: ConversionMode,
+    ) -> ConversionPass {
+        let config = ConversionConfig::new(mode);
+        ConversionPass::with_config(target, patterns, converter, config)
+    }
+
+    pub fn with_config(
+        target: ConversionTarget,
+        patterns: ConversionPatternSet,
+        converter: TypeConverter,
+        config: ConversionConfig,
+    ) -> ConversionPass {
+        ConversionPass {
+            target: Arc::new(target),
+            patterns: Arc::new(patterns),
+            converter: Arc::new(converter),
+            config,
+        }
+    }
+}
+
+impl OperationPass for ConversionPass {
+    fn reset(&self) -> Box<dyn OperationPass> {
+        Box::new(self.clone())
+    }
+
+    fn check(&self, _op: &RwLock<Operation>) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn apply(
+        &self,
+        op_lock: &RwLock<Operation>,
+        _analysis_lock: &RwLock<AnalysisManager>,
+    ) -> Result<(), Report> {
+        let mut op = op_lock.write().unwrap();
+        apply_conversion(
+            &mut op,
+            &self.target,
+            &self.patterns,
+            &self.converter,
+            &self.config,
+        )
+    }
+}
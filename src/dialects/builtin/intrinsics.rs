@@ -1,10 +1,11 @@
 use crate::core::*;
 use crate::dialects::builtin::*;
 use crate::*;
+use std::sync::Once;
 
 intrinsic! {
     Module: ["builtin", "module"],
-    [ProvidesSymbolTableAttr], extern: []
+    [ProvidesSymbolTableAttr, IsolatedFromAbove], extern: []
 }
 
 impl Module {
@@ -25,7 +26,7 @@ impl Module {
 
 intrinsic! {
     Func: ["builtin", "func"],
-    [ProvidesSymbolAttr, ProvidesLinkageAttr, FunctionLike, RequiresTerminators],
+    [ProvidesSymbolAttr, ProvidesLinkageAttr, FunctionLike, RequiresTerminators, IsolatedFromAbove],
     extern: []
 }
 
@@ -44,3 +45,46 @@ impl Func {
         Ok(b)
     }
 }
+
+intrinsic! {
+    /// A cast between types, which is inserted during dialect conversion
+    /// when values of a converted type are used where the original type
+    /// is expected (or vice versa). The target type is stored in a [`TypeAttr`].
+    ///
+    /// These casts are expected to cancel out, or be lowered, by later conversions.
+    UnrealizedConversionCast: ["builtin", "unrealized_conversion_cast"],
+    [ProvidesTypeAttr, MemoryEffects],
+    extern: []
+}
+
+impl UnrealizedConversionCast {
+    pub fn get_builder(
+        &self,
+        operand: Var,
+        t: BuiltinLattice,
+        loc: LocationInfo,
+    ) -> Result<OperationBuilder, Report> {
+        let intr = Box::new(UnrealizedConversionCast);
+        let mut b = OperationBuilder::default(intr, loc);
+        b.push_operand(operand);
+        b.insert_attr("builtin.type", Box::new(TypeAttr::new(t)));
+        Ok(b)
+    }
+}
+
+/// Registers the intrinsics and attributes of this dialect,
+/// so that its operations can be read by [`parse_generic`].
+pub fn register_builtin_dialect() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        register_intrinsic(Box::new(Module));
+        register_intrinsic(Box::new(Func));
+        register_intrinsic(Box::new(UnrealizedConversionCast));
+        register_attribute::<ConstantAttr>("builtin.value");
+        register_attribute::<LinkageAttr>("builtin.linkage");
+        register_attribute::<SignatureAttr>("builtin.signature");
+        register_attribute::<TypeAttr>("builtin.type");
+        register_attribute::<SymbolTableAttr>("builtin.symbols");
+        register_attribute::<SymbolAttr>("builtin.symbol");
+    });
+}
@@ -1,3 +1,6 @@
+use crate::core::{LatticeJoin, LatticeMeet, LatticeWiden, SupportsInterfaceTraits};
+use crate::dialects::builtin::attributes::ConstantAttr;
+use crate::dialects::builtin::traits::{FoldResult, Foldable};
 use std::fmt;
 use yansi::Paint;
 
@@ -8,10 +11,11 @@ use yansi::Paint;
 ///
 /// In general, propagation rules are defined for most
 /// of the standard dialects (e.g. [`crate::dialects::arith`], [`crate::dialects::memref`], etc).
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum BuiltinLattice {
     Float32,
     Float64,
+    Int1,
     Int32,
     Int64,
     MemRef(Box<BuiltinLattice>),
@@ -24,6 +28,7 @@ impl fmt::Display for BuiltinLattice {
         match self {
             BuiltinLattice::Float32 => write!(f, "{}", Paint::magenta("Float32").bold()),
             BuiltinLattice::Float64 => write!(f, "{}", Paint::magenta("Float64").bold()),
+            BuiltinLattice::Int1 => write!(f, "{}", Paint::magenta("Int1").bold()),
             BuiltinLattice::Int32 => write!(f, "{}", Paint::magenta("Int32").bold()),
             BuiltinLattice::Int64 => write!(f, "{}", Paint::magenta("Int64").bold()),
             BuiltinLattice::MemRef(l) => write!(f, "{}<{}>", Paint::magenta("memref"), l),
@@ -45,3 +50,150 @@ impl fmt::Display for BuiltinLattice {
         }
     }
 }
+
+/// The lattice of constant values, used for constant propagation.
+///
+/// `Undefined` is the bottom element (no value has been
+/// propagated yet) and `Overdefined` is the top element
+/// (the value is not a known constant).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstantLattice {
+    Undefined,
+    Constant(ConstantAttr),
+    Overdefined,
+}
+
+impl ConstantLattice {
+    pub fn get_constant(&self) -> Option<&ConstantAttr> {
+        match self {
+            ConstantLattice::Constant(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// A propagation rule for [`Foldable`] intrinsics - the operation is folded
+    /// with the constant operand values. If any operand is `Undefined`, so is the result.
+    pub fn fold(
+        folder: &dyn Foldable,
+        op: &dyn SupportsInterfaceTraits,
+        vtypes: Vec<&ConstantLattice>,
+    ) -> ConstantLattice {
+        if vtypes.iter().any(|l| **l == ConstantLattice::Undefined) {
+            return ConstantLattice::Undefined;
+        }
+        let operands = vtypes.iter().map(|l| l.get_constant()).collect::<Vec<_>>();
+        match folder.fold(op, &operands) {
+            None => ConstantLattice::Overdefined,
+            Some(FoldResult::Constant(c)) => ConstantLattice::Constant(c),
+            Some(FoldResult::Var(v)) => match op.get_operands().iter().position(|o| *o == v) {
+                None => ConstantLattice::Overdefined,
+                Some(ind) => vtypes[ind].clone(),
+            },
+        }
+    }
+}
+
+impl LatticeJoin for ConstantLattice {
+    fn join(&self, other: &ConstantLattice) -> ConstantLattice {
+        match (self, other) {
+            (ConstantLattice::Undefined, v) | (v, ConstantLattice::Undefined) => v.clone(),
+            (ConstantLattice::Constant(a), ConstantLattice::Constant(b)) if a == b => self.clone(),
+            (_, _) => ConstantLattice::Overdefined,
+        }
+    }
+}
+
+impl LatticeWiden for ConstantLattice {
+    fn widen(&self, other: &ConstantLattice) -> ConstantLattice {
+        self.join(other)
+    }
+}
+
+impl LatticeMeet for ConstantLattice {
+    fn meet(&self, other: &ConstantLattice) -> ConstantLattice {
+        match (self, other) {
+            (ConstantLattice::Overdefined, v) | (v, ConstantLattice::Overdefined) => v.clone(),
+            (ConstantLattice::Constant(a), ConstantLattice::Constant(b)) if a == b => self.clone(),
+            (_, _) => ConstantLattice::Undefined,
+        }
+    }
+}
+
+impl fmt::Display for ConstantLattice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConstantLattice::Undefined => write!(f, "{}", Paint::magenta("undef").bold()),
+            ConstantLattice::Constant(c) => write!(f, "{}", c),
+            ConstantLattice::Overdefined => write!(f, "{}", Paint::magenta("overdef").bold()),
+        }
+    }
+}
+
+/// The lattice of types, over the [`BuiltinLattice`] types.
+///
+/// `Bottom` is the bottom element (no value has been propagated yet)
+/// and `Top` is the top element (the value may have any type).
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeLattice {
+    Bottom,
+    Type(BuiltinLattice),
+    Top,
+}
+
+impl TypeLattice {
+    pub fn get_type(&self) -> Option<&BuiltinLattice> {
+        match self {
+            TypeLattice::Type(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// The type of a constant, or `Top` if the
+    /// width of the constant is not a builtin type.
+    pub fn from_constant(c: &ConstantAttr) -> TypeLattice {
+        match c {
+            ConstantAttr::Integer(_, 1) => TypeLattice::Type(BuiltinLattice::Int1),
+            ConstantAttr::Integer(_, 32) => TypeLattice::Type(BuiltinLattice::Int32),
+            ConstantAttr::Integer(_, 64) => TypeLattice::Type(BuiltinLattice::Int64),
+            ConstantAttr::Float(_, 32) => TypeLattice::Type(BuiltinLattice::Float32),
+            ConstantAttr::Float(_, 64) => TypeLattice::Type(BuiltinLattice::Float64),
+            _ => TypeLattice::Top,
+        }
+    }
+}
+
+impl LatticeJoin for TypeLattice {
+    fn join(&self, other: &TypeLattice) -> TypeLattice {
+        match (self, other) {
+            (TypeLattice::Bottom, v) | (v, TypeLattice::Bottom) => v.clone(),
+            (TypeLattice::Type(a), TypeLattice::Type(b)) if a == b => self.clone(),
+            (_, _) => TypeLattice::Top,
+        }
+    }
+}
+
+impl LatticeWiden for TypeLattice {
+    fn widen(&self, other: &TypeLattice) -> TypeLattice {
+        self.join(other)
+    }
+}
+
+impl LatticeMeet for TypeLattice {
+    fn meet(&self, other: &TypeLattice) -> TypeLattice {
+        match (self, other) {
+            (TypeLattice::Top, v) | (v, TypeLattice::Top) => v.clone(),
+            (TypeLattice::Type(a), TypeLattice::Type(b)) if a == b => self.clone(),
+            (_, _) => TypeLattice::Bottom,
+        }
+    }
+}
+
+impl fmt::Display for TypeLattice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeLattice::Bottom => write!(f, "{}", Paint::magenta("{}").bold()),
+            TypeLattice::Type(t) => write!(f, "{}", t),
+            TypeLattice::Top => write!(f, "{}", Paint::magenta("top").bold()),
+        }
+    }
+}
@@ -6,6 +6,7 @@
 //! as closely as possible.
 
 mod attributes;
+mod conversion;
 mod intrinsics;
 mod lattice;
 mod passes;
@@ -13,11 +14,21 @@ mod traits;
 
 pub use self::{
     attributes::{
-        ConstantAttr, LinkageAttr, ProvidesConstantAttr, ProvidesLinkageAttr, ProvidesSymbolAttr,
-        ProvidesSymbolTableAttr, SymbolAttr, SymbolTableAttr,
+        lookup_symbol, ConstantAttr, LinkageAttr, ProvidesConstantAttr, ProvidesLinkageAttr,
+        ProvidesSignatureAttr, ProvidesSymbolAttr, ProvidesSymbolTableAttr, ProvidesTypeAttr,
+        SignatureAttr, SymbolAttr, SymbolTableAttr, SymbolVisibility, TypeAttr,
+    },
+    conversion::{
+        apply_conversion, apply_full_conversion, apply_partial_conversion, ConversionConfig,
+        ConversionMode, ConversionPass, ConversionPattern, ConversionPatternSet, ConversionTarget,
+        Legality, SignatureConversionPattern, TypeConverter,
+    },
+    intrinsics::{register_builtin_dialect, Func, Module, UnrealizedConversionCast},
+    lattice::{BuiltinLattice, ConstantLattice, TypeLattice},
+    passes::{register_builtin_passes, CSEPass, DCEPass, PopulateSymbolTablePass},
+    traits::{
+        Commutative, EffectResource, FoldResult, Foldable, FunctionLike,
+        HasCanonicalizationPatterns, MemoryEffect, MemoryEffects, NonVariadic, RequiresTerminators,
+        Terminator,
     },
-    intrinsics::{Func, Module},
-    lattice::BuiltinLattice,
-    passes::PopulateSymbolTablePass,
-    traits::{FunctionLike, NonVariadic, RequiresTerminators, Terminator},
 };
@@ -1,15 +1,37 @@
 use crate::core::*;
 use crate::dialects::builtin::*;
 use crate::*;
-use std::sync::RwLock;
+use std::collections::HashMap;
+use std::sync::{Once, RwLock};
 use yansi::Paint;
 
-#[derive(Debug)]
-pub struct PopulateSymbolTablePass;
+#[derive(Debug, Clone)]
+pub struct PopulateSymbolTablePass {
+    inserted: Statistic,
+}
+
+impl Default for PopulateSymbolTablePass {
+    fn default() -> Self {
+        PopulateSymbolTablePass {
+            inserted: Statistic::new(
+                "symbols-inserted",
+                "Number of symbols inserted into the symbol table",
+            ),
+        }
+    }
+}
 
 impl OperationPass for PopulateSymbolTablePass {
     fn reset(&self) -> Box<dyn OperationPass> {
-        Box::new(PopulateSymbolTablePass)
+        Box::new(PopulateSymbolTablePass::default())
+    }
+
+    fn get_argument(&self) -> String {
+        "populate-symbol-table".to_string()
+    }
+
+    fn get_statistics(&self) -> Vec<&Statistic> {
+        vec![&self.inserted]
     }
 
     fn check(&self, op_lock: &RwLock<Operation>) -> Result<(), Report> {
@@ -36,12 +58,10 @@ impl OperationPass for PopulateSymbolTablePass {
             let mut v: Vec<(String, Var)> = Vec::new();
             for (var, child) in region.get_block_iter(0) {
                 let intr = child.get_intrinsic();
-                match intr.query_ref::<dyn ProvidesSymbolAttr>() {
-                    None => (),
-                    Some(trt) => match trt.verify(op) {
-                        Ok(_) => v.push((trt.get_value(child).to_string(), var)),
-                        Err(_) => (),
-                    },
+                if let Some(trt) = intr.query_ref::<dyn ProvidesSymbolAttr>() {
+                    if trt.verify(child).is_ok() {
+                        v.push((trt.get_value(child).get_name().to_string(), var));
+                    }
                 }
             }
             v
@@ -52,9 +72,275 @@ impl OperationPass for PopulateSymbolTablePass {
             .query_ref::<dyn ProvidesSymbolTableAttr>()
             .unwrap()
             .get_value_mut(&mut *op);
+        self.inserted.add(v.len());
         for (s, v) in v.into_iter() {
             tbl.insert(s, v);
         }
         Ok(())
     }
 }
+
+/// Common subexpression elimination.
+///
+/// A pure operation (see [`MemoryEffects`]) is replaced by an earlier operation
+/// with the same intrinsic, operands and attributes, if the earlier operation
+/// dominates it (in `SSACFG` regions - in `Graph` regions, any equivalent operation
+/// can be used). Operands of [`Commutative`] intrinsics are compared
+/// irrespective of order. Operations which hold regions or successors are never eliminated.
+///
+/// Nested regions are processed recursively.
+#[derive(Debug, Clone)]
+pub struct CSEPass {
+    eliminated: Statistic,
+}
+
+impl Default for CSEPass {
+    fn default() -> Self {
+        CSEPass {
+            eliminated: Statistic::new(
+                "ops-eliminated",
+                "Number of common subexpressions eliminated",
+            ),
+        }
+    }
+}
+
+type CSEKey = (String, Vec<Var>);
+
+fn cse_key(op: &Operation) -> Option<CSEKey> {
+    let intr = op.get_intrinsic();
+    if !intr
+        .query_ref::<dyn MemoryEffects>()
+        .is_some_and(|e| e.is_pure(op))
+        || !op.get_regions().is_empty()
+        || !op.get_successors().is_empty()
+    {
+        return None;
+    }
+    let mut operands = op.get_operands().to_vec();
+    if intr.query_ref::<dyn Commutative>().is_some() {
+        operands.sort();
+    }
+    Some((intr.get_unique_id(), operands))
+}
+
+fn cse_attributes_eq(a: &Operation, b: &Operation) -> bool {
+    let (a, b) = (a.get_attributes(), b.get_attributes());
+    a.len() == b.len()
+        && a.iter().all(|(k, v)| match b.get(k) {
+            None => false,
+            Some(w) => v.obj_partial_eq(&**w),
+        })
+}
+
+fn cse_region(r: &mut Region) -> usize {
+    let (order, dom) = match r {
+        Region::Directed(_) => {
+            let dom = DominanceInfo::new(r);
+            let order = dom
+                .get_preorder()
+                .into_iter()
+                .flat_map(|b| r.get_block_iter(b).map(|(v, _)| v).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            (order, Some(dom))
+        }
+        Region::Undirected(_) => (r.get_block_iter(0).map(|(v, _)| v).collect(), None),
+    };
+    let mut count = 0;
+    let mut known: HashMap<CSEKey, Vec<Var>> = HashMap::new();
+    for v in order {
+        let (_, op) = r.get_op(v).unwrap();
+        let key = match cse_key(op) {
+            None => continue,
+            Some(key) => key,
+        };
+        let blk = r.get_op_position(v).unwrap().0;
+        let candidates = known.entry(key).or_default();
+        let existing = candidates.iter().find(|w| {
+            let (_, other) = r.get_op(**w).unwrap();
+            let dominates = match &dom {
+                None => true,
+                Some(dom) => dom.dominates(r.get_op_position(**w).unwrap().0, blk),
+            };
+            dominates && cse_attributes_eq(op, other)
+        });
+        match existing {
+            None => candidates.push(v),
+            Some(w) => {
+                let w = *w;
+                r.replace_all_uses(v, w);
+                r.remove_op(v);
+                count += 1;
+            }
+        }
+    }
+    for b in 0..r.get_blocks().len() {
+        for v in r.get_block_iter(b).map(|(v, _)| v).collect::<Vec<_>>() {
+            let (_, op) = r.get_op_mut(v).unwrap();
+            for nested in op.get_regions_mut().iter_mut() {
+                count += cse_region(nested);
+            }
+        }
+    }
+    count
+}
+
+impl OperationPass for CSEPass {
+    fn reset(&self) -> Box<dyn OperationPass> {
+        Box::new(CSEPass::default())
+    }
+
+    fn get_argument(&self) -> String {
+        "cse".to_string()
+    }
+
+    fn get_statistics(&self) -> Vec<&Statistic> {
+        vec![&self.eliminated]
+    }
+
+    fn check(&self, _op: &RwLock<Operation>) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn apply(
+        &self,
+        op_lock: &RwLock<Operation>,
+        _analysis_lock: &RwLock<AnalysisManager>,
+    ) -> Result<(), Report> {
+        let mut op = op_lock.write().unwrap();
+        let mut count = 0;
+        for r in op.get_regions_mut().iter_mut() {
+            count += cse_region(r);
+        }
+        self.eliminated.add(count);
+        tracing::debug!("Eliminated {} common subexpressions.", count);
+        Ok(())
+    }
+}
+
+/// Dead code elimination.
+///
+/// Removes blocks which are unreachable from the entry block of `SSACFG` regions,
+/// and operations whose results are unused and which have no side effects - pure operations
+/// (see [`MemoryEffects`]), and allocations whose only effect
+/// is allocating their result. Operations which hold regions or successors are never removed.
+///
+/// Nested regions are processed recursively.
+#[derive(Debug, Clone)]
+pub struct DCEPass {
+    eliminated: Statistic,
+}
+
+impl Default for DCEPass {
+    fn default() -> Self {
+        DCEPass {
+            eliminated: Statistic::new("ops-eliminated", "Number of dead operations eliminated"),
+        }
+    }
+}
+
+fn is_trivially_dead(r: &Region, v: Var, op: &Operation) -> bool {
+    if !op.get_regions().is_empty() || !op.get_successors().is_empty() {
+        return false;
+    }
+    let removable = match op.get_intrinsic().query_ref::<dyn MemoryEffects>() {
+        None => false,
+        Some(e) => e
+            .get_effects(op)
+            .iter()
+            .all(|eff| *eff == (MemoryEffect::Allocate, EffectResource::Result)),
+    };
+    removable && r.get_uses(v).is_empty()
+}
+
+fn dce_region(r: &mut Region) -> Result<usize, Report> {
+    let mut count = 0;
+    if let Region::Directed(_) = r {
+        let dom = DominanceInfo::new(r);
+        for b in (0..r.get_blocks().len()).rev() {
+            if !dom.is_reachable(b) {
+                count += r.get_block(b).get_ops().len();
+                r.remove_block(b)?;
+            }
+        }
+    }
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for b in (0..r.get_blocks().len()).rev() {
+            let vs = r.get_block_iter(b).map(|(v, _)| v).collect::<Vec<_>>();
+            for v in vs.into_iter().rev() {
+                let (_, op) = r.get_op(v).unwrap();
+                if is_trivially_dead(r, v, op) {
+                    r.remove_op(v);
+                    count += 1;
+                    changed = true;
+                }
+            }
+        }
+    }
+    for b in 0..r.get_blocks().len() {
+        for v in r.get_block_iter(b).map(|(v, _)| v).collect::<Vec<_>>() {
+            let (_, op) = r.get_op_mut(v).unwrap();
+            for nested in op.get_regions_mut().iter_mut() {
+                count += dce_region(nested)?;
+            }
+        }
+    }
+    Ok(count)
+}
+
+impl OperationPass for DCEPass {
+    fn reset(&self) -> Box<dyn OperationPass> {
+        Box::new(DCEPass::default())
+    }
+
+    fn get_argument(&self) -> String {
+        "dce".to_string()
+    }
+
+    fn get_statistics(&self) -> Vec<&Statistic> {
+        vec![&self.eliminated]
+    }
+
+    fn check(&self, _op: &RwLock<Operation>) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn apply(
+        &self,
+        op_lock: &RwLock<Operation>,
+        _analysis_lock: &RwLock<AnalysisManager>,
+    ) -> Result<(), Report> {
+        let mut op = op_lock.write().unwrap();
+        let mut count = 0;
+        for r in op.get_regions_mut().iter_mut() {
+            count += dce_region(r)?;
+        }
+        self.eliminated.add(count);
+        tracing::debug!("Eliminated {} dead operations.", count);
+        Ok(())
+    }
+}
+
+/// Registers the passes of this dialect (and pass managers for [`Module`]
+/// and [`Func`]) for use in textual pipelines.
+pub fn register_builtin_passes() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        register_pass_manager(Module);
+        register_pass_manager(Func);
+        register_pass(
+            "populate-symbol-table",
+            "Populate the symbol table of an operation.",
+            &[],
+            |_| Ok(Box::new(PopulateSymbolTablePass::default())),
+        );
+        register_pass("cse", "Eliminate common subexpressions.", &[], |_| {
+            Ok(Box::new(CSEPass::default()))
+        });
+        register_pass("dce", "Eliminate dead code.", &[], |_| {
+            Ok(Box::new(DCEPass::default()))
+        });
+    });
+}
@@ -1,7 +1,6 @@
-use crate::core::{AttributeValue, Region, SupportsInterfaceTraits, Var};
+use crate::core::{Region, RewritePatternSet, SupportsInterfaceTraits, Var};
 use crate::dialects::builtin::*;
 use crate::{bail, Report};
-use std::collections::HashMap;
 use yansi::Paint;
 
 pub trait Terminator {
@@ -10,24 +9,33 @@ pub trait Terminator {
     }
 }
 
+/// Operations of `Commutative` intrinsics compute
+/// the same result for any order of their operands.
+pub trait Commutative {
+    fn verify(&self, _op: &dyn SupportsInterfaceTraits) -> Result<(), Report> {
+        Ok(())
+    }
+}
+
 pub trait RequiresTerminators {
     fn verify(&self, op: &dyn SupportsInterfaceTraits) -> Result<(), Report> {
         for r in op.get_regions().iter() {
             for (ind, _) in r.get_blocks().iter().enumerate() {
                 match r.get_block_iter(ind).last() {
                     None => bail!(format!("Block {} is empty in {}.", ind, op.get_intrinsic())),
-                    Some((v, term)) => match term.get_intrinsic().query_ref::<dyn Terminator>() {
-                        None => bail!(format!(
-                            "{} is not {} traited, so is not a valid terminator.\n\n{}\n=> In {} at ({}, {}).",
-                            term.get_intrinsic(),
-                            Paint::magenta("Terminator").bold(),
-                            op,
-                            op.get_intrinsic(),
-                            Paint::white(format!("{}", ind)).bold(),
-                            v
-                        )),
-                        Some(_) => (),
-                    },
+                    Some((v, term)) => {
+                        if term.get_intrinsic().query_ref::<dyn Terminator>().is_none() {
+                            bail!(format!(
+                                "{} is not {} traited, so is not a valid terminator.\n\n{}\n=> In {} at ({}, {}).",
+                                term.get_intrinsic(),
+                                Paint::magenta("Terminator").bold(),
+                                op,
+                                op.get_intrinsic(),
+                                Paint::white(format!("{}", ind)).bold(),
+                                v
+                            ))
+                        }
+                    }
                 };
             }
         }
@@ -61,3 +69,90 @@ pub trait FunctionLike: ProvidesSymbolAttr {
 pub trait NonVariadic {
     fn verify(&self, op: &dyn SupportsInterfaceTraits) -> Result<(), Report>;
 }
+
+/// The result of folding an operation with [`Foldable`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum FoldResult {
+    /// The operation computes a constant value.
+    Constant(ConstantAttr),
+
+    /// The operation computes an existing value.
+    Var(Var),
+}
+
+/// An "extern" interface for intrinsics whose operations can be
+/// evaluated at compile time, given the constant values of (some of) their operands.
+///
+/// `operands` is aligned with the operands of `op`, an entry is `Some`
+/// when the operand is defined by a constant operation.
+pub trait Foldable {
+    fn verify(&self, _op: &dyn SupportsInterfaceTraits) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn fold(
+        &self,
+        op: &dyn SupportsInterfaceTraits,
+        operands: &[Option<&ConstantAttr>],
+    ) -> Option<FoldResult>;
+}
+
+/// An "extern" interface for intrinsics which provide canonicalization patterns,
+/// which are applied (together with folding) by the `CanonicalizePass`
+/// for every intrinsic which occurs in the canonicalized operation.
+///
+/// Patterns can also be attached to existing intrinsics
+/// with `dynamic_interfaces!`.
+pub trait HasCanonicalizationPatterns {
+    fn verify(&self, _op: &dyn SupportsInterfaceTraits) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn get_canonicalization_patterns(&self, patterns: &mut RewritePatternSet);
+}
+
+/// The kinds of memory side effects, see [`MemoryEffects`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryEffect {
+    Read,
+    Write,
+    Allocate,
+    Free,
+}
+
+/// The resource which a [`MemoryEffect`] acts on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EffectResource {
+    /// Any memory - used when the effect can't be attributed to a specific value.
+    Default,
+
+    /// The value referenced by the operand at the given index.
+    Operand(usize),
+
+    /// The value produced by the operation.
+    Result,
+}
+
+/// Describes the memory side effects of operations.
+///
+/// Listing `MemoryEffects` in the trait list of `intrinsic!` declares
+/// that operations have no side effects (they are pure). Intrinsics
+/// with side effects list `MemoryEffects` as an "extern" interface,
+/// and implement `get_effects`. Operations of intrinsics which don't implement
+/// `MemoryEffects` are conservatively assumed to have arbitrary side effects.
+pub trait MemoryEffects {
+    fn verify(&self, _op: &dyn SupportsInterfaceTraits) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn get_effects(
+        &self,
+        _op: &dyn SupportsInterfaceTraits,
+    ) -> Vec<(MemoryEffect, EffectResource)> {
+        Vec::new()
+    }
+
+    fn is_pure(&self, op: &dyn SupportsInterfaceTraits) -> bool {
+        self.get_effects(op).is_empty()
+    }
+}
@@ -0,0 +1,126 @@
+use crate::core::*;
+use crate::dialects::memref::intrinsics::*;
+use crate::*;
+use std::sync::Once;
+
+fn get_indices(op: &Operation, args: &[&RuntimeValue]) -> Result<Vec<usize>, Report> {
+    match args
+        .iter()
+        .map(|v| v.as_index())
+        .collect::<Option<Vec<_>>>()
+    {
+        None => bail!(format!(
+            "{} requires non-negative integer sizes (or indices).",
+            op.get_intrinsic()
+        )),
+        Some(indices) => Ok(indices),
+    }
+}
+
+fn get_memref<'a>(
+    op: &Operation,
+    args: &[&'a RuntimeValue],
+    ind: usize,
+) -> Result<&'a RuntimeValue, Report> {
+    match args.get(ind) {
+        Some(v @ RuntimeValue::MemRef(_)) => Ok(v),
+        _ => bail!(format!(
+            "{} requires a memref as operand {}.",
+            op.get_intrinsic(),
+            ind
+        )),
+    }
+}
+
+impl ConcreteSemantics for Alloc {
+    fn evaluate(
+        &self,
+        op: &Operation,
+        args: Vec<&RuntimeValue>,
+        heap: &mut Heap,
+    ) -> Result<Option<RuntimeValue>, Report> {
+        Ok(Some(heap.alloc(get_indices(op, &args)?)))
+    }
+}
+
+impl ConcreteSemantics for Alloca {
+    fn evaluate(
+        &self,
+        op: &Operation,
+        args: Vec<&RuntimeValue>,
+        heap: &mut Heap,
+    ) -> Result<Option<RuntimeValue>, Report> {
+        Ok(Some(heap.alloc_scoped(get_indices(op, &args)?)))
+    }
+}
+
+impl ConcreteSemantics for Copyto {
+    fn evaluate(
+        &self,
+        op: &Operation,
+        args: Vec<&RuntimeValue>,
+        heap: &mut Heap,
+    ) -> Result<Option<RuntimeValue>, Report> {
+        let src = heap.get(get_memref(op, &args, 0)?)?.clone();
+        heap.get_mut(get_memref(op, &args, 1)?)?.copy_from(&src)?;
+        Ok(None)
+    }
+}
+
+impl ConcreteSemantics for Dealloc {
+    fn evaluate(
+        &self,
+        op: &Operation,
+        args: Vec<&RuntimeValue>,
+        heap: &mut Heap,
+    ) -> Result<Option<RuntimeValue>, Report> {
+        heap.free(get_memref(op, &args, 0)?)?;
+        Ok(None)
+    }
+}
+
+impl ConcreteSemantics for Load {
+    fn evaluate(
+        &self,
+        op: &Operation,
+        args: Vec<&RuntimeValue>,
+        heap: &mut Heap,
+    ) -> Result<Option<RuntimeValue>, Report> {
+        let m = get_memref(op, &args, 0)?;
+        let indices = get_indices(op, &args[1..])?;
+        Ok(Some(heap.get(m)?.load(&indices)?))
+    }
+}
+
+impl ConcreteSemantics for Store {
+    fn evaluate(
+        &self,
+        op: &Operation,
+        args: Vec<&RuntimeValue>,
+        heap: &mut Heap,
+    ) -> Result<Option<RuntimeValue>, Report> {
+        let (v, m) = (args.first(), get_memref(op, &args, 1)?);
+        let indices = get_indices(op, &args[2..])?;
+        match v {
+            None => bail!(format!("{} requires a value to store.", op.get_intrinsic())),
+            Some(v) => heap.get_mut(m)?.store(&indices, (*v).clone())?,
+        }
+        Ok(None)
+    }
+}
+
+/// Registers the [`ConcreteSemantics`] of the intrinsics
+/// of this dialect (as dynamic interfaces).
+pub fn register_concrete_semantics() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        dynamic_interfaces! {
+            Alloc: dyn ConcreteSemantics;
+            Alloca: dyn ConcreteSemantics;
+            Copyto: dyn ConcreteSemantics;
+            Dealloc: dyn ConcreteSemantics;
+            Load: dyn ConcreteSemantics;
+            Store: dyn ConcreteSemantics;
+        }
+    });
+}
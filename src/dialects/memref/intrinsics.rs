@@ -1,26 +1,198 @@
 use crate::core::*;
+use crate::dialects::builtin::{EffectResource, MemoryEffect, MemoryEffects};
 use crate::*;
+use std::sync::Once;
 
 intrinsic! {
+    /// Allocates a buffer, the operands are the sizes of its dimensions.
     Alloc: ["memref", "alloc"],
     [],
-    extern: []
+    extern: [MemoryEffects]
+}
+
+impl MemoryEffects for Alloc {
+    fn get_effects(
+        &self,
+        _op: &dyn SupportsInterfaceTraits,
+    ) -> Vec<(MemoryEffect, EffectResource)> {
+        vec![(MemoryEffect::Allocate, EffectResource::Result)]
+    }
+}
+
+impl Alloc {
+    pub fn get_builder(
+        &self,
+        operands: Vec<Var>,
+        loc: LocationInfo,
+    ) -> Result<OperationBuilder, Report> {
+        let intr = Box::new(Alloc);
+        let mut b = OperationBuilder::default(intr, loc);
+        b.set_operands(operands);
+        Ok(b)
+    }
 }
 
 intrinsic! {
+    /// Allocates a buffer which is freed when the enclosing function returns,
+    /// the operands are the sizes of its dimensions.
     Alloca: ["memref", "alloca"],
     [],
-    extern: []
+    extern: [MemoryEffects]
+}
+
+impl MemoryEffects for Alloca {
+    fn get_effects(
+        &self,
+        _op: &dyn SupportsInterfaceTraits,
+    ) -> Vec<(MemoryEffect, EffectResource)> {
+        vec![(MemoryEffect::Allocate, EffectResource::Result)]
+    }
+}
+
+impl Alloca {
+    pub fn get_builder(
+        &self,
+        operands: Vec<Var>,
+        loc: LocationInfo,
+    ) -> Result<OperationBuilder, Report> {
+        let intr = Box::new(Alloca);
+        let mut b = OperationBuilder::default(intr, loc);
+        b.set_operands(operands);
+        Ok(b)
+    }
 }
 
 intrinsic! {
+    /// Copies the memref referenced by the first operand
+    /// into the memref referenced by the second operand.
     Copyto: ["memref", "copyto"],
     [],
-    extern: []
+    extern: [MemoryEffects]
+}
+
+impl MemoryEffects for Copyto {
+    fn get_effects(
+        &self,
+        _op: &dyn SupportsInterfaceTraits,
+    ) -> Vec<(MemoryEffect, EffectResource)> {
+        vec![
+            (MemoryEffect::Read, EffectResource::Operand(0)),
+            (MemoryEffect::Write, EffectResource::Operand(1)),
+        ]
+    }
+}
+
+impl Copyto {
+    pub fn get_builder(
+        &self,
+        operands: Vec<Var>,
+        loc: LocationInfo,
+    ) -> Result<OperationBuilder, Report> {
+        let intr = Box::new(Copyto);
+        let mut b = OperationBuilder::default(intr, loc);
+        b.set_operands(operands);
+        Ok(b)
+    }
 }
 
 intrinsic! {
     Dealloc: ["memref", "dealloc"],
     [],
-    extern: []
+    extern: [MemoryEffects]
+}
+
+impl MemoryEffects for Dealloc {
+    fn get_effects(
+        &self,
+        _op: &dyn SupportsInterfaceTraits,
+    ) -> Vec<(MemoryEffect, EffectResource)> {
+        vec![(MemoryEffect::Free, EffectResource::Operand(0))]
+    }
+}
+
+impl Dealloc {
+    pub fn get_builder(
+        &self,
+        operands: Vec<Var>,
+        loc: LocationInfo,
+    ) -> Result<OperationBuilder, Report> {
+        let intr = Box::new(Dealloc);
+        let mut b = OperationBuilder::default(intr, loc);
+        b.set_operands(operands);
+        Ok(b)
+    }
+}
+
+intrinsic! {
+    /// Loads the element of the memref referenced by the first operand,
+    /// at the indices given by the remaining operands.
+    Load: ["memref", "load"],
+    [],
+    extern: [MemoryEffects]
+}
+
+impl MemoryEffects for Load {
+    fn get_effects(
+        &self,
+        _op: &dyn SupportsInterfaceTraits,
+    ) -> Vec<(MemoryEffect, EffectResource)> {
+        vec![(MemoryEffect::Read, EffectResource::Operand(0))]
+    }
+}
+
+impl Load {
+    pub fn get_builder(
+        &self,
+        operands: Vec<Var>,
+        loc: LocationInfo,
+    ) -> Result<OperationBuilder, Report> {
+        let intr = Box::new(Load);
+        let mut b = OperationBuilder::default(intr, loc);
+        b.set_operands(operands);
+        Ok(b)
+    }
+}
+
+intrinsic! {
+    /// Stores the first operand into the memref referenced by the second
+    /// operand, at the indices given by the remaining operands.
+    Store: ["memref", "store"],
+    [],
+    extern: [MemoryEffects]
+}
+
+impl MemoryEffects for Store {
+    fn get_effects(
+        &self,
+        _op: &dyn SupportsInterfaceTraits,
+    ) -> Vec<(MemoryEffect, EffectResource)> {
+        vec![(MemoryEffect::Write, EffectResource::Operand(1))]
+    }
+}
+
+impl Store {
+    pub fn get_builder(
+        &self,
+        operands: Vec<Var>,
+        loc: LocationInfo,
+    ) -> Result<OperationBuilder, Report> {
+        let intr = Box::new(Store);
+        let mut b = OperationBuilder::default(intr, loc);
+        b.set_operands(operands);
+        Ok(b)
+    }
+}
+
+/// Registers the intrinsics of this dialect,
+/// so that its operations can be read by [`parse_generic`].
+pub fn register_memref_dialect() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        register_intrinsic(Box::new(Alloc));
+        register_intrinsic(Box::new(Alloca));
+        register_intrinsic(Box::new(Copyto));
+        register_intrinsic(Box::new(Dealloc));
+        register_intrinsic(Box::new(Load));
+        register_intrinsic(Box::new(Store));
+    });
 }
@@ -1,4 +1,5 @@
+mod concrete;
 mod intrinsics;
 mod traits;
 
-pub use self::{intrinsics::*, traits::*};
+pub use self::{concrete::register_concrete_semantics, intrinsics::*, traits::*};
@@ -0,0 +1,126 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+
+fn register() {
+    abstraps::dialects::arith::register_constant_semantics();
+    abstraps::dialects::base::register_constant_semantics();
+}
+
+fn constant(c: i64) -> Result<OperationBuilder, Report> {
+    Constant.get_builder(ConstantAttr::Integer(c, 64), LocationInfo::Unknown)
+}
+
+fn interpret(
+    op: &Operation,
+    env: Vec<Option<ConstantLattice>>,
+) -> Result<InterpreterFrame<ConstantLattice>, Report> {
+    let mut interp = Interpreter::new(op, env);
+    interp.run(op)?;
+    interp.clone_frame()
+}
+
+// Branches on `x` to `left` (which computes `1 + 3`) or `right`
+// (which forwards `4`, or `x` itself), and returns the value from the merge block.
+fn diamond(forward_x: bool) -> Result<(Operation, Var), Report> {
+    let mut func = Func.get_builder("diamond", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let c1 = func.push(constant(1)?)?;
+    let c3 = func.push(constant(3)?)?;
+    let c4 = func.push(constant(4)?)?;
+    let exit = func.new_block(BasicBlock::default())?;
+    let e = func.push_arg_to(exit)?;
+    let left = func.new_block(BasicBlock::default())?;
+    let a = func.push_arg_to(left)?;
+    let right = func.new_block(BasicBlock::default())?;
+    func.with_block(left, |b| {
+        let v = b.push(Addi.get_builder(vec![a, c1], LocationInfo::Unknown)?)?;
+        b.push(Branch.get_builder(vec![v], vec![exit.get_block()], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.with_block(right, |b| {
+        let v = if forward_x { x } else { c4 };
+        b.push(Branch.get_builder(vec![v], vec![exit.get_block()], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.with_block(exit, |b| {
+        b.push(Return.get_builder(vec![e], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.push(ConditionalBranch.get_builder(
+        vec![x, c3],
+        vec![left.get_block(), right.get_block()],
+        LocationInfo::Unknown,
+    )?)?;
+    Ok((func.finish()?, e))
+}
+
+#[test]
+fn absint_branches_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    register();
+    let (op, e) = diamond(false)?;
+    let frame = interpret(&op, vec![Some(ConstantLattice::Overdefined)])?;
+    println!("{}", op);
+    println!("{}", frame);
+
+    // Both predecessors of the merge block forward `4`.
+    let four = ConstantLattice::Constant(ConstantAttr::Integer(4, 64));
+    assert!((0..4).all(|b| frame.is_executable(b)));
+    assert_eq!(frame.get(e), Some(&four));
+    assert_eq!(frame.get_ret(), Some(four));
+
+    // Values from different predecessors are joined.
+    let (op, e) = diamond(true)?;
+    let frame = interpret(&op, vec![Some(ConstantLattice::Overdefined)])?;
+    assert_eq!(frame.get(e), Some(&ConstantLattice::Overdefined));
+    assert_eq!(frame.get_ret(), Some(ConstantLattice::Overdefined));
+    Ok(())
+}
+
+#[test]
+fn absint_loop_0() -> Result<(), Report> {
+    register();
+    let mut func = Func.get_builder("loop", LocationInfo::Unknown)?;
+    let c0 = func.push(constant(0)?)?;
+    let header = func.new_block(BasicBlock::default())?;
+    let i = func.push_arg_to(header)?;
+    let exit = func.new_block(BasicBlock::default())?;
+    let r = func.push_arg_to(exit)?;
+    let mut next = None;
+    func.with_block(header, |b| {
+        let c1 = b.push(constant(1)?)?;
+        let n = b.push(Addi.get_builder(vec![i, c1], LocationInfo::Unknown)?)?;
+        let c10 = b.push(constant(10)?)?;
+        let c =
+            b.push(Cmpi.get_builder(CmpiPredicate::Slt, vec![n, c10], LocationInfo::Unknown)?)?;
+        b.push(ConditionalBranch.get_builder(
+            vec![c, n, i],
+            vec![header.get_block(), exit.get_block()],
+            LocationInfo::Unknown,
+        )?)?;
+        next = Some(n);
+        Ok(())
+    })?;
+    func.with_block(exit, |b| {
+        b.push(Return.get_builder(vec![r], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.push(Branch.get_builder(vec![c0], vec![header.get_block()], LocationInfo::Unknown)?)?;
+    let op = func.finish()?;
+    let frame = interpret(&op, Vec::new())?;
+    println!("{}", op);
+    println!("{}", frame);
+
+    // The induction variable changes along the back edge,
+    // so the fixpoint is overdefined.
+    assert_eq!(frame.get(i), Some(&ConstantLattice::Overdefined));
+    assert_eq!(
+        frame.get(next.unwrap()),
+        Some(&ConstantLattice::Overdefined)
+    );
+    assert_eq!(frame.get_ret(), Some(ConstantLattice::Overdefined));
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+
+intrinsic! {
+    /// A structured conditional with a "then" and an "else" region,
+    /// as in MLIR's `scf.if`.
+    IfLike: ["test", "if"],
+    [],
+    extern: []
+}
+
+#[test]
+fn builder_blocks_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    let mut func = Func.get_builder("branches", LocationInfo::Unknown)?;
+    let entry = func.get_current_block().unwrap();
+    let (x, y) = (func.push_arg()?, func.push_arg()?);
+
+    // The exit block is referenced before it is filled in.
+    let exit = func.new_block(BasicBlock::default())?;
+    let ret = func.push_arg_to(exit)?;
+    let left = func.build_block(|b| {
+        let v = b.push(Addi.get_builder(vec![x, x], LocationInfo::Unknown)?)?;
+        b.push(Branch.get_builder(vec![v], vec![exit.get_block()], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    let right = func.build_block(|b| {
+        let v = b.push(Addi.get_builder(vec![y, y], LocationInfo::Unknown)?)?;
+        b.push(Branch.get_builder(vec![v], vec![exit.get_block()], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.with_block(exit, |b| {
+        b.push(Return.get_builder(vec![ret], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+
+    // The cursor is back at the entry block.
+    assert_eq!(func.get_current_block(), Some(entry));
+    let cond = func.push(Addi.get_builder(vec![x, y], LocationInfo::Unknown)?)?;
+    func.push(ConditionalBranch.get_builder(
+        vec![cond],
+        vec![left.get_block(), right.get_block()],
+        LocationInfo::Unknown,
+    )?)?;
+
+    // Only arguments of the entry block are operands of the function.
+    assert_eq!(func.get_operands(), vec![x, y]);
+    let op = func.finish()?;
+    println!("{}", op);
+    assert_eq!(op.get_regions()[0].get_blocks().len(), 4);
+    assert_eq!(
+        op.get_regions()[0]
+            .get_block(exit.get_block())
+            .get_operands(),
+        &[ret]
+    );
+    Ok(())
+}
+
+#[test]
+fn builder_regions_0() -> Result<(), Report> {
+    let mut func = Func.get_builder("regions", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let mut cond = OperationBuilder::default(Box::new(IfLike), LocationInfo::Unknown);
+    cond.push_operand(x);
+
+    // Pushing without a region is an error, rather than a panic.
+    let add = Addi.get_builder(vec![x, x], LocationInfo::Unknown)?;
+    assert!(cond.push(add).is_err());
+    assert!(cond.push_arg().is_err());
+
+    let then_region = cond.build_region(Region::Directed(SSACFG::default()), |b| {
+        b.build_block(|b| {
+            b.push(Addi.get_builder(vec![x, x], LocationInfo::Unknown)?)?;
+            Ok(())
+        })?;
+        Ok(())
+    })?;
+    let else_region = cond.build_region(Region::Directed(SSACFG::default()), |b| {
+        let blk = b.build_block(|_| Ok(()))?;
+        let arg = b.push_arg_to(blk)?;
+        b.with_block(blk, |b| {
+            b.push(Addi.get_builder(vec![arg, arg], LocationInfo::Unknown)?)?;
+            Ok(())
+        })
+    })?;
+    assert_eq!((then_region, else_region), (0, 1));
+    assert_eq!(cond.get_operands(), vec![x]);
+    assert_eq!(cond.get_regions().len(), 2);
+    let v = func.push(cond)?;
+    func.push(Return.get_builder(vec![v], LocationInfo::Unknown)?)?;
+    let op = func.finish()?;
+    println!("{}", op);
+    Ok(())
+}
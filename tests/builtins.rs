@@ -1,3 +1,5 @@
+#![allow(unused_must_use)]
+
 use abstraps::core::*;
 use abstraps::dialects::builtin::*;
 use abstraps::*;
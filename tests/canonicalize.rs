@@ -0,0 +1,221 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+
+fn constant(c: ConstantAttr) -> Result<OperationBuilder, Report> {
+    Constant.get_builder(c, LocationInfo::Unknown)
+}
+
+fn get_constant(region: &Region, v: Var) -> Option<&ConstantAttr> {
+    let (_, op) = region.get_op(v)?;
+    let intr = op.get_intrinsic();
+    intr.query_ref::<dyn ProvidesConstantAttr>()
+        .map(|trt| trt.get_value(op))
+}
+
+#[test]
+fn canonicalize_fold_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let c0 = func.push(constant(ConstantAttr::Integer(0, 64))?)?;
+    let c2 = func.push(constant(ConstantAttr::Integer(2, 64))?)?;
+    let c3 = func.push(constant(ConstantAttr::Integer(3, 64))?)?;
+    let one = func.push(constant(ConstantAttr::Integer(
+        1.0f64.to_bits() as i64,
+        64,
+    ))?)?;
+    let a = func.push(Addi.get_builder(vec![c2, c3], LocationInfo::Unknown)?)?;
+    let b = func.push(Andi.get_builder(vec![x, a], LocationInfo::Unknown)?)?;
+    let z = func.push(Addi.get_builder(vec![b, c0], LocationInfo::Unknown)?)?;
+    let s =
+        func.push(Cmpi.get_builder(CmpiPredicate::Slt, vec![c2, c3], LocationInfo::Unknown)?)?;
+    let e = func.push(Cmpi.get_builder(CmpiPredicate::Ne, vec![x, x], LocationInfo::Unknown)?)?;
+    let f = func.push(Bitcast.get_builder(one, LocationInfo::Unknown)?)?;
+    let ret = func.push(Return.get_builder(vec![z, s, e, f], LocationInfo::Unknown)?)?;
+    let mut op = func.finish()?;
+
+    let mut patterns = RewritePatternSet::new();
+    patterns.add(Box::new(FoldPattern));
+    let result = apply_patterns_greedily(&mut op, &patterns, &GreedyRewriteConfig::default())?;
+    assert!(result.converged);
+    println!("{}", op);
+    let region = &op.get_regions()[0];
+    let operands = region.get_op(ret).unwrap().1.get_operands();
+    assert_eq!(operands[0], b);
+    assert_eq!(
+        get_constant(region, operands[1]),
+        Some(&ConstantAttr::Integer(1, 1))
+    );
+    assert_eq!(
+        get_constant(region, operands[2]),
+        Some(&ConstantAttr::Integer(0, 1))
+    );
+    assert_eq!(
+        get_constant(region, operands[3]),
+        Some(&ConstantAttr::Float(1.0, 64))
+    );
+    let andi = region.get_op(b).unwrap().1;
+    assert_eq!(
+        get_constant(region, andi.get_operands()[1]),
+        Some(&ConstantAttr::Integer(5, 64))
+    );
+    // Unused constants are erased.
+    for v in [c0, c2, c3, one] {
+        assert!(region.get_op(v).is_none());
+    }
+    assert_eq!(region.get_block_iter(0).count(), 6);
+    Ok(())
+}
+
+// Booleans folded by `cmpi`, `addi` and `andi` share one form,
+// so `andi(x, true)` folds to `x` whichever fold produced `true`.
+#[test]
+fn canonicalize_fold_i1_0() -> Result<(), Report> {
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let c2 = func.push(constant(ConstantAttr::Integer(2, 64))?)?;
+    let t =
+        func.push(Cmpi.get_builder(CmpiPredicate::Eq, vec![c2, c2], LocationInfo::Unknown)?)?;
+    let f =
+        func.push(Cmpi.get_builder(CmpiPredicate::Ne, vec![c2, c2], LocationInfo::Unknown)?)?;
+    let s = func.push(Addi.get_builder(vec![t, f], LocationInfo::Unknown)?)?;
+    let a = func.push(Andi.get_builder(vec![x, t], LocationInfo::Unknown)?)?;
+    let b = func.push(Andi.get_builder(vec![s, x], LocationInfo::Unknown)?)?;
+    let n = func.push(constant(ConstantAttr::Integer(-1, 1))?)?;
+    let m = func.push(Andi.get_builder(vec![n, t], LocationInfo::Unknown)?)?;
+    let ret = func.push(Return.get_builder(vec![s, a, b, m], LocationInfo::Unknown)?)?;
+    let mut op = func.finish()?;
+
+    let mut patterns = RewritePatternSet::new();
+    patterns.add(Box::new(FoldPattern));
+    let result = apply_patterns_greedily(&mut op, &patterns, &GreedyRewriteConfig::default())?;
+    assert!(result.converged);
+    let region = &op.get_regions()[0];
+    let operands = region.get_op(ret).unwrap().1.get_operands();
+    assert_eq!(
+        get_constant(region, operands[0]),
+        Some(&ConstantAttr::Integer(1, 1))
+    );
+    assert_eq!(operands[1], x);
+    assert_eq!(operands[2], x);
+    assert_eq!(
+        get_constant(region, operands[3]),
+        Some(&ConstantAttr::Integer(1, 1))
+    );
+    Ok(())
+}
+
+rewrite! {
+    AndiReassociate: Andi(Andi(x, y), z) => Andi(x, Andi(y, z))
+}
+
+#[test]
+fn canonicalize_pass_0() -> Result<(), Report> {
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let ones = func.push(constant(ConstantAttr::Integer(-1, 64))?)?;
+    let a = func.push(Andi.get_builder(vec![x, ones], LocationInfo::Unknown)?)?;
+    let b = func.push(Andi.get_builder(vec![a, x], LocationInfo::Unknown)?)?;
+    func.push(Return.get_builder(vec![b], LocationInfo::Unknown)?)?;
+    module.push(func)?;
+    let op = module.finish()?;
+
+    let mut patterns = RewritePatternSet::new();
+    patterns.add(Box::new(AndiReassociate));
+    let mut pm = OperationPassManager::new(Module);
+    pm.push(Box::new(CanonicalizePass::new(patterns)))?;
+    let op = pm.prewalk(op)?;
+    println!("{}", op);
+
+    // andi(andi(x, -1), x) => x
+    let (_, func) = op.get_regions()[0].get_block_iter(0).next().unwrap();
+    let region = &func.get_regions()[0];
+    let ops = region.get_block_iter(0).collect::<Vec<_>>();
+    assert_eq!(ops.len(), 1);
+    assert_eq!(ops[0].1.get_operands(), vec![x]);
+    Ok(())
+}
+
+intrinsic! {
+    /// Integer negation, which is canonicalized with [`NegNeg`].
+    Neg: ["test", "neg"],
+    [],
+    extern: [HasCanonicalizationPatterns]
+}
+
+rewrite! {
+    NegNeg: Neg(Neg(x)) => x
+}
+
+impl HasCanonicalizationPatterns for Neg {
+    fn get_canonicalization_patterns(&self, patterns: &mut RewritePatternSet) {
+        patterns.add(Box::new(NegNeg));
+    }
+}
+
+#[test]
+fn canonicalize_registered_patterns_0() -> Result<(), Report> {
+    register_builtin_passes();
+    register_base_passes();
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let mut neg = |v| -> Result<Var, Report> {
+        let mut b = OperationBuilder::default(Box::new(Neg), LocationInfo::Unknown);
+        b.push_operand(v);
+        func.push(b)
+    };
+    let a = neg(x)?;
+    let b = neg(a)?;
+    let ret = func.push(Return.get_builder(vec![b], LocationInfo::Unknown)?)?;
+    let op = func.finish()?;
+
+    // The pass is created from the textual pipeline.
+    let mut pm = parse_pass_pipeline("builtin.func(canonicalize)")?;
+    let op = pm.prewalk(op)?;
+    println!("{}", op);
+    let region = &op.get_regions()[0];
+    assert_eq!(region.get_op(ret).unwrap().1.get_operands(), vec![x]);
+    assert!(region.get_op(b).is_none());
+    Ok(())
+}
+
+#[test]
+fn canonicalize_fold_widths_0() -> Result<(), Report> {
+    // Integer operands are interpreted at their width.
+    assert!(CmpiPredicate::Eq.evaluate(255, -1, 8));
+    assert!(!CmpiPredicate::Ne.evaluate(255, -1, 8));
+    assert!(CmpiPredicate::Slt.evaluate(128, 0, 8));
+    assert!(!CmpiPredicate::Slt.evaluate(-1, 255, 8));
+    assert!(CmpiPredicate::Ugt.evaluate(-1, 1, 8));
+    assert!(!CmpiPredicate::Ult.evaluate(255, -1, 8));
+
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let x = func.push(constant(ConstantAttr::Float(0.1, 32))?)?;
+    let y = func.push(constant(ConstantAttr::Float(0.2, 32))?)?;
+    let z = func.push(constant(ConstantAttr::Float(3.0, 32))?)?;
+    let a = func.push(Addf.get_builder(vec![x, y], LocationInfo::Unknown)?)?;
+    let d = func.push(Divf.get_builder(vec![x, z], LocationInfo::Unknown)?)?;
+    let ret = func.push(Return.get_builder(vec![a, d], LocationInfo::Unknown)?)?;
+    let mut op = func.finish()?;
+
+    let mut patterns = RewritePatternSet::new();
+    patterns.add(Box::new(FoldPattern));
+    apply_patterns_greedily(&mut op, &patterns, &GreedyRewriteConfig::default())?;
+    let region = &op.get_regions()[0];
+    let operands = region.get_op(ret).unwrap().1.get_operands();
+
+    // Float results at width 32 are rounded to `f32`.
+    assert_eq!(
+        get_constant(region, operands[0]),
+        Some(&ConstantAttr::Float((0.1f64 + 0.2f64) as f32 as f64, 32))
+    );
+    assert_eq!(
+        get_constant(region, operands[1]),
+        Some(&ConstantAttr::Float((0.1f64 / 3.0f64) as f32 as f64, 32))
+    );
+    Ok(())
+}
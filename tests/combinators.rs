@@ -0,0 +1,133 @@
+use abstraps::core::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+use common::*;
+
+mod common;
+
+type IntervalType = Product<Interval, TypeLattice>;
+type SignInterval = ReducedProduct<Sign, Interval>;
+type Constants = Powerset<ConstantLattice, 2>;
+
+fn register() {
+    abstraps::dialects::arith::register_constant_semantics();
+    abstraps::dialects::arith::register_sign_semantics();
+    abstraps::dialects::arith::register_interval_semantics();
+    abstraps::dialects::arith::register_type_semantics();
+    abstraps::dialects::arith::register_lattice_semantics::<IntervalType>();
+    abstraps::dialects::arith::register_lattice_semantics::<SignInterval>();
+    abstraps::dialects::arith::register_lattice_semantics::<Constants>();
+    abstraps::dialects::base::register_constant_semantics();
+    abstraps::dialects::base::register_sign_semantics();
+    abstraps::dialects::base::register_interval_semantics();
+    abstraps::dialects::base::register_type_semantics();
+    abstraps::dialects::base::register_lattice_semantics::<IntervalType>();
+    abstraps::dialects::base::register_lattice_semantics::<SignInterval>();
+    abstraps::dialects::base::register_lattice_semantics::<Constants>();
+}
+
+#[test]
+fn combinators_product_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    register();
+    let (op, m) = masked()?;
+    println!("{}", op);
+
+    // The interval component prunes the second branch,
+    // and the type component is carried along.
+    let int64 = TypeLattice::Type(BuiltinLattice::Int64);
+    let frame = run(&op, vec![Product(Interval::top(), int64.clone())])?;
+    println!("{}", frame);
+    assert_eq!(
+        frame.get(m),
+        Some(&Product(Interval::Range(0, 4), int64.clone()))
+    );
+    assert!(!frame.is_executable(2));
+    assert_eq!(
+        frame.get_ret(),
+        Some(Product(Interval::Range(-4, 5), int64))
+    );
+
+    // A type error in either component is an error.
+    let float64 = TypeLattice::Type(BuiltinLattice::Float64);
+    let res = run(&op, vec![Product(Interval::top(), float64)]);
+    assert!(res.is_err());
+    println!("{}", res.err().unwrap());
+    Ok(())
+}
+
+#[test]
+fn combinators_reduced_product_0() -> Result<(), Report> {
+    register();
+
+    // The sign refines the interval (and vice versa).
+    let v = SignInterval::new(Sign::Positive, Interval::Range(-3, 7));
+    assert_eq!(v.get_first(), &Sign::Positive);
+    assert_eq!(v.get_second(), &Interval::Range(1, 7));
+    let v = SignInterval::new(Sign::Top, Interval::Range(-3, -1));
+    assert_eq!(v.get_first(), &Sign::Negative);
+    assert_eq!(
+        v.join(&SignInterval::new(Sign::Zero, Interval::top())),
+        SignInterval::new(Sign::NonPositive, Interval::Range(-3, 0))
+    );
+
+    let (op, _) = masked()?;
+    let frame = run(
+        &op,
+        vec![SignInterval::new(Sign::Positive, Interval::top())],
+    )?;
+    println!("{}", frame);
+    assert!(!frame.is_executable(2));
+    assert_eq!(
+        frame.get_ret(),
+        Some(SignInterval::new(Sign::Top, Interval::Range(-4, 5)))
+    );
+    Ok(())
+}
+
+#[test]
+fn combinators_powerset_0() -> Result<(), Report> {
+    register();
+    let (op, m) = masked()?;
+    let int = |v| ConstantLattice::Constant(ConstantAttr::Integer(v, 64));
+
+    // Each value is propagated separately, so the comparison
+    // is known (for every value), and only the first branch is taken.
+    let frame = run(&op, vec![Constants::new(vec![int(1), int(12)])])?;
+    println!("{}", frame);
+    assert_eq!(frame.get(m), Some(&Constants::new(vec![int(4), int(0)])));
+    assert!(!frame.is_executable(2));
+    assert_eq!(frame.get_ret(), Some(Constants::new(vec![int(1), int(5)])));
+
+    // Sets with more than two values are the top element.
+    assert_eq!(Constants::new(vec![int(1), int(2), int(3)]), Constants::Top);
+    let v = Constants::new(vec![int(1), int(2)]).join(&Constants::singleton(int(1)));
+    assert_eq!(v.get_values().map(|v| v.len()), Some(2));
+    let frame = run(&op, vec![Constants::Top])?;
+    assert!(frame.is_executable(2));
+    assert_eq!(frame.get_ret(), Some(Constants::Top));
+    Ok(())
+}
+
+#[test]
+fn combinators_map_0() -> Result<(), Report> {
+    let mut a = MapLattice::default();
+    a.insert("x", Interval::Range(0, 1));
+    let mut b = MapLattice::default();
+    b.insert("x", Interval::Range(2, 3));
+    b.insert("y", Interval::constant(5));
+
+    // Keys which are missing from a map are the bottom element.
+    let j = a.join(&b);
+    println!("{}", j);
+    assert_eq!(j.get(&"x"), Some(&Interval::Range(0, 3)));
+    assert_eq!(j.get(&"y"), Some(&Interval::constant(5)));
+    let m = j.meet(&a);
+    assert_eq!(m.len(), 1);
+    assert_eq!(m.get(&"x"), Some(&Interval::Range(0, 1)));
+
+    let w = a.widen(&j);
+    assert_eq!(w.get(&"x"), Some(&Interval::Range(0, i64::MAX)));
+    assert_eq!(w.narrow(&j), j);
+    Ok(())
+}
@@ -0,0 +1,49 @@
+// Fixtures shared by the lattice tests.
+
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+
+pub fn constant(c: i64) -> Result<OperationBuilder, Report> {
+    Constant.get_builder(ConstantAttr::Integer(c, 64), LocationInfo::Unknown)
+}
+
+pub fn run<L>(op: &Operation, env: Vec<L>) -> Result<InterpreterFrame<L>, Report>
+where
+    L: Clone + PartialEq + LatticeJoin + 'static,
+{
+    let mut interp = Interpreter::new(op, env.into_iter().map(Some).collect());
+    interp.run(op)?;
+    interp.finish()
+}
+
+// `masked(x)` masks `x` with `4`, and returns `m + 1` if `m < 5` (otherwise `x`).
+pub fn masked() -> Result<(Operation, Var), Report> {
+    let mut func = Func.get_builder("masked", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let c4 = func.push(constant(4)?)?;
+    let m = func.push(Andi.get_builder(vec![x, c4], LocationInfo::Unknown)?)?;
+    let c5 = func.push(constant(5)?)?;
+    let c =
+        func.push(Cmpi.get_builder(CmpiPredicate::Slt, vec![m, c5], LocationInfo::Unknown)?)?;
+    let then = func.new_block(BasicBlock::default())?;
+    let otherwise = func.new_block(BasicBlock::default())?;
+    func.with_block(then, |b| {
+        let c1 = b.push(constant(1)?)?;
+        let v = b.push(Addi.get_builder(vec![m, c1], LocationInfo::Unknown)?)?;
+        b.push(Return.get_builder(vec![v], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.with_block(otherwise, |b| {
+        b.push(Return.get_builder(vec![x], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.push(ConditionalBranch.get_builder(
+        vec![c],
+        vec![then.get_block(), otherwise.get_block()],
+        LocationInfo::Unknown,
+    )?)?;
+    Ok((func.finish()?, m))
+}
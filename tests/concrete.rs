@@ -1,3 +1,5 @@
+#![allow(dead_code, unused_must_use, unused_variables)]
+
 use abstraps::core::*;
 use abstraps::dialects::arith::*;
 use abstraps::dialects::base::*;
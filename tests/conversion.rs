@@ -0,0 +1,171 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+
+intrinsic! {
+    /// A target-level addition, which `arith.addi` is lowered to.
+    LowAdd: ["low", "add"],
+    [],
+    extern: []
+}
+
+rewrite! {
+    AddiToLow: Addi(x, y) => LowAdd(x, y)
+}
+
+fn target() -> ConversionTarget {
+    let mut target = ConversionTarget::new();
+    target.add_legal_namespace("low");
+    target.add_legal_namespace("base");
+    target.add_illegal_intrinsic(&Addi);
+    target
+}
+
+fn patterns() -> ConversionPatternSet {
+    let mut patterns = ConversionPatternSet::new();
+    patterns.add_rewrite_pattern(Box::new(AddiToLow));
+    patterns
+}
+
+fn module() -> Result<Operation, Report> {
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let (x, y) = (func.push_arg()?, func.push_arg()?);
+    let a = func.push(Addi.get_builder(vec![x, y], LocationInfo::Unknown)?)?;
+    let b = func.push(Andi.get_builder(vec![a, x], LocationInfo::Unknown)?)?;
+    func.push(Return.get_builder(vec![b], LocationInfo::Unknown)?)?;
+    module.push(func)?;
+    module.finish()
+}
+
+fn get_func(op: &Operation) -> &Operation {
+    let (_, func) = op.get_regions()[0].get_block_iter(0).next().unwrap();
+    func
+}
+
+#[test]
+fn conversion_partial_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    let mut op = module()?;
+    let mut target = target();
+    target.add_legal_intrinsic(&Func);
+    apply_partial_conversion(&mut op, &target, &patterns(), &TypeConverter::new())?;
+    println!("{}", op);
+
+    // `arith.andi` has unknown legality, so it is left as is.
+    let func = get_func(&op);
+    let ops = func.get_regions()[0]
+        .get_block_iter(0)
+        .map(|(_, o)| o.get_intrinsic().get_unique_id())
+        .collect::<Vec<_>>();
+    assert_eq!(ops, vec!["low.add", "arith.andi", "base.return"]);
+    Ok(())
+}
+
+#[test]
+fn conversion_full_0() -> Result<(), Report> {
+    let mut op = module()?;
+    let mut target = target();
+    target.add_legal_intrinsic(&Func);
+    let res = apply_full_conversion(&mut op, &target, &patterns(), &TypeConverter::new());
+    assert!(res.is_err());
+    let msg = format!("{}", res.unwrap_err());
+    println!("{}", msg);
+    assert!(msg.contains("andi"));
+    assert!(!msg.contains("addi"));
+    Ok(())
+}
+
+#[test]
+fn conversion_signature_0() -> Result<(), Report> {
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let a = func.push(Addi.get_builder(vec![x, x], LocationInfo::Unknown)?)?;
+    func.push(Return.get_builder(vec![a], LocationInfo::Unknown)?)?;
+    func.insert_attr(
+        "builtin.signature",
+        Box::new(SignatureAttr::new(
+            vec![BuiltinLattice::Int32],
+            BuiltinLattice::Int32,
+        )),
+    );
+    module.push(func)?;
+    let mut op = module.finish()?;
+
+    let mut converter = TypeConverter::new();
+    converter.add_conversion(|t| match t {
+        BuiltinLattice::Int32 => Some(BuiltinLattice::Int64),
+        _ => None,
+    });
+    let mut target = ConversionTarget::new();
+    let legal = converter.clone();
+    target.add_dynamically_legal_intrinsic(&Func, move |op| {
+        match op.get_attributes().get("builtin.signature") {
+            None => true,
+            Some(attr) => attr
+                .query_ref::<dyn AttributeValue<SignatureAttr>>()
+                .is_none_or(|sig| legal.is_legal_signature(sig.get_value())),
+        }
+    });
+    let mut patterns = ConversionPatternSet::new();
+    patterns.add(Box::new(SignatureConversionPattern));
+    apply_partial_conversion(&mut op, &target, &patterns, &converter)?;
+    println!("{}", op);
+
+    // The argument is cast back to the original type.
+    let func = get_func(&op);
+    let region = &func.get_regions()[0];
+    let (cast, cast_op) = region.get_block_iter(0).next().unwrap();
+    assert!(cast_op.get_intrinsic().is::<UnrealizedConversionCast>());
+    assert_eq!(cast_op.get_operands(), vec![x]);
+    let (_, add) = region.get_op(a).unwrap();
+    assert_eq!(add.get_operands(), vec![cast, cast]);
+    Ok(())
+}
+
+intrinsic! {
+    /// An intermediate addition, which is lowered to `low.add`.
+    MidAdd: ["mid", "add"],
+    [],
+    extern: []
+}
+
+rewrite! {
+    AddiToMid: Addi(x, y) => MidAdd(x, y)
+}
+
+rewrite! {
+    MidToLow: MidAdd(x, y) => LowAdd(x, y)
+}
+
+#[test]
+fn conversion_iterations_0() -> Result<(), Report> {
+    let mut target = target();
+    target.add_legal_intrinsic(&Func);
+    target.add_illegal_namespace("mid");
+    let mut patterns = ConversionPatternSet::new();
+    patterns.add_rewrite_pattern(Box::new(AddiToMid));
+    patterns.add_rewrite_pattern(Box::new(MidToLow));
+
+    // Each step of the chain takes a sweep.
+    let mut op = module()?;
+    let config = ConversionConfig {
+        mode: ConversionMode::Partial,
+        max_iterations: 1,
+    };
+    let res = apply_conversion(&mut op, &target, &patterns, &TypeConverter::new(), &config);
+    let msg = format!("{}", res.unwrap_err());
+    println!("{}", msg);
+    assert!(msg.contains("did not converge in 1 iterations"));
+
+    let mut op = module()?;
+    let config = ConversionConfig::new(ConversionMode::Partial);
+    apply_conversion(&mut op, &target, &patterns, &TypeConverter::new(), &config)?;
+    let func = get_func(&op);
+    let (_, add) = func.get_regions()[0].get_block_iter(0).next().unwrap();
+    assert!(add.get_intrinsic().is::<LowAdd>());
+    Ok(())
+}
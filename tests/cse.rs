@@ -0,0 +1,112 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+
+fn get_func(op: &Operation) -> &Operation {
+    op.get_regions()[0].get_block_iter(0).next().unwrap().1
+}
+
+#[test]
+fn cse_block_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let (x, y) = (func.push_arg()?, func.push_arg()?);
+    let a = func.push(Addi.get_builder(vec![x, y], LocationInfo::Unknown)?)?;
+    let b = func.push(Addi.get_builder(vec![y, x], LocationInfo::Unknown)?)?;
+    let c = func.push(Andi.get_builder(vec![a, b], LocationInfo::Unknown)?)?;
+    let one = ConstantAttr::Integer(1, 64);
+    let c1 = func.push(Constant.get_builder(one.clone(), LocationInfo::Unknown)?)?;
+    let c2 = func.push(Constant.get_builder(one, LocationInfo::Unknown)?)?;
+    let c3 =
+        func.push(Constant.get_builder(ConstantAttr::Integer(2, 64), LocationInfo::Unknown)?)?;
+    let lt =
+        func.push(Cmpi.get_builder(CmpiPredicate::Slt, vec![c1, c2], LocationInfo::Unknown)?)?;
+    let gt =
+        func.push(Cmpi.get_builder(CmpiPredicate::Sgt, vec![c1, c2], LocationInfo::Unknown)?)?;
+    let ret = func.push(Return.get_builder(vec![c, c3, lt, gt], LocationInfo::Unknown)?)?;
+    module.push(func)?;
+    let op = module.finish()?;
+
+    let mut pm = OperationPassManager::new(Module);
+    pm.push(Box::new(CSEPass::default()))?;
+    let op = pm.prewalk(op)?;
+    println!("{}", op);
+    let region = &get_func(&op).get_regions()[0];
+
+    // Commutative operands are unordered.
+    assert!(region.get_op(b).is_none());
+    assert_eq!(region.get_op(c).unwrap().1.get_operands(), vec![a, a]);
+
+    // Attributes are compared.
+    assert!(region.get_op(c2).is_none());
+    assert!(region.get_op(c3).is_some());
+    assert_eq!(region.get_op(lt).unwrap().1.get_operands(), vec![c1, c1]);
+    assert!(region.get_op(gt).is_some());
+    assert_eq!(
+        region.get_op(ret).unwrap().1.get_operands(),
+        vec![c, c3, lt, gt]
+    );
+    Ok(())
+}
+
+#[test]
+fn cse_dominance_0() -> Result<(), Report> {
+    let mut func = Func.get_builder("branches", LocationInfo::Unknown)?;
+    let (x, y) = (func.push_arg()?, func.push_arg()?);
+    let exit = func.new_block(BasicBlock::default())?;
+    let mut vs = Vec::new();
+    let left = func.build_block(|b| {
+        vs.push(b.push(Addi.get_builder(vec![x, y], LocationInfo::Unknown)?)?);
+        vs.push(b.push(Andi.get_builder(vec![x, y], LocationInfo::Unknown)?)?);
+        b.push(Branch.get_builder(vec![], vec![exit.get_block()], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    let right = func.build_block(|b| {
+        vs.push(b.push(Andi.get_builder(vec![x, y], LocationInfo::Unknown)?)?);
+        b.push(Branch.get_builder(vec![], vec![exit.get_block()], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.with_block(exit, |b| {
+        vs.push(b.push(Andi.get_builder(vec![x, y], LocationInfo::Unknown)?)?);
+        vs.push(b.push(Andi.get_builder(vec![y, x], LocationInfo::Unknown)?)?);
+        b.push(Return.get_builder(vec![vs[3], vs[4]], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    let a = func.push(Addi.get_builder(vec![x, y], LocationInfo::Unknown)?)?;
+    func.push(ConditionalBranch.get_builder(
+        vec![a],
+        vec![left.get_block(), right.get_block()],
+        LocationInfo::Unknown,
+    )?)?;
+    let op = func.finish()?;
+
+    let dom = DominanceInfo::new(&op.get_regions()[0]);
+    let (l, r, e) = (left.get_block(), right.get_block(), exit.get_block());
+    assert_eq!(dom.get_idom(l), Some(0));
+    assert_eq!(dom.get_idom(e), Some(0));
+    assert!(dom.dominates(0, e));
+    assert!(!dom.dominates(l, e));
+    assert!(!dom.dominates(l, r));
+    assert_eq!(dom.get_preorder()[0], 0);
+
+    let lock = std::sync::RwLock::new(op);
+    CSEPass::default().apply(&lock, &std::sync::RwLock::new(AnalysisManager::default()))?;
+    let op = lock.into_inner().unwrap();
+    println!("{}", op);
+    let region = &op.get_regions()[0];
+
+    // The entry block dominates the left block.
+    assert!(region.get_op(vs[0]).is_none());
+    // Neither branch dominates the other, or the exit block.
+    assert!(region.get_op(vs[1]).is_some());
+    assert!(region.get_op(vs[2]).is_some());
+    assert!(region.get_op(vs[3]).is_some());
+    // ... but operations in the exit block are deduplicated.
+    assert!(region.get_op(vs[4]).is_none());
+    let (_, ret) = region.get_block_iter(e).last().unwrap();
+    assert_eq!(ret.get_operands(), vec![vs[3], vs[3]]);
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+use std::sync::RwLock;
+
+fn apply(pass: &dyn OperationPass, op: Operation) -> Result<Operation, Report> {
+    let lock = RwLock::new(op);
+    pass.apply(&lock, &RwLock::new(AnalysisManager::default()))?;
+    Ok(lock.into_inner().unwrap())
+}
+
+#[test]
+fn dce_ops_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let (x, y) = (func.push_arg()?, func.push_arg()?);
+    let c =
+        func.push(Constant.get_builder(ConstantAttr::Integer(1, 64), LocationInfo::Unknown)?)?;
+    let a = func.push(Addi.get_builder(vec![x, c], LocationInfo::Unknown)?)?;
+    let b = func.push(Andi.get_builder(vec![a, y], LocationInfo::Unknown)?)?;
+    let call = func.push(Call.get_builder("foo", vec![x], LocationInfo::Unknown)?)?;
+    let live = func.push(Addi.get_builder(vec![x, y], LocationInfo::Unknown)?)?;
+    func.push(Return.get_builder(vec![live], LocationInfo::Unknown)?)?;
+    let op = apply(&DCEPass::default(), func.finish()?)?;
+    println!("{}", op);
+
+    // The dead chain is removed, but calls may have side effects.
+    let region = &op.get_regions()[0];
+    for v in [c, a, b] {
+        assert!(region.get_op(v).is_none());
+    }
+    assert!(region.get_op(call).is_some());
+    assert!(region.get_op(live).is_some());
+    assert_eq!(region.get_block_iter(0).count(), 3);
+    Ok(())
+}
+
+#[test]
+fn dce_blocks_0() -> Result<(), Report> {
+    let mut func = Func.get_builder("branches", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let exit = func.new_block(BasicBlock::default())?;
+    let ret = func.push_arg_to(exit)?;
+    let dead = func.build_block(|b| {
+        let v = b.push(Addi.get_builder(vec![x, x], LocationInfo::Unknown)?)?;
+        b.push(Branch.get_builder(vec![v], vec![exit.get_block()], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    let live = func.build_block(|b| {
+        b.push(Branch.get_builder(vec![x], vec![exit.get_block()], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.with_block(exit, |b| {
+        b.push(Return.get_builder(vec![ret], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.push(Branch.get_builder(vec![], vec![live.get_block()], LocationInfo::Unknown)?)?;
+    assert!(dead.get_block() < live.get_block());
+    let op = apply(&DCEPass::default(), func.finish()?)?;
+    println!("{}", op);
+
+    // The unreachable block is removed, and later blocks are renumbered.
+    let region = &op.get_regions()[0];
+    assert_eq!(region.get_blocks().len(), 3);
+    assert_eq!(region.get_block_successors(0), vec![live.get_block() - 1]);
+    assert_eq!(
+        region.get_block_successors(live.get_block() - 1),
+        vec![exit.get_block()]
+    );
+    let intr = op.get_intrinsic();
+    intr.verify(intr, &op)?;
+    Ok(())
+}
+
+#[cfg(feature = "memref")]
+#[test]
+fn dce_allocations_0() -> Result<(), Report> {
+    use abstraps::dialects::memref::*;
+    let mut func = Func.get_builder("allocs", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let unused = func.push(OperationBuilder::default(
+        Box::new(Alloc),
+        LocationInfo::Unknown,
+    ))?;
+    let used = func.push(OperationBuilder::default(
+        Box::new(Alloc),
+        LocationInfo::Unknown,
+    ))?;
+    let mut copy = OperationBuilder::default(Box::new(Copyto), LocationInfo::Unknown);
+    copy.set_operands(vec![x, used]);
+    let copy = func.push(copy)?;
+    func.push(Return.get_builder(vec![], LocationInfo::Unknown)?)?;
+    let op = apply(&DCEPass::default(), func.finish()?)?;
+    let region = &op.get_regions()[0];
+    assert!(region.get_op(unused).is_none());
+    assert!(region.get_op(used).is_some());
+    assert!(region.get_op(copy).is_some());
+    Ok(())
+}
@@ -0,0 +1,281 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+
+fn register() {
+    abstraps::dialects::arith::register_concrete_semantics();
+    abstraps::dialects::base::register_concrete_semantics();
+}
+
+fn constant(c: i64) -> Result<OperationBuilder, Report> {
+    Constant.get_builder(ConstantAttr::Integer(c, 64), LocationInfo::Unknown)
+}
+
+fn int(v: i64) -> RuntimeValue {
+    RuntimeValue::Integer(v, 64)
+}
+
+fn finish_module(funcs: Vec<OperationBuilder>) -> Result<Operation, Report> {
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    for func in funcs {
+        module.push(func)?;
+    }
+    let mut pm = OperationPassManager::new(Module);
+    pm.push(Box::new(PopulateSymbolTablePass::default()))?;
+    pm.prewalk(module.finish()?)
+}
+
+// `sum(n)` adds `n + (n - 1) + ... + 1` in a loop (counting `i` up to `n`).
+fn sum() -> Result<OperationBuilder, Report> {
+    let mut func = Func.get_builder("sum", LocationInfo::Unknown)?;
+    let n = func.push_arg()?;
+    let c0 = func.push(constant(0)?)?;
+    let header = func.new_block(BasicBlock::default())?;
+    let i = func.push_arg_to(header)?;
+    let acc = func.push_arg_to(header)?;
+    let exit = func.new_block(BasicBlock::default())?;
+    let r = func.push_arg_to(exit)?;
+    func.with_block(header, |b| {
+        let c1 = b.push(constant(1)?)?;
+        let next = b.push(Addi.get_builder(vec![i, c1], LocationInfo::Unknown)?)?;
+        let acc = b.push(Addi.get_builder(vec![acc, next], LocationInfo::Unknown)?)?;
+        let c =
+            b.push(Cmpi.get_builder(CmpiPredicate::Slt, vec![next, n], LocationInfo::Unknown)?)?;
+        b.push(ConditionalBranch.get_builder(
+            vec![c, next, acc, acc],
+            vec![header.get_block(), exit.get_block()],
+            LocationInfo::Unknown,
+        )?)?;
+        Ok(())
+    })?;
+    func.with_block(exit, |b| {
+        b.push(Return.get_builder(vec![r], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.push(Branch.get_builder(
+        vec![c0, c0],
+        vec![header.get_block()],
+        LocationInfo::Unknown,
+    )?)?;
+    Ok(func)
+}
+
+#[test]
+fn evaluate_loop_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    register();
+    let module = finish_module(vec![sum()?])?;
+    println!("{}", module);
+    let mut interp = ConcreteInterpreter::new(|s: &str| lookup_symbol(&module, s));
+    assert_eq!(interp.call_symbol("sum", vec![int(10)])?, vec![int(55)]);
+    assert_eq!(interp.call_symbol("sum", vec![int(1)])?, vec![int(1)]);
+    Ok(())
+}
+
+#[test]
+fn evaluate_calls_0() -> Result<(), Report> {
+    register();
+
+    // `twice(n) = sum(n) + sum(n)`
+    let mut func = Func.get_builder("twice", LocationInfo::Unknown)?;
+    let n = func.push_arg()?;
+    let a = func.push(Call.get_builder("sum", vec![n], LocationInfo::Unknown)?)?;
+    let b = func.push(Call.get_builder("sum", vec![n], LocationInfo::Unknown)?)?;
+    let v = func.push(Addi.get_builder(vec![a, b], LocationInfo::Unknown)?)?;
+    func.push(Return.get_builder(vec![v], LocationInfo::Unknown)?)?;
+    let module = finish_module(vec![sum()?, func])?;
+    let mut interp = ConcreteInterpreter::new(|s: &str| lookup_symbol(&module, s));
+    assert_eq!(interp.call_symbol("twice", vec![int(4)])?, vec![int(20)]);
+    Ok(())
+}
+
+#[test]
+fn evaluate_errors_0() -> Result<(), Report> {
+    register();
+
+    // `loop(n) = loop(n)` never returns.
+    let mut func = Func.get_builder("loop", LocationInfo::Unknown)?;
+    let n = func.push_arg()?;
+    let v = func.push(Call.get_builder("loop", vec![n], LocationInfo::Unknown)?)?;
+    func.push(Return.get_builder(vec![v], LocationInfo::Unknown)?)?;
+    let module = finish_module(vec![sum()?, func])?;
+    let lookup = |s: &str| lookup_symbol(&module, s);
+
+    let mut interp = ConcreteInterpreter::new(lookup).with_max_depth(16);
+    let res = interp.call_symbol("loop", vec![int(1)]);
+    assert!(res.is_err());
+    println!("{}", res.err().unwrap());
+
+    let mut interp = ConcreteInterpreter::new(lookup).with_fuel(5);
+    let res = interp.call_symbol("sum", vec![int(100)]);
+    assert!(res.is_err());
+    println!("{}", res.err().unwrap());
+
+    let mut interp = ConcreteInterpreter::new(lookup);
+    for (name, args) in [
+        ("sum", vec![]),
+        ("sum", vec![RuntimeValue::Float(1.0, 64)]),
+        ("unknown", vec![int(1)]),
+    ] {
+        let res = interp.call_symbol(name, args);
+        assert!(res.is_err());
+        println!("{}", res.err().unwrap());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "memref")]
+#[test]
+fn evaluate_memref_0() -> Result<(), Report> {
+    use abstraps::dialects::memref::*;
+    register();
+    abstraps::dialects::memref::register_concrete_semantics();
+
+    // `fill(x)` allocates a buffer of 2 elements, stores `x` and `x + x` into a
+    // scratch buffer (on the stack), copies it into the buffer, and returns it.
+    let mut func = Func.get_builder("fill", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let (c0, c1, c2) = (
+        func.push(constant(0)?)?,
+        func.push(constant(1)?)?,
+        func.push(constant(2)?)?,
+    );
+    let m = func.push(Alloc.get_builder(vec![c2], LocationInfo::Unknown)?)?;
+    let s = func.push(Alloca.get_builder(vec![c2], LocationInfo::Unknown)?)?;
+    let y = func.push(Addi.get_builder(vec![x, x], LocationInfo::Unknown)?)?;
+    func.push(Store.get_builder(vec![x, s, c0], LocationInfo::Unknown)?)?;
+    func.push(Store.get_builder(vec![y, s, c1], LocationInfo::Unknown)?)?;
+    func.push(Copyto.get_builder(vec![s, m], LocationInfo::Unknown)?)?;
+    func.push(Return.get_builder(vec![m], LocationInfo::Unknown)?)?;
+    let module = finish_module(vec![func])?;
+
+    let mut interp = ConcreteInterpreter::new(|s: &str| lookup_symbol(&module, s));
+    let ret = interp.call_symbol("fill", vec![int(3)])?;
+    let buf = interp.get_heap().get(&ret[0])?;
+    assert_eq!(buf.get_shape(), &[2]);
+    assert_eq!(buf.load(&[1])?, int(6));
+
+    // The scratch buffer is freed on return.
+    assert_eq!(interp.get_heap().live(), 1);
+    let m = ret[0].clone();
+    interp.get_heap_mut().free(&m)?;
+    assert!(interp.get_heap().get(&m).is_err());
+    Ok(())
+}
+
+// `f(x, y)` applies a binary operation, returns the module and the result.
+fn binary<F>(build: F) -> Result<(Operation, Var), Report>
+where
+    F: Fn(Vec<Var>) -> Result<OperationBuilder, Report>,
+{
+    let mut func = Func.get_builder("f", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let y = func.push_arg()?;
+    let v = func.push(build(vec![x, y])?)?;
+    func.push(Return.get_builder(vec![v], LocationInfo::Unknown)?)?;
+    Ok((finish_module(vec![func])?, v))
+}
+
+// Evaluate the operation concretely, and by folding it with constant operands.
+fn differential(module: &Operation, v: Var, args: [ConstantAttr; 2]) -> Result<(), Report> {
+    let (_, f) = module.get_regions()[0].get_block_iter(0).next().unwrap();
+    let op = f.get_regions()[0].get_op(v).unwrap().1;
+    let fold = op.get_intrinsic().query_ref::<dyn Foldable>().unwrap();
+    let mut interp = ConcreteInterpreter::new(|s: &str| lookup_symbol(module, s));
+    let values = args.iter().map(|c| c.to_runtime_value()).collect();
+    let concrete = interp.call_symbol("f", values)?;
+    let folded = match fold.fold(op, &[Some(&args[0]), Some(&args[1])]) {
+        Some(FoldResult::Constant(c)) => c.to_runtime_value(),
+        _ => panic!("{} didn't fold with {:?}.", op.get_intrinsic(), args),
+    };
+    // Compared by their debug form, so that NaN results are equal.
+    assert_eq!(
+        format!("{:?}", concrete[0]),
+        format!("{:?}", folded),
+        "{} with {:?}",
+        op.get_intrinsic(),
+        args
+    );
+    Ok(())
+}
+
+#[test]
+fn evaluate_differential_0() -> Result<(), Report> {
+    register();
+    let wrap = |v: i64, w: usize| match w {
+        64 => v,
+        w => (v << (64 - w)) >> (64 - w),
+    };
+    let ints = [
+        0,
+        1,
+        -1,
+        5,
+        127,
+        -128,
+        255,
+        0x1234_5678_9abc,
+        i64::MAX,
+        i64::MIN,
+    ];
+    let mut ops = vec![
+        binary(|v| Addi.get_builder(v, LocationInfo::Unknown))?,
+        binary(|v| Andi.get_builder(v, LocationInfo::Unknown))?,
+    ];
+    for pred in [
+        CmpiPredicate::Eq,
+        CmpiPredicate::Ne,
+        CmpiPredicate::Slt,
+        CmpiPredicate::Sle,
+        CmpiPredicate::Sgt,
+        CmpiPredicate::Sge,
+        CmpiPredicate::Ult,
+        CmpiPredicate::Ule,
+        CmpiPredicate::Ugt,
+        CmpiPredicate::Uge,
+    ] {
+        ops.push(binary(|v| {
+            Cmpi.get_builder(pred, v, LocationInfo::Unknown)
+        })?);
+    }
+    for w in [8, 32, 64] {
+        for (module, v) in ops.iter() {
+            for l in ints {
+                for r in ints {
+                    let args = [
+                        ConstantAttr::Integer(wrap(l, w), w),
+                        ConstantAttr::Integer(wrap(r, w), w),
+                    ];
+                    differential(module, *v, args)?;
+                }
+            }
+        }
+    }
+
+    // Floats (of width 32 and 64) are rounded to their width.
+    let floats = [0.0, 0.1, 0.2, -3.0, 7.5, 1e300, f64::INFINITY];
+    let ops = [
+        binary(|v| Addf.get_builder(v, LocationInfo::Unknown))?,
+        binary(|v| Divf.get_builder(v, LocationInfo::Unknown))?,
+    ];
+    for w in [32, 64] {
+        let round = |v: f64| match w {
+            32 => v as f32 as f64,
+            _ => v,
+        };
+        for (module, v) in ops.iter() {
+            for l in floats {
+                for r in floats {
+                    let args = [
+                        ConstantAttr::Float(round(l), w),
+                        ConstantAttr::Float(round(r), w),
+                    ];
+                    differential(module, *v, args)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+fn module() -> Result<Operation, Report> {
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    for name in ["new_func1", "new_func2"] {
+        let mut func = Func.get_builder(name, LocationInfo::Unknown)?;
+        let x = func.push_arg()?;
+        func.push(Addi.get_builder(vec![x, x], LocationInfo::Unknown)?)?;
+        let a = func.push(Addi.get_builder(vec![x, x], LocationInfo::Unknown)?)?;
+        func.push(Return.get_builder(vec![a], LocationInfo::Unknown)?)?;
+        module.push(func)?;
+    }
+    module.finish()
+}
+
+fn pipeline() -> Result<OperationPassManager<Module>, Report> {
+    let mut pm = OperationPassManager::new(Module);
+    let mut pm2 = OperationPassManager::new(Func);
+    pm2.push(Box::new(CSEPass::default()))?;
+    pm2.push(Box::new(DCEPass::default()))?;
+    pm.nest(Box::new(pm2))?;
+    Ok(pm)
+}
+
+#[test]
+fn instrumentation_timing_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    let timing = Arc::new(PassTiming::new());
+    let mut pm = pipeline()?;
+    pm.add_instrumentation(timing.clone());
+    pm.add_instrumentation(Arc::new(PassTracing::new()));
+    pm.prewalk(module()?)?;
+    println!("{}", timing);
+    let timings = timing.get_timings();
+    let names = timings
+        .iter()
+        .map(|(n, _, _)| n.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["CSEPass", "DCEPass"]);
+    assert!(timings.iter().all(|(_, _, runs)| *runs == 2));
+    Ok(())
+}
+
+#[test]
+fn instrumentation_printing_0() -> Result<(), Report> {
+    let output = Arc::new(Mutex::new(String::new()));
+    let printing = IRPrinting::new()
+        .print_after(PassFilter::Only(vec!["dce".to_string()]))
+        .with_output(output.clone());
+    let mut pm = pipeline()?;
+    pm.add_instrumentation(Arc::new(printing));
+    pm.prewalk(module()?)?;
+    let output = output.lock().unwrap();
+    println!("{}", output);
+    assert_eq!(output.matches("IR Dump After").count(), 2);
+    assert!(output.contains("DCEPass"));
+    assert!(!output.contains("CSEPass"));
+    Ok(())
+}
+
+// Removes the terminator of the entry block.
+#[derive(Debug)]
+struct RemoveTerminator;
+
+impl OperationPass for RemoveTerminator {
+    fn reset(&self) -> Box<dyn OperationPass> {
+        Box::new(RemoveTerminator)
+    }
+
+    fn check(&self, _op: &RwLock<Operation>) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn apply(
+        &self,
+        op_lock: &RwLock<Operation>,
+        _analysis_lock: &RwLock<AnalysisManager>,
+    ) -> Result<(), Report> {
+        let mut op = op_lock.write().unwrap();
+        let r = &mut op.get_regions_mut()[0];
+        let (v, _) = r.get_block_iter(0).last().unwrap();
+        r.remove_op(v);
+        Ok(())
+    }
+}
+
+#[test]
+fn instrumentation_verify_each_0() -> Result<(), Report> {
+    let mut pm = pipeline()?;
+    let mut pm2 = OperationPassManager::new(Func);
+    pm2.push(Box::new(RemoveTerminator))?;
+    pm.nest(Box::new(pm2))?;
+    pm.set_num_threads(1);
+
+    // Without verification, the invalid IR goes unnoticed.
+    assert!(pm.reset().prewalk(module()?).is_ok());
+    pm.add_instrumentation(Arc::new(VerifyEach));
+    let err = pm.prewalk(module()?).unwrap_err();
+    let msgs = err.chain().map(|e| e.to_string()).collect::<Vec<_>>();
+    println!("{}", msgs[1]);
+    assert!(msgs[0].contains("RemoveTerminator"));
+    assert!(msgs[1].contains("Verification failed"));
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct CountHooks {
+    before: AtomicUsize,
+    after: AtomicUsize,
+    failed: AtomicUsize,
+}
+
+impl PassInstrumentation for CountHooks {
+    fn run_before_pass(&self, _pass: &dyn OperationPass, _op: &Operation) -> Result<(), Report> {
+        self.before.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn run_after_pass(&self, _pass: &dyn OperationPass, _op: &Operation) -> Result<(), Report> {
+        self.after.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn run_after_pass_failed(&self, _pass: &dyn OperationPass, _op: &Operation, _err: &Report) {
+        self.failed.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn instrumentation_hooks_0() -> Result<(), Report> {
+    let hooks = Arc::new(CountHooks::default());
+    let mut pm = pipeline()?;
+    pm.set_num_threads(2);
+    pm.add_instrumentation(hooks.clone());
+    pm.prewalk(module()?)?;
+    assert_eq!(hooks.before.load(Ordering::SeqCst), 4);
+    assert_eq!(hooks.after.load(Ordering::SeqCst), 4);
+    assert_eq!(hooks.failed.load(Ordering::SeqCst), 0);
+
+    // A failing pass calls `run_after_pass_failed`.
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    func.push(Return.get_builder(vec![x], LocationInfo::Unknown)?)?;
+    let mut pm = OperationPassManager::new(Func);
+    pm.push(Box::new(PopulateSymbolTablePass::default()))?;
+    pm.add_instrumentation(hooks.clone());
+    assert!(pm.prewalk(func.finish()?).is_err());
+    assert_eq!(hooks.before.load(Ordering::SeqCst), 5);
+    assert_eq!(hooks.failed.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+// Fails the pipeline from its before (or after) hook.
+#[derive(Debug)]
+struct FailingHook {
+    before: bool,
+}
+
+impl PassInstrumentation for FailingHook {
+    fn run_before_pass(&self, _pass: &dyn OperationPass, _op: &Operation) -> Result<(), Report> {
+        match self.before {
+            true => bail!("Failing before hook."),
+            false => Ok(()),
+        }
+    }
+
+    fn run_after_pass(&self, _pass: &dyn OperationPass, _op: &Operation) -> Result<(), Report> {
+        match self.before {
+            true => Ok(()),
+            false => bail!("Failing after hook."),
+        }
+    }
+}
+
+#[test]
+fn instrumentation_hook_errors_0() -> Result<(), Report> {
+    let func = || -> Result<Operation, Report> {
+        let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+        let x = func.push_arg()?;
+        func.push(Return.get_builder(vec![x], LocationInfo::Unknown)?)?;
+        func.finish()
+    };
+
+    // The hooks which ran before a failing before hook are unwound.
+    let hooks = Arc::new(CountHooks::default());
+    let mut pm = OperationPassManager::new(Func);
+    pm.push(Box::new(DCEPass::default()))?;
+    pm.add_instrumentation(hooks.clone());
+    pm.add_instrumentation(Arc::new(FailingHook { before: true }));
+    let err = pm.prewalk(func()?).unwrap_err();
+    assert!(err.chain().any(|e| e.to_string() == "Failing before hook."));
+    assert_eq!(hooks.before.load(Ordering::SeqCst), 1);
+    assert_eq!(hooks.after.load(Ordering::SeqCst), 0);
+    assert_eq!(hooks.failed.load(Ordering::SeqCst), 1);
+
+    // Every after hook runs, even after a failing one.
+    let hooks = Arc::new(CountHooks::default());
+    let mut pm = OperationPassManager::new(Func);
+    pm.push(Box::new(DCEPass::default()))?;
+    pm.add_instrumentation(Arc::new(FailingHook { before: false }));
+    pm.add_instrumentation(hooks.clone());
+    let err = pm.prewalk(func()?).unwrap_err();
+    assert!(err.chain().any(|e| e.to_string() == "Failing after hook."));
+    assert_eq!(hooks.before.load(Ordering::SeqCst), 1);
+    assert_eq!(hooks.after.load(Ordering::SeqCst), 1);
+    assert_eq!(hooks.failed.load(Ordering::SeqCst), 0);
+    Ok(())
+}
+
+#[test]
+fn instrumentation_statistics_0() -> Result<(), Report> {
+    let stats = Arc::new(PassStatistics::new());
+    let mut pm = pipeline()?;
+    pm.set_num_threads(2);
+    pm.add_instrumentation(stats.clone());
+    pm.prewalk(module()?)?;
+    println!("{}", stats);
+
+    // Counters are summed over both functions.
+    assert_eq!(stats.get("CSEPass", "ops-eliminated"), Some(2));
+    assert_eq!(stats.get("DCEPass", "ops-eliminated"), Some(0));
+    let json = stats.to_json();
+    println!("{}", json);
+    assert!(
+        json.starts_with("[{\"pass\": \"CSEPass\", \"statistics\": [{\"name\": \"ops-eliminated\"")
+    );
+    Ok(())
+}
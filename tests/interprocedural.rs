@@ -0,0 +1,322 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+use std::fmt::Display;
+use std::hash::Hash;
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+enum Parity {
+    Even,
+    Odd,
+    Top,
+}
+
+impl std::fmt::Display for Parity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Parity::Even => write!(f, "Even"),
+            Parity::Odd => write!(f, "Odd"),
+            Parity::Top => write!(f, "Top"),
+        }
+    }
+}
+
+impl LatticeJoin for Parity {
+    fn join(&self, other: &Parity) -> Parity {
+        match self == other {
+            true => self.clone(),
+            false => Parity::Top,
+        }
+    }
+}
+
+impl LatticeWiden for Parity {
+    fn widen(&self, other: &Parity) -> Parity {
+        self.join(other)
+    }
+}
+
+impl LatticeSemantics<Parity> for Addi {
+    fn propagate(&self, op: &Operation, vtypes: Vec<&Parity>) -> Result<Parity, Report> {
+        let operands = op.get_operands();
+        if operands[0] == operands[1] {
+            return Ok(Parity::Even);
+        }
+        match (vtypes[0], vtypes[1]) {
+            (Parity::Top, _) | (_, Parity::Top) => Ok(Parity::Top),
+            (a, b) if a == b => Ok(Parity::Even),
+            _ => Ok(Parity::Odd),
+        }
+    }
+}
+
+impl LatticeSemantics<Parity> for Return {
+    fn propagate(&self, _op: &Operation, vtypes: Vec<&Parity>) -> Result<Parity, Report> {
+        Ok(vtypes[0].clone())
+    }
+}
+
+fn register() {
+    dynamic_interfaces! {
+        Return: dyn LatticeSemantics<Parity>;
+        Addi: dyn LatticeSemantics<Parity>;
+    }
+}
+
+fn build_module() -> Result<Operation, Report> {
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+
+    // `double(x) = x + x`
+    let mut func1 = Func.get_builder("double", LocationInfo::Unknown)?;
+    let x = func1.push_arg()?;
+    let v = func1.push(Addi.get_builder(vec![x, x], LocationInfo::Unknown)?)?;
+    func1.push(Return.get_builder(vec![v], LocationInfo::Unknown)?)?;
+
+    // `main(x, y) = double(x) + y`
+    let mut func2 = Func.get_builder("main", LocationInfo::Unknown)?;
+    let x = func2.push_arg()?;
+    let y = func2.push_arg()?;
+    let v = func2.push(Call.get_builder("double", vec![x], LocationInfo::Unknown)?)?;
+    let v = func2.push(Addi.get_builder(vec![v, y], LocationInfo::Unknown)?)?;
+    func2.push(Return.get_builder(vec![v], LocationInfo::Unknown)?)?;
+
+    module.push(func1)?;
+    module.push(func2)?;
+    let mut pm = OperationPassManager::new(Module);
+    pm.push(Box::new(PopulateSymbolTablePass::default()))?;
+    pm.prewalk(module.finish()?)
+}
+
+#[test]
+fn interprocedural_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    register();
+    let module = build_module()?;
+    println!("{}", module);
+    let main = lookup_symbol(&module, "main").unwrap();
+    let double = lookup_symbol(&module, "double").unwrap();
+    let lookup = |s: &str| lookup_symbol(&module, s);
+
+    let mut am = AnalysisManager::new();
+    let key = Signature::new("main", vec![Some(Parity::Top), Some(Parity::Odd)]);
+    let ret = interpret_with_calls(&mut am, key, main, &lookup)?;
+    println!("{}", am);
+    assert_eq!(ret, Some(Parity::Odd));
+
+    // The callee was interpreted (and cached) with the argument values at the call.
+    let key = Signature::new("double", vec![Some(Parity::Top)]);
+    let cached = am.get_cached_analysis::<LatticeInterpreterPass<Parity>>(&key, double);
+    assert!(cached.is_some());
+    let frame = cached.unwrap().get_result().unwrap();
+    assert_eq!(frame.get_ret(), Some(Parity::Even));
+    Ok(())
+}
+
+#[test]
+fn interprocedural_errors_0() -> Result<(), Report> {
+    register();
+    let module = build_module()?;
+    let main = lookup_symbol(&module, "main").unwrap();
+
+    // Calls can't be resolved when interpreting a function in isolation.
+    let mut am = AnalysisManager::new();
+    let key = Signature::new("main", vec![Some(Parity::Even), Some(Parity::Odd)]);
+    let res = am.analyze(key.clone(), main);
+    assert!(res.is_err());
+    println!("{}", res.err().unwrap());
+
+    // ... or when the callee isn't defined.
+    let res = interpret_with_calls(&mut am, key, main, &|_: &str| None);
+    assert!(res.is_err());
+    println!("{}", res.err().unwrap());
+    Ok(())
+}
+
+// `name(x, y)` branches on `x`, and returns `x` or the result of `rec`
+// (given the function, and `x` and `y`) in the other branch.
+fn branching(
+    name: &str,
+    rec: impl FnOnce(&mut OperationBuilder, Var, Var) -> Result<Var, Report>,
+) -> Result<OperationBuilder, Report> {
+    let mut func = Func.get_builder(name, LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let y = func.push_arg()?;
+    let base = func.new_block(BasicBlock::default())?;
+    let recursive = func.new_block(BasicBlock::default())?;
+    func.with_block(base, |b| {
+        b.push(Return.get_builder(vec![x], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.with_block(recursive, |b| {
+        let v = rec(b, x, y)?;
+        b.push(Return.get_builder(vec![v], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.push(ConditionalBranch.get_builder(
+        vec![x],
+        vec![base.get_block(), recursive.get_block()],
+        LocationInfo::Unknown,
+    )?)?;
+    Ok(func)
+}
+
+fn finish_module(funcs: Vec<OperationBuilder>) -> Result<Operation, Report> {
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    for func in funcs {
+        module.push(func)?;
+    }
+    let mut pm = OperationPassManager::new(Module);
+    pm.push(Box::new(PopulateSymbolTablePass::default()))?;
+    pm.prewalk(module.finish()?)
+}
+
+fn cached<L>(am: &AnalysisManager, module: &Operation, name: &str, env: Vec<L>) -> Option<Option<L>>
+where
+    L: 'static + Clone + Eq + Hash + LatticeJoin + Display,
+{
+    let op = lookup_symbol(module, name)?;
+    let key = Signature::new(name, env.into_iter().map(Some).collect());
+    let pass = am.get_cached_analysis::<LatticeInterpreterPass<L>>(&key, op)?;
+    Some(pass.get_result()?.get_ret())
+}
+
+#[test]
+fn recursion_0() -> Result<(), Report> {
+    register();
+
+    // `f(x, y) = x ? x : f(x, y) + y`
+    let func = branching("f", |b, x, y| {
+        let v = b.push(Call.get_builder("f", vec![x, y], LocationInfo::Unknown)?)?;
+        b.push(Addi.get_builder(vec![v, y], LocationInfo::Unknown)?)
+    })?;
+    let module = finish_module(vec![func])?;
+    println!("{}", module);
+    let f = lookup_symbol(&module, "f").unwrap();
+    let mut am = AnalysisManager::new();
+    let key = Signature::new("f", vec![Some(Parity::Odd), Some(Parity::Odd)]);
+    let ret = interpret_with_calls(&mut am, key, f, &|s: &str| lookup_symbol(&module, s))?;
+    println!("{}", am);
+
+    // The recursive call first returns nothing (so the first result is `Odd`),
+    // then `Odd` (`Odd + Odd` is `Even`), and the fixpoint is `Top`.
+    assert_eq!(ret, Some(Parity::Top));
+    assert_eq!(
+        cached(&am, &module, "f", vec![Parity::Odd, Parity::Odd]),
+        Some(Some(Parity::Top))
+    );
+    Ok(())
+}
+
+#[test]
+fn recursion_mutual_0() -> Result<(), Report> {
+    register();
+
+    // `is_even(x, y) = x ? x : is_odd(y, x)`, `is_odd(x, y) = x ? x : is_even(y, y)`
+    let is_even = branching("is_even", |b, x, y| {
+        b.push(Call.get_builder("is_odd", vec![y, x], LocationInfo::Unknown)?)
+    })?;
+    let is_odd = branching("is_odd", |b, _, y| {
+        b.push(Call.get_builder("is_even", vec![y, y], LocationInfo::Unknown)?)
+    })?;
+    let module = finish_module(vec![is_even, is_odd])?;
+    let main = lookup_symbol(&module, "is_even").unwrap();
+    let mut am = AnalysisManager::new();
+    let key = Signature::new("is_even", vec![Some(Parity::Even), Some(Parity::Odd)]);
+    let ret = interpret_with_calls(&mut am, key, main, &|s: &str| lookup_symbol(&module, s))?;
+    println!("{}", am);
+
+    // `is_even(Even, Odd)` calls `is_odd(Odd, Even)`, which calls `is_even(Even, Even)`,
+    // which calls `is_odd(Even, Even)`, which calls `is_even(Even, Even)` again.
+    assert_eq!(ret, Some(Parity::Top));
+    assert_eq!(
+        cached(&am, &module, "is_even", vec![Parity::Even, Parity::Even]),
+        Some(Some(Parity::Even))
+    );
+    assert_eq!(
+        cached(&am, &module, "is_odd", vec![Parity::Odd, Parity::Even]),
+        Some(Some(Parity::Top))
+    );
+    Ok(())
+}
+
+#[test]
+fn recursion_depth_limit_0() -> Result<(), Report> {
+    register();
+
+    // `f(x, y) = x ? x : f(y, x + y)` is called with a different signature at each depth.
+    let func = branching("f", |b, x, y| {
+        let s = b.push(Addi.get_builder(vec![x, y], LocationInfo::Unknown)?)?;
+        b.push(Call.get_builder("f", vec![y, s], LocationInfo::Unknown)?)
+    })?;
+    let module = finish_module(vec![func])?;
+    let f = lookup_symbol(&module, "f").unwrap();
+    let lookup = |s: &str| lookup_symbol(&module, s);
+    let key = Signature::new("f", vec![Some(Parity::Odd), Some(Parity::Odd)]);
+
+    let mut am = AnalysisManager::new();
+    let ret = interpret_with_calls(&mut am, key.clone(), f, &lookup)?;
+    println!("{}", am);
+    assert_eq!(ret, Some(Parity::Top));
+    assert!(cached(&am, &module, "f", vec![Parity::Odd, Parity::Even]).is_some());
+
+    // Past the limit, the signature of the call is widened with the previous one.
+    let mut am = AnalysisManager::new();
+    let ret = InterproceduralInterpreter::new()
+        .with_depth_limit(1)
+        .run(&mut am, key, f, &lookup)?;
+    println!("{}", am);
+    assert_eq!(ret, Some(Parity::Top));
+    assert!(cached(&am, &module, "f", vec![Parity::Odd, Parity::Even]).is_none());
+    assert!(cached(&am, &module, "f", vec![Parity::Odd, Parity::Top]).is_some());
+    Ok(())
+}
+
+#[test]
+fn interprocedural_widening_0() -> Result<(), Report> {
+    abstraps::dialects::arith::register_interval_semantics();
+    abstraps::dialects::base::register_interval_semantics();
+
+    // `f(n, c) = c ? n : f(n + 1, c)` is called with a larger interval at each depth,
+    // and joining the signatures past the depth limit would grow them forever.
+    let mut func = Func.get_builder("f", LocationInfo::Unknown)?;
+    let n = func.push_arg()?;
+    let c = func.push_arg()?;
+    let base = func.new_block(BasicBlock::default())?;
+    let recursive = func.new_block(BasicBlock::default())?;
+    func.with_block(base, |b| {
+        b.push(Return.get_builder(vec![n], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.with_block(recursive, |b| {
+        let one =
+            b.push(Constant.get_builder(ConstantAttr::Integer(1, 64), LocationInfo::Unknown)?)?;
+        let s = b.push(Addi.get_builder(vec![n, one], LocationInfo::Unknown)?)?;
+        let v = b.push(Call.get_builder("f", vec![s, c], LocationInfo::Unknown)?)?;
+        b.push(Return.get_builder(vec![v], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.push(ConditionalBranch.get_builder(
+        vec![c],
+        vec![base.get_block(), recursive.get_block()],
+        LocationInfo::Unknown,
+    )?)?;
+    let module = finish_module(vec![func])?;
+    let f = lookup_symbol(&module, "f").unwrap();
+    let lookup = |s: &str| lookup_symbol(&module, s);
+    let key = Signature::new(
+        "f",
+        vec![Some(Interval::constant(0)), Some(Interval::Range(0, 1))],
+    );
+
+    // Past the limit, the signature of the call is widened with the previous one.
+    let mut am = AnalysisManager::new();
+    let ret = interpret_with_calls(&mut am, key, f, &lookup)?;
+    println!("{}", am);
+    assert_eq!(ret, Some(Interval::top()));
+    let sig = |n: Interval| vec![n, Interval::Range(0, 1)];
+    assert!(cached(&am, &module, "f", sig(Interval::constant(1))).is_some());
+    assert!(cached(&am, &module, "f", sig(Interval::top())).is_some());
+    Ok(())
+}
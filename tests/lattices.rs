@@ -0,0 +1,274 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+use common::*;
+
+mod common;
+
+fn register() {
+    abstraps::dialects::arith::register_sign_semantics();
+    abstraps::dialects::arith::register_interval_semantics();
+    abstraps::dialects::arith::register_known_bits_semantics();
+    abstraps::dialects::arith::register_nullness_semantics();
+    abstraps::dialects::arith::register_type_semantics();
+    abstraps::dialects::base::register_sign_semantics();
+    abstraps::dialects::base::register_interval_semantics();
+    abstraps::dialects::base::register_known_bits_semantics();
+    abstraps::dialects::base::register_nullness_semantics();
+    abstraps::dialects::base::register_type_semantics();
+}
+
+#[test]
+fn lattices_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    register();
+    let (op, m) = masked()?;
+    println!("{}", op);
+
+    // The comparison is known from the interval of the mask,
+    // so only the first branch is taken. The width of the mask isn't
+    // known, and `m + 1` wraps (to `-4`) if `m` is an integer of width 3.
+    let frame = run(&op, vec![Interval::top()])?;
+    println!("{}", frame);
+    assert_eq!(frame.get(m), Some(&Interval::Range(0, 4)));
+    assert!(!frame.is_executable(2));
+    assert_eq!(frame.get_ret(), Some(Interval::Range(-4, 5)));
+
+    // The sign of the mask doesn't determine the comparison,
+    // and `m + 1` may wrap to any sign (`1 + 1 == 0` at width 1).
+    let frame = run(&op, vec![Sign::Negative])?;
+    println!("{}", frame);
+    assert_eq!(frame.get(m), Some(&Sign::NonNegative));
+    assert!(frame.is_executable(2));
+    assert_eq!(frame.get_ret(), Some(Sign::Top));
+
+    // Every bit of the mask (but the third) is known to be zero,
+    // but the comparison isn't known.
+    let frame = run(&op, vec![KnownBits::top()])?;
+    println!("{}", frame);
+    assert_eq!(frame.get(m), Some(&KnownBits::Known { zeros: !4, ones: 0 }));
+    assert!(frame.is_executable(2));
+    assert_eq!(frame.get_ret(), Some(KnownBits::top()));
+
+    let frame = run(&op, vec![Nullness::Null])?;
+    println!("{}", frame);
+    assert_eq!(frame.get(m), Some(&Nullness::Null));
+    assert_eq!(frame.get_ret(), Some(Nullness::Top));
+
+    let frame = run(&op, vec![TypeLattice::Type(BuiltinLattice::Int64)])?;
+    println!("{}", frame);
+    assert_eq!(
+        frame.get_ret(),
+        Some(TypeLattice::Type(BuiltinLattice::Int64))
+    );
+    Ok(())
+}
+
+#[test]
+fn lattices_errors_0() -> Result<(), Report> {
+    register();
+    let (op, _) = masked()?;
+
+    // Masking a float is a type error.
+    let res = run(&op, vec![TypeLattice::Type(BuiltinLattice::Float64)]);
+    assert!(res.is_err());
+    println!("{}", res.err().unwrap());
+    Ok(())
+}
+
+#[test]
+fn lattices_known_bits_0() -> Result<(), Report> {
+    // Every sum of values with the known bits of `a` and `b`
+    // has the known bits of `a + b`.
+    let values = [-9, -8, -3, -1, 0, 1, 2, 5, 6, 12, i64::MAX, i64::MIN];
+    for x in values.windows(2) {
+        for y in values.windows(3) {
+            let join = |v: &[i64]| {
+                v.iter()
+                    .map(|v| KnownBits::constant(*v))
+                    .fold(KnownBits::Bottom, |a, b| a.join(&b))
+            };
+            let (a, b) = (join(x), join(y));
+            let (zeros, ones) = match a.add(&b) {
+                KnownBits::Known { zeros, ones } => (zeros, ones),
+                KnownBits::Bottom => panic!("{} + {} is bottom.", a, b),
+            };
+            for l in x {
+                for r in y {
+                    let s = l.wrapping_add(*r) as u64;
+                    assert_eq!(s & zeros, 0, "{} + {} (with {} + {})", l, r, a, b);
+                    assert_eq!(!s & ones, 0, "{} + {} (with {} + {})", l, r, a, b);
+                }
+            }
+        }
+    }
+    let c = KnownBits::constant(3).add(&KnownBits::constant(4));
+    assert_eq!(c.get_constant(), Some(7));
+    Ok(())
+}
+
+#[test]
+fn lattices_convert_0() -> Result<(), Report> {
+    let i: Interval = Sign::NonNegative.convert();
+    assert_eq!(i, Interval::Range(0, i64::MAX));
+    let s: Sign = Interval::Range(-3, 5).convert();
+    assert_eq!(s, Sign::Top);
+    let s: Sign = Interval::Range(-3, -1).convert();
+    assert_eq!(s, Sign::Negative);
+    assert_eq!(Sign::Negative.join(&Sign::Zero), Sign::NonPositive);
+    assert_eq!(Sign::Negative.add(&Sign::Positive), Sign::Top);
+    assert_eq!(Sign::Negative.and(&Sign::Top), Sign::Top);
+
+    // Widening the interval of a loop counter
+    // jumps to infinity, and narrowing recovers the bound.
+    let w = Interval::Range(0, 1).widen(&Interval::Range(0, 2));
+    assert_eq!(w, Interval::Range(0, i64::MAX));
+    assert_eq!(w.narrow(&Interval::Range(0, 10)), Interval::Range(0, 10));
+    Ok(())
+}
+
+#[test]
+fn lattices_overflow_0() -> Result<(), Report> {
+    // Sums which may overflow include every integer of the width.
+    let top = Interval::top();
+    assert_eq!(
+        Interval::Range(0, i64::MAX).add(&Interval::constant(1), 64),
+        top
+    );
+    assert_eq!(
+        Interval::constant(i64::MIN).add(&Interval::constant(-1), 64),
+        top
+    );
+    assert_eq!(
+        Interval::Range(1, 2).add(&Interval::constant(3), 64),
+        Interval::Range(4, 5)
+    );
+    assert_eq!(
+        Interval::Range(0, 100).add(&Interval::Range(0, 100), 8),
+        Interval::Range(-128, 127)
+    );
+    assert_eq!(
+        Sign::Positive.add_wrapping(&Sign::Positive, 64),
+        Sign::NonZero
+    );
+    assert_eq!(Sign::Negative.add_wrapping(&Sign::Negative, 64), Sign::Top);
+    assert_eq!(Sign::Zero.add_wrapping(&Sign::Negative, 64), Sign::Negative);
+
+    // Every wrapping sum of integers of width 8 is included.
+    let wrap = |v: i64| (v << 56) >> 56;
+    let values = [-128, -127, -1, 0, 1, 2, 63, 64, 126, 127];
+    for x in values.windows(2) {
+        for y in values.windows(2) {
+            let (a, b) = (Interval::Range(x[0], x[1]), Interval::Range(y[0], y[1]));
+            let (sa, sb): (Sign, Sign) = (a.convert(), b.convert());
+            for l in x[0]..=x[1] {
+                for r in y[0]..=y[1] {
+                    let s = wrap(l + r);
+                    assert!(
+                        a.add(&b, 8).contains(s),
+                        "{} + {} (with {} + {})",
+                        l,
+                        r,
+                        a,
+                        b
+                    );
+                    let sign: Sign = Interval::constant(s).convert();
+                    assert_eq!(sign.join(&sa.add_wrapping(&sb, 8)), sa.add_wrapping(&sb, 8));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn lattices_overflow_1() -> Result<(), Report> {
+    register();
+    abstraps::dialects::arith::register_concrete_semantics();
+    abstraps::dialects::base::register_concrete_semantics();
+
+    // Sums wrap at the width of the integers.
+    let max = i32::MAX as i64;
+    assert_eq!(
+        Interval::constant(max).add(&Interval::constant(1), 32),
+        Interval::Range(i32::MIN as i64, max)
+    );
+    let c = KnownBits::constant(127).add_wrapping(&KnownBits::constant(1), 8);
+    assert_eq!(c.get_constant(), Some(-128));
+    let c = KnownBits::constant(1).add_wrapping(&KnownBits::constant(1), 1);
+    assert_eq!(c.get_constant(), Some(0));
+    assert_eq!(Sign::Positive.add_wrapping(&Sign::Positive, 1), Sign::Zero);
+
+    // `overflow() = i32::MAX + 1` wraps to `i32::MIN`.
+    let mut func = Func.get_builder("overflow", LocationInfo::Unknown)?;
+    let i32_constant =
+        |v| Constant.get_builder(ConstantAttr::Integer(v, 32), LocationInfo::Unknown);
+    let a = func.push(i32_constant(max)?)?;
+    let b = func.push(i32_constant(1)?)?;
+    let s = func.push(Addi.get_builder(vec![a, b], LocationInfo::Unknown)?)?;
+    func.push(Return.get_builder(vec![s], LocationInfo::Unknown)?)?;
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    module.push(func)?;
+    let mut pm = OperationPassManager::new(Module);
+    pm.push(Box::new(PopulateSymbolTablePass::default()))?;
+    let module = pm.prewalk(module.finish()?)?;
+
+    let mut interp = ConcreteInterpreter::new(|s: &str| lookup_symbol(&module, s));
+    let concrete = interp.call_symbol("overflow", vec![])?;
+    let min = i32::MIN as i64;
+    assert_eq!(concrete, vec![RuntimeValue::Integer(min, 32)]);
+
+    // The width of the sum isn't known, but its values include the concrete result.
+    let (_, op) = module.get_regions()[0].get_block_iter(0).next().unwrap();
+    let frame = run::<Interval>(op, vec![])?;
+    println!("{}", frame);
+    assert!(frame.get(s).unwrap().contains(min));
+    let frame = run::<Sign>(op, vec![])?;
+    assert_eq!(frame.get(s), Some(&Sign::Top));
+    let frame = run::<KnownBits>(op, vec![])?;
+    let (zeros, ones) = match frame.get(s) {
+        Some(KnownBits::Known { zeros, ones }) => (*zeros, *ones),
+        v => panic!("The sum is {:?}.", v),
+    };
+    assert_eq!(min as u64 & zeros, 0);
+    assert_eq!(!(min as u64) & ones, 0);
+    Ok(())
+}
+
+#[test]
+fn lattices_known_bits_cmpi_0() -> Result<(), Report> {
+    register();
+    abstraps::dialects::arith::register_concrete_semantics();
+    abstraps::dialects::base::register_concrete_semantics();
+
+    // `ult(1, -1)` compares `1` with the largest unsigned integer.
+    let mut func = Func.get_builder("ult", LocationInfo::Unknown)?;
+    let c1 = func.push(constant(1)?)?;
+    let m1 = func.push(constant(-1)?)?;
+    let c =
+        func.push(Cmpi.get_builder(CmpiPredicate::Ult, vec![c1, m1], LocationInfo::Unknown)?)?;
+    func.push(Return.get_builder(vec![c], LocationInfo::Unknown)?)?;
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    module.push(func)?;
+    let mut pm = OperationPassManager::new(Module);
+    pm.push(Box::new(PopulateSymbolTablePass::default()))?;
+    let module = pm.prewalk(module.finish()?)?;
+
+    let mut interp = ConcreteInterpreter::new(|s: &str| lookup_symbol(&module, s));
+    let concrete = interp.call_symbol("ult", vec![])?;
+    assert_eq!(concrete, vec![RuntimeValue::Bool(true)]);
+
+    // The known bits of the comparison include the concrete result.
+    let (_, op) = module.get_regions()[0].get_block_iter(0).next().unwrap();
+    let frame = run::<KnownBits>(op, vec![])?;
+    println!("{}", frame);
+    let (zeros, ones) = match frame.get(c) {
+        Some(KnownBits::Known { zeros, ones }) => (*zeros, *ones),
+        v => panic!("The comparison is {:?}.", v),
+    };
+    assert_eq!(zeros & 1, 0);
+    assert_eq!(ones & !1, 0);
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+use std::sync::RwLock;
+
+#[test]
+fn licm_hoist_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    let mut func = Func.get_builder("loop", LocationInfo::Unknown)?;
+    let (x, y) = (func.push_arg()?, func.push_arg()?);
+    let header = func.new_block(BasicBlock::default())?;
+    let i = func.push_arg_to(header)?;
+    let exit = func.build_block(|b| {
+        b.push(Return.get_builder(vec![y], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    let mut vs = Vec::new();
+    func.with_block(header, |b| {
+        let a = b.push(Addi.get_builder(vec![x, y], LocationInfo::Unknown)?)?;
+        let c = b.push(Addi.get_builder(vec![a, x], LocationInfo::Unknown)?)?;
+        let n = b.push(Addi.get_builder(vec![i, c], LocationInfo::Unknown)?)?;
+        let cond =
+            b.push(Cmpi.get_builder(CmpiPredicate::Slt, vec![n, y], LocationInfo::Unknown)?)?;
+        b.push(ConditionalBranch.get_builder(
+            vec![cond, n],
+            vec![header.get_block(), exit.get_block()],
+            LocationInfo::Unknown,
+        )?)?;
+        vs.extend([a, c, n, cond]);
+        Ok(())
+    })?;
+    let cond =
+        func.push(Cmpi.get_builder(CmpiPredicate::Slt, vec![x, y], LocationInfo::Unknown)?)?;
+    func.push(ConditionalBranch.get_builder(
+        vec![cond, x],
+        vec![header.get_block(), exit.get_block()],
+        LocationInfo::Unknown,
+    )?)?;
+    let op = func.finish()?;
+
+    let dom = DominanceInfo::new(&op.get_regions()[0]);
+    let loops = LoopInfo::new(&dom);
+    assert_eq!(loops.get_loops().len(), 1);
+    let l = &loops.get_loops()[0];
+    assert_eq!(l.get_header(), header.get_block());
+    assert_eq!(l.get_blocks(), &[header.get_block()]);
+    assert_eq!(l.get_latches(), &[header.get_block()]);
+
+    let lock = RwLock::new(op);
+    LICMPass::default().apply(&lock, &RwLock::new(AnalysisManager::default()))?;
+    let op = lock.into_inner().unwrap();
+    println!("{}", op);
+    let intr = op.get_intrinsic();
+    intr.verify(intr, &op)?;
+
+    // The entry block also branches to the exit,
+    // so a preheader is inserted.
+    let region = &op.get_regions()[0];
+    assert_eq!(region.get_blocks().len(), 4);
+    let preheader = 3;
+    assert_eq!(
+        region.get_block_successors(0),
+        vec![preheader, exit.get_block()]
+    );
+    assert_eq!(
+        region.get_block_successors(preheader),
+        vec![header.get_block()]
+    );
+    let hoisted = region
+        .get_block_iter(preheader)
+        .map(|(v, _)| v)
+        .collect::<Vec<_>>();
+    assert_eq!(hoisted[..2], vs[..2]);
+    let body = region
+        .get_block_iter(header.get_block())
+        .map(|(v, _)| v)
+        .collect::<Vec<_>>();
+    assert_eq!(body[..2], vs[2..]);
+    Ok(())
+}
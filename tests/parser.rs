@@ -0,0 +1,138 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+
+fn register() {
+    register_builtin_dialect();
+    register_base_dialect();
+    register_arith_dialect();
+}
+
+fn constant(c: ConstantAttr) -> Result<OperationBuilder, Report> {
+    Constant.get_builder(c, LocationInfo::Unknown)
+}
+
+// Branches on `x < 5` to a block which returns its argument, or one which returns `0.5`.
+fn module() -> Result<Operation, Report> {
+    let loc = LocationInfo::NameFileLineCol("x \"y\"".to_string(), "foo.jl".to_string(), 3, 1);
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    let mut func = Func.get_builder("new_func1", loc.clone())?;
+    let x = func.push_arg()?;
+    let c5 = func.push(constant(ConstantAttr::Integer(5, 64))?)?;
+    let h = func.push(constant(ConstantAttr::Float(0.5, 32))?)?;
+    let p = func.push(Cmpi.get_builder(CmpiPredicate::Slt, vec![x, c5], loc)?)?;
+    let left = func.new_block(BasicBlock::default())?;
+    let a = func.push_arg_to(left)?;
+    let right = func.new_block(BasicBlock::default())?;
+    func.with_block(left, |b| {
+        let c = b.push(UnrealizedConversionCast.get_builder(
+            a,
+            BuiltinLattice::Function(
+                vec![
+                    BuiltinLattice::Int64,
+                    BuiltinLattice::MemRef(Box::new(BuiltinLattice::Float64)),
+                ],
+                Box::new(BuiltinLattice::Tensor(
+                    vec![2, 3],
+                    Box::new(BuiltinLattice::Int1),
+                )),
+            ),
+            LocationInfo::Unknown,
+        )?)?;
+        b.push(Return.get_builder(vec![c], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.with_block(right, |b| {
+        b.push(Return.get_builder(vec![h], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.push(ConditionalBranch.get_builder(
+        vec![p, x],
+        vec![left.get_block(), right.get_block()],
+        LocationInfo::FileLineCol("foo.jl".to_string(), 4, 2),
+    )?)?;
+    module.push(func)?;
+    module.finish()
+}
+
+#[test]
+fn parser_roundtrip_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    register();
+    register_builtin_passes();
+    let op = parse_pass_pipeline("builtin.module(populate-symbol-table)")?.prewalk(module()?)?;
+    let text = print_generic(&op)?;
+    println!("{}", text);
+    let parsed = parse_generic(&text)?;
+    assert_eq!(print_generic(&parsed)?, text);
+    assert!(text.contains("new_func1 = %0"));
+    assert!(text.contains("(i64, memref<f64>) -> tensor<[2, 3], i1>"));
+    Ok(())
+}
+
+#[test]
+fn parser_attributes_0() -> Result<(), Report> {
+    let sig = SignatureAttr::parse_generic("(i64, (f32) -> i1) -> memref<f64>")?;
+    assert_eq!(sig.print_generic(), "(i64, (f32) -> i1) -> memref<f64>");
+    assert_eq!(
+        SignatureAttr::parse_generic("() -> i32")?.print_generic(),
+        "() -> i32"
+    );
+    assert_eq!(
+        ConstantAttr::parse_generic("-3 : i8")?,
+        ConstantAttr::Integer(-3, 8)
+    );
+    assert_eq!(
+        ConstantAttr::parse_generic("0.1 : f32")?,
+        ConstantAttr::Float(0.1, 32)
+    );
+    assert_eq!(CmpiPredicate::parse_generic("uge")?, CmpiPredicate::Uge);
+    assert!(CmpiPredicate::parse_generic("lt").is_err());
+    assert!(TypeAttr::parse_generic("memref<f64").is_err());
+    assert!(TypeAttr::parse_generic("i64 i64").is_err());
+    assert!(ConstantAttr::parse_generic("5 : u64").is_err());
+    Ok(())
+}
+
+intrinsic! {
+    Neg: ["test", "neg"],
+    [],
+    extern: []
+}
+
+#[test]
+fn parser_errors_0() -> Result<(), Report> {
+    register();
+    let parse_err = |src: &str| -> String {
+        let err = parse_generic(src).unwrap_err();
+        let msgs = err.chain().map(|e| e.to_string()).collect::<Vec<_>>();
+        println!("{}", msgs.join(": "));
+        msgs.join(": ")
+    };
+    assert!(parse_err("\"test.foo\"() loc(unknown)")
+        .contains("Unknown intrinsic `test.foo` at line 1, column 1."));
+    assert!(parse_err("\"base.return\"(%0) loc(unknown) x").contains("trailing input"));
+    assert!(parse_err(
+        "\"base.constant\"() {\"builtin.value\" = builtin.value \"5\"} loc(unknown)"
+    )
+    .contains("Invalid attribute `builtin.value` at line 1, column 38"));
+    let src = "// A comment.\n\"builtin.func\"() loc(unknown) (\n  directed 1 {\n    ^0(%0):\n      %0 = \"base.return\"(%0) loc(unknown)\n  }\n)";
+    assert!(parse_err(src).contains("%0 is defined twice at line 5"));
+
+    // Operations are verified as they are read.
+    let src = "\"builtin.func\"() {\"builtin.symbol\" = builtin.symbol \"public foo\"} loc(unknown) (\n  directed 0 {\n    ^0():\n  }\n)";
+    assert!(parse_err(src).contains("Invalid builtin.func operation at line 1, column 1"));
+
+    // Unregistered intrinsics can't be written.
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let mut b = OperationBuilder::default(Box::new(Neg), LocationInfo::Unknown);
+    b.push_operand(x);
+    let a = func.push(b)?;
+    func.push(Return.get_builder(vec![a], LocationInfo::Unknown)?)?;
+    let err = print_generic(&func.finish()?).unwrap_err();
+    assert!(err.to_string().contains("is not a registered intrinsic"));
+    Ok(())
+}
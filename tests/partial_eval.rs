@@ -0,0 +1,128 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+use std::sync::RwLock;
+
+fn register() {
+    abstraps::dialects::arith::register_constant_semantics();
+    abstraps::dialects::base::register_constant_semantics();
+}
+
+fn constant(c: i64) -> Result<OperationBuilder, Report> {
+    Constant.get_builder(ConstantAttr::Integer(c, 64), LocationInfo::Unknown)
+}
+
+fn known(c: i64) -> Option<ConstantLattice> {
+    Some(ConstantLattice::Constant(ConstantAttr::Integer(c, 64)))
+}
+
+// Branches on `x` to `left` (which returns `(x + 1) + y`) or `right` (which returns `y`).
+fn branching() -> Result<Operation, Report> {
+    let mut func = Func.get_builder("branching", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let y = func.push_arg()?;
+    let c1 = func.push(constant(1)?)?;
+    let a = func.push(Addi.get_builder(vec![x, c1], LocationInfo::Unknown)?)?;
+    let left = func.new_block(BasicBlock::default())?;
+    let p = func.push_arg_to(left)?;
+    let right = func.new_block(BasicBlock::default())?;
+    func.with_block(left, |b| {
+        let s = b.push(Addi.get_builder(vec![p, y], LocationInfo::Unknown)?)?;
+        b.push(Return.get_builder(vec![s], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.with_block(right, |b| {
+        b.push(Return.get_builder(vec![y], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.push(ConditionalBranch.get_builder(
+        vec![x, a],
+        vec![left.get_block(), right.get_block()],
+        LocationInfo::Unknown,
+    )?)?;
+    func.finish()
+}
+
+fn specialize(
+    op: &Operation,
+    env: Vec<Option<ConstantLattice>>,
+) -> Result<(InterpreterFrame<ConstantLattice>, Operation), Report> {
+    let mut interp = Interpreter::new(op, env).with_partial_evaluation();
+    interp.run(op)?;
+    let mut frame = interp.finish()?;
+    let specialized = frame.take_trace().unwrap();
+    Ok((frame, specialized))
+}
+
+#[test]
+fn partial_eval_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    register();
+    let op = branching()?;
+    println!("{}", op);
+    let (frame, specialized) = specialize(&op, vec![known(2), Some(ConstantLattice::Overdefined)])?;
+    println!("{}", specialized);
+
+    // Only the `left` branch is taken, and the argument
+    // of `left` is known (`x + 1`).
+    assert!(frame.is_executable(1));
+    assert!(!frame.is_executable(2));
+    assert_eq!(frame.get_ret(), Some(ConstantLattice::Overdefined));
+    let r = &specialized.get_regions()[0];
+    assert_eq!(r.get_blocks().len(), 2);
+    let (_, term) = r.get_block_iter(0).last().unwrap();
+    assert!(term.get_intrinsic().is::<Branch>());
+    let (_, s) = r.get_block_iter(1).nth(1).unwrap();
+    let (_, c) = r.get_op(s.get_operands()[0]).unwrap();
+    assert_eq!(format!("{}", c), format!("{}", constant(3)?.finish()?));
+
+    // The specialized function still takes both arguments.
+    assert_eq!(specialized.get_operands().len(), 2);
+    Ok(())
+}
+
+#[test]
+fn partial_eval_constant_0() -> Result<(), Report> {
+    register();
+    let op = branching()?;
+    let (frame, specialized) = specialize(&op, vec![known(2), known(5)])?;
+    println!("{}", specialized);
+    assert_eq!(frame.get_ret(), known(8));
+
+    // The returned value is folded, and the operations
+    // which computed it are dead.
+    let lock = RwLock::new(specialized);
+    DCEPass::default().apply(&lock, &RwLock::new(AnalysisManager::default()))?;
+    let specialized = lock.into_inner().unwrap();
+    println!("{}", specialized);
+    let r = &specialized.get_regions()[0];
+    assert_eq!(r.get_block_iter(1).count(), 2);
+    let (_, ret) = r.get_block_iter(1).last().unwrap();
+    let (_, c) = r.get_op(ret.get_operands()[0]).unwrap();
+    assert!(c.get_intrinsic().is::<Constant>());
+    Ok(())
+}
+
+#[test]
+fn partial_eval_unknown_0() -> Result<(), Report> {
+    register();
+    let op = branching()?;
+    let (frame, specialized) = specialize(
+        &op,
+        vec![
+            Some(ConstantLattice::Overdefined),
+            Some(ConstantLattice::Overdefined),
+        ],
+    )?;
+    println!("{}", specialized);
+
+    // Both branches may be taken, so the function is copied.
+    assert!((0..3).all(|b| frame.is_executable(b)));
+    let r = &specialized.get_regions()[0];
+    assert_eq!(r.get_blocks().len(), 3);
+    let (_, term) = r.get_block_iter(0).last().unwrap();
+    assert!(term.get_intrinsic().is::<ConditionalBranch>());
+    Ok(())
+}
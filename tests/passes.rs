@@ -1,8 +1,13 @@
+#![allow(unused_must_use)]
+
 use abstraps::core::*;
 use abstraps::dialects::arith::*;
 use abstraps::dialects::base::*;
 use abstraps::dialects::builtin::*;
 use abstraps::*;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::ThreadId;
 
 #[test]
 fn passes_0() -> Result<(), Report> {
@@ -10,6 +15,7 @@ fn passes_0() -> Result<(), Report> {
     let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
     let mut func1 = Func.get_builder("new_func1", LocationInfo::Unknown)?;
     let operands = vec![func1.push_arg()?, func1.push_arg()?];
+    func1.push(Addi.get_builder(operands.clone(), LocationInfo::Unknown)?)?;
     let add1 = Addi.get_builder(operands, LocationInfo::Unknown)?;
     let ret = func1.push(add1)?;
     let add2 = Addi.get_builder(vec![ret, ret], LocationInfo::Unknown)?;
@@ -32,12 +38,221 @@ fn passes_0() -> Result<(), Report> {
     let op = end.unwrap();
     println!("{}", op);
     let mut pm = OperationPassManager::new(Module);
-    pm.push(Box::new(PopulateSymbolTablePass))?;
+    pm.push(Box::new(PopulateSymbolTablePass::default()))?;
     let mut pm2 = OperationPassManager::new(Func);
-    pm2.push(Box::new(PopulateSymbolTablePass))?;
+    pm2.push(Box::new(DCEPass::default()))?;
     pm.nest(Box::new(pm2))?;
     println!("{}", pm);
     let finished = pm.prewalk(op).unwrap();
     println!("{}", finished);
+
+    // The nested pass manager removes the (dead) first addition
+    // in `new_func1`.
+    let (_, func1) = finished.get_regions()[0].get_block_iter(0).next().unwrap();
+    assert_eq!(func1.get_regions()[0].get_block_iter(0).count(), 3);
+    Ok(())
+}
+
+#[derive(Debug)]
+struct FailingPass;
+
+impl OperationPass for FailingPass {
+    fn reset(&self) -> Box<dyn OperationPass> {
+        Box::new(FailingPass)
+    }
+
+    fn check(&self, _op: &RwLock<Operation>) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn apply(
+        &self,
+        _op: &RwLock<Operation>,
+        _analysis_lock: &RwLock<AnalysisManager>,
+    ) -> Result<(), Report> {
+        bail!("Failing pass.")
+    }
+}
+
+#[test]
+fn passes_nested_error_0() -> Result<(), Report> {
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    let loc = LocationInfo::FileLineCol("foo.jl".to_string(), 3, 1);
+    let mut func = Func.get_builder("new_func1", loc)?;
+    let x = func.push_arg()?;
+    func.push(Return.get_builder(vec![x], LocationInfo::Unknown)?)?;
+    module.push(func)?;
+    let op = module.finish()?;
+    let mut pm = OperationPassManager::new(Module);
+    let mut pm2 = OperationPassManager::new(Func);
+    pm2.push(Box::new(FailingPass))?;
+    pm.nest(Box::new(pm2))?;
+    let err = pm.prewalk(op).unwrap_err();
+    let msgs = err.chain().map(|e| e.to_string()).collect::<Vec<_>>();
+    println!("{:?}", msgs);
+    assert!(msgs[0].contains("FailingPass"));
+    assert!(msgs[0].contains("foo.jl @ 3:1"));
+    assert_eq!(msgs[1], "Failing pass.");
+    Ok(())
+}
+
+// Records the threads which the pass is applied on.
+#[derive(Debug, Default)]
+struct ThreadsPass(Arc<Mutex<HashSet<ThreadId>>>);
+
+impl OperationPass for ThreadsPass {
+    fn reset(&self) -> Box<dyn OperationPass> {
+        Box::new(ThreadsPass(self.0.clone()))
+    }
+
+    fn check(&self, _op: &RwLock<Operation>) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn apply(
+        &self,
+        _op: &RwLock<Operation>,
+        _analysis_manager: &RwLock<AnalysisManager>,
+    ) -> Result<(), Report> {
+        self.0.lock().unwrap().insert(std::thread::current().id());
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        Ok(())
+    }
+}
+
+#[test]
+fn passes_parallel_0() -> Result<(), Report> {
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    for i in 0..32 {
+        let mut func = Func.get_builder(&format!("new_func{}", i), LocationInfo::Unknown)?;
+        let x = func.push_arg()?;
+        for _ in 0..i % 4 {
+            func.push_arg()?;
+        }
+        func.push(Addi.get_builder(vec![x, x], LocationInfo::Unknown)?)?;
+        func.push(Return.get_builder(vec![x], LocationInfo::Unknown)?)?;
+        module.push(func)?;
+    }
+    let op = module.finish()?;
+    let mut pm = OperationPassManager::new(Module);
+    pm.set_num_threads(4);
+    let mut pm2 = OperationPassManager::new(Func);
+    let threads = Arc::new(Mutex::new(HashSet::new()));
+    pm2.push(Box::new(DCEPass::default()))?;
+    pm2.push(Box::new(ThreadsPass(threads.clone())))?;
+    pm.nest(Box::new(pm2))?;
+    let op = pm.prewalk(op)?;
+
+    // The functions are walked on (at most) 4 distinct threads.
+    let threads = threads.lock().unwrap();
+    assert!(threads.len() >= 2 && threads.len() <= 4);
+
+    // Every function is walked, and the functions stay in order.
+    let region = &op.get_regions()[0];
+    for (i, (_, func)) in region.get_block_iter(0).enumerate() {
+        assert_eq!(func.get_operands().len(), i % 4 + 1);
+        assert_eq!(func.get_regions()[0].get_block_iter(0).count(), 1);
+    }
+    Ok(())
+}
+
+// Counts the operations in the entry block of an operation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CountOps;
+
+impl AnalysisKey for CountOps {
+    fn to_pass(&self, _op: &Operation) -> Box<dyn AnalysisPass> {
+        Box::new(OpCount(0))
+    }
+}
+
+interfaces!(CountOps: dyn ObjectClone, dyn ObjectEq, dyn ObjectHash, dyn AnalysisKey);
+
+#[derive(Debug)]
+struct OpCount(usize);
+
+impl AnalysisPass for OpCount {
+    fn apply(&mut self, op: &Operation) -> Result<(), Report> {
+        self.0 = op.get_regions()[0].get_block_iter(0).count();
+        Ok(())
+    }
+}
+
+interfaces!(OpCount: dyn AnalysisPass);
+
+// Records the (possibly cached) `CountOps` analysis.
+#[derive(Debug, Clone)]
+struct RecordCount(Arc<Mutex<Vec<usize>>>);
+
+impl OperationPass for RecordCount {
+    fn reset(&self) -> Box<dyn OperationPass> {
+        Box::new(self.clone())
+    }
+
+    fn preserved_analyses(&self) -> PreservedAnalyses {
+        PreservedAnalyses::all()
+    }
+
+    fn check(&self, _op: &RwLock<Operation>) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn apply(
+        &self,
+        op_lock: &RwLock<Operation>,
+        analysis_lock: &RwLock<AnalysisManager>,
+    ) -> Result<(), Report> {
+        let op = op_lock.read().unwrap();
+        let mut am = analysis_lock.write().unwrap();
+        let count = am.get_analysis::<_, OpCount>(CountOps, &op)?;
+        self.0.lock().unwrap().push(count.0);
+        Ok(())
+    }
+}
+
+fn dead_code_func(name: &str) -> Result<Operation, Report> {
+    let mut func = Func.get_builder(name, LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    func.push(Addi.get_builder(vec![x, x], LocationInfo::Unknown)?)?;
+    func.push(Return.get_builder(vec![x], LocationInfo::Unknown)?)?;
+    func.finish()
+}
+
+#[test]
+fn passes_analysis_invalidation_0() -> Result<(), Report> {
+    let counts = Arc::new(Mutex::new(Vec::new()));
+    let mut pm = OperationPassManager::new(Func);
+    pm.push(Box::new(RecordCount(counts.clone())))?;
+    pm.push(Box::new(RecordCount(counts.clone())))?;
+    pm.push(Box::new(DCEPass::default()))?;
+    pm.push(Box::new(RecordCount(counts.clone())))?;
+    pm.prewalk(dead_code_func("new_func1")?)?;
+
+    // `DCEPass` doesn't preserve the count, so it is recomputed.
+    assert_eq!(*counts.lock().unwrap(), vec![2, 2, 1]);
+    Ok(())
+}
+
+#[test]
+fn passes_analysis_cache_0() -> Result<(), Report> {
+    let (f1, f2) = (dead_code_func("new_func1")?, dead_code_func("new_func2")?);
+    let mut am = AnalysisManager::new();
+    am.analyze(CountOps, &f1)?;
+
+    // Results are cached per operation.
+    assert_eq!(
+        am.get_cached_analysis::<OpCount>(&CountOps, &f1).unwrap().0,
+        2
+    );
+    assert!(am.get_cached_analysis::<OpCount>(&CountOps, &f2).is_none());
+
+    am.invalidate(&PreservedAnalyses::none().preserve::<CountOps>());
+    assert!(am.ask(&CountOps, &f1).is_some());
+    am.invalidate(&PreservedAnalyses::none());
+    assert!(am.ask(&CountOps, &f1).is_none());
+
+    am.get_analysis::<_, OpCount>(CountOps, &f2)?;
+    am.invalidate_op(&f1);
+    assert!(am.ask(&CountOps, &f2).is_some());
     Ok(())
 }
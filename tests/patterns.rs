@@ -0,0 +1,209 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+
+fn andi(operands: Vec<Var>) -> OperationBuilder {
+    let mut b = OperationBuilder::default(Box::new(Andi), LocationInfo::Unknown);
+    b.set_operands(operands);
+    b
+}
+
+// andi(x, x) => x
+#[derive(Debug)]
+struct AndiIdempotent;
+
+impl RewritePattern for AndiIdempotent {
+    fn root_intrinsic(&self) -> Option<Box<dyn Intrinsic>> {
+        Some(Box::new(Andi))
+    }
+
+    fn match_and_rewrite(&self, v: Var, rewriter: &mut IrRewriter) -> Result<bool, Report> {
+        let operands = rewriter.get_op(v).unwrap().get_operands();
+        if operands[0] != operands[1] {
+            return Ok(false);
+        }
+        rewriter.replace_op(v, operands[0])?;
+        Ok(true)
+    }
+}
+
+// andi(andi(x, y), y) => andi(x, y)
+#[derive(Debug)]
+struct AndiAbsorb;
+
+impl RewritePattern for AndiAbsorb {
+    fn root_intrinsic(&self) -> Option<Box<dyn Intrinsic>> {
+        Some(Box::new(Andi))
+    }
+
+    fn benefit(&self) -> usize {
+        2
+    }
+
+    fn match_and_rewrite(&self, v: Var, rewriter: &mut IrRewriter) -> Result<bool, Report> {
+        let operands = rewriter.get_op(v).unwrap().get_operands();
+        match rewriter.get_op(operands[0]) {
+            Some(inner) if inner.get_intrinsic().is::<Andi>() => {
+                if inner.get_operands()[1] != operands[1] {
+                    return Ok(false);
+                }
+                rewriter.replace_op(v, operands[0])?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+// Never converges: keeps swapping the operands of `arith.addi`.
+#[derive(Debug)]
+struct SwapOperands;
+
+impl RewritePattern for SwapOperands {
+    fn root_intrinsic(&self) -> Option<Box<dyn Intrinsic>> {
+        Some(Box::new(Addi))
+    }
+
+    fn match_and_rewrite(&self, v: Var, rewriter: &mut IrRewriter) -> Result<bool, Report> {
+        rewriter.modify_op(v, |op| op.get_operands_mut().reverse())?;
+        Ok(true)
+    }
+}
+
+fn build_module() -> Result<Operation, Report> {
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let (x, y) = (func.push_arg()?, func.push_arg()?);
+    let a = func.push(andi(vec![x, y]))?;
+    let b = func.push(andi(vec![a, y]))?;
+    let c = func.push(andi(vec![b, b]))?;
+    func.push(Return.get_builder(vec![c], LocationInfo::Unknown)?)?;
+    module.push(func)?;
+    module.finish()
+}
+
+fn get_func(op: &Operation) -> &Operation {
+    op.get_regions()[0].get_block_iter(0).next().unwrap().1
+}
+
+#[test]
+fn patterns_greedy_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    let mut op = build_module()?;
+    let mut patterns = RewritePatternSet::new();
+    patterns.add(Box::new(AndiIdempotent));
+    patterns.add(Box::new(AndiAbsorb));
+    let result = apply_patterns_greedily(&mut op, &patterns, &GreedyRewriteConfig::default())?;
+    assert!(result.changed);
+    assert!(result.converged);
+    assert_eq!(result.rewrites, 2);
+    let func = get_func(&op);
+    let ops = func.get_regions()[0].get_block_iter(0).collect::<Vec<_>>();
+    assert_eq!(ops.len(), 2);
+    assert_eq!(ops[1].1.get_operands(), vec![ops[0].0]);
+    println!("{}", op);
+
+    // Rewriting again does nothing.
+    let result = apply_patterns_greedily(&mut op, &patterns, &GreedyRewriteConfig::default())?;
+    assert!(!result.changed);
+    assert!(result.converged);
+    Ok(())
+}
+
+#[test]
+fn patterns_greedy_limit_0() -> Result<(), Report> {
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let (x, y) = (func.push_arg()?, func.push_arg()?);
+    let v = func.push(Addi.get_builder(vec![x, y], LocationInfo::Unknown)?)?;
+    func.push(Return.get_builder(vec![v], LocationInfo::Unknown)?)?;
+    let mut op = func.finish()?;
+    let mut patterns = RewritePatternSet::new();
+    patterns.add(Box::new(SwapOperands));
+    let config = GreedyRewriteConfig {
+        max_iterations: 3,
+        max_rewrites: Some(5),
+        recursive: true,
+    };
+    // The modified operation is revisited after every rewrite,
+    // so only the rewrite limit stops the driver.
+    let result = apply_patterns_greedily(&mut op, &patterns, &config)?;
+    assert!(result.changed);
+    assert!(!result.converged);
+    assert_eq!(result.rewrites, 5);
+    Ok(())
+}
+
+#[test]
+fn patterns_pass_0() -> Result<(), Report> {
+    let op = build_module()?;
+    let mut patterns = RewritePatternSet::new();
+    patterns.add(Box::new(AndiIdempotent));
+    patterns.add(Box::new(AndiAbsorb));
+    let mut pm = OperationPassManager::new(Module);
+    pm.push(Box::new(GreedyRewritePass::new(patterns)))?;
+    let op = pm.prewalk(op)?;
+    let func = get_func(&op);
+    assert_eq!(func.get_regions()[0].get_block_iter(0).count(), 2);
+    Ok(())
+}
+
+rewrite! {
+    /// andi(x, x) => x
+    AndiSelf: Andi(x, y) if x == y => x
+}
+
+rewrite! {
+    AddiZero: Addi(x, Constant() { "builtin.value": ConstantAttr = ConstantAttr::Integer(0, _) })
+        => x
+}
+
+rewrite! {
+    AndiReassociate: Andi(Andi(x, y), z) => Andi(x, Andi(y, z))
+}
+
+#[test]
+fn patterns_rewrite_macro_0() -> Result<(), Report> {
+    assert_eq!(AndiSelf.benefit(), 1);
+    assert_eq!(AddiZero.benefit(), 2);
+    assert_eq!(AndiReassociate.benefit(), 2);
+
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let (x, y) = (func.push_arg()?, func.push_arg()?);
+    let zero =
+        func.push(Constant.get_builder(ConstantAttr::Integer(0, 64), LocationInfo::Unknown)?)?;
+    let one =
+        func.push(Constant.get_builder(ConstantAttr::Integer(1, 64), LocationInfo::Unknown)?)?;
+    let a = func.push(Addi.get_builder(vec![x, zero], LocationInfo::Unknown)?)?;
+    let b = func.push(Addi.get_builder(vec![a, one], LocationInfo::Unknown)?)?;
+    let c = func.push(andi(vec![b, y]))?;
+    let d = func.push(andi(vec![c, b]))?;
+    let e = func.push(andi(vec![x, x]))?;
+    let f = func.push(Addi.get_builder(vec![d, e], LocationInfo::Unknown)?)?;
+    func.push(Return.get_builder(vec![f], LocationInfo::Unknown)?)?;
+    let mut op = func.finish()?;
+
+    let mut patterns = RewritePatternSet::new();
+    patterns.add(Box::new(AndiSelf));
+    patterns.add(Box::new(AddiZero));
+    patterns.add(Box::new(AndiReassociate));
+    let result = apply_patterns_greedily(&mut op, &patterns, &GreedyRewriteConfig::default())?;
+    assert!(result.converged);
+    println!("{}", op);
+
+    let region = &op.get_regions()[0];
+    // addi(x, 0) => x
+    let (_, add) = region.get_op(b).unwrap();
+    assert_eq!(add.get_operands(), vec![x, one]);
+    // andi(x, x) => x
+    let operands = region.get_op(f).unwrap().1.get_operands();
+    assert_eq!(operands[1], x);
+    // andi(andi(b, y), b) => andi(b, andi(y, b))
+    let (_, outer) = region.get_op(operands[0]).unwrap();
+    assert!(outer.get_intrinsic().is::<Andi>());
+    assert_eq!(outer.get_operands()[0], b);
+    let (_, inner) = region.get_op(outer.get_operands()[1]).unwrap();
+    assert_eq!(inner.get_operands(), vec![y, b]);
+    Ok(())
+}
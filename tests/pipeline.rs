@@ -0,0 +1,135 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+use std::sync::{Arc, Mutex};
+
+fn register() {
+    register_builtin_passes();
+    register_base_passes();
+}
+
+#[test]
+fn pipeline_parse_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    register();
+    let src =
+        "builtin.module(populate-symbol-table, builtin.func(cse, canonicalize{max-iterations=10}))";
+    let mut pm = parse_pass_pipeline(src)?;
+    println!("{}", pm);
+    assert_eq!(format!("{}", pm), src);
+
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    let mut func = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let a = func.push(Addi.get_builder(vec![x, x], LocationInfo::Unknown)?)?;
+    let b = func.push(Addi.get_builder(vec![x, x], LocationInfo::Unknown)?)?;
+    let c = func.push(Addi.get_builder(vec![a, b], LocationInfo::Unknown)?)?;
+    func.push(Return.get_builder(vec![c], LocationInfo::Unknown)?)?;
+    module.push(func)?;
+    let op = pm.prewalk(module.finish()?)?;
+    println!("{}", op);
+
+    // The nested `cse` pass ran.
+    let (_, func) = op.get_regions()[0].get_block_iter(0).next().unwrap();
+    assert_eq!(func.get_regions()[0].get_block_iter(0).count(), 3);
+    Ok(())
+}
+
+#[test]
+fn pipeline_print_0() -> Result<(), Report> {
+    register();
+    let mut pm = OperationPassManager::new(Module);
+    let mut pm2 = OperationPassManager::new(Func);
+    pm2.push(Box::new(DCEPass::default()))?;
+    pm2.push(Box::new(CanonicalizePass::default()))?;
+    pm.nest(Box::new(pm2))?;
+    assert_eq!(
+        format!("{}", pm),
+        "builtin.module(builtin.func(dce, canonicalize))"
+    );
+
+    // Elements can be parsed into an existing pass manager,
+    // and options are separated by whitespace (or commas).
+    let mut pm = OperationPassManager::new(Func);
+    parse_pass_pipeline_into(
+        &mut pm,
+        "sccp, canonicalize{recursive=false,max-iterations=3}",
+    )?;
+    assert_eq!(
+        format!("{}", pm),
+        "builtin.func(sccp, canonicalize{max-iterations=3 recursive=false})"
+    );
+
+    // Options which are set explicitly are printed, even with default values.
+    let src = "builtin.func(canonicalize{recursive=true max-rewrites=none})";
+    let pm = parse_pass_pipeline(src)?;
+    assert_eq!(
+        format!("{}", pm),
+        "builtin.func(canonicalize{max-rewrites=none recursive=true})"
+    );
+    let printed = format!("{}", pm);
+    assert_eq!(format!("{}", parse_pass_pipeline(&printed)?), printed);
+    Ok(())
+}
+
+// Records the arguments of the passes which are run.
+#[derive(Debug, Default)]
+struct PassOrder(Mutex<Vec<String>>);
+
+impl PassInstrumentation for PassOrder {
+    fn run_before_pass(&self, pass: &dyn OperationPass, _op: &Operation) -> Result<(), Report> {
+        self.0.lock().unwrap().push(pass.get_argument());
+        Ok(())
+    }
+}
+
+#[test]
+fn pipeline_order_0() -> Result<(), Report> {
+    register();
+    let src = "builtin.module(builtin.func(cse), populate-symbol-table, builtin.func(dce))";
+    let mut pm = parse_pass_pipeline(src)?;
+    assert_eq!(format!("{}", pm), src);
+    let order = Arc::new(PassOrder::default());
+    pm.add_instrumentation(order.clone());
+
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    for name in ["new_func1", "new_func2"] {
+        let mut func = Func.get_builder(name, LocationInfo::Unknown)?;
+        let x = func.push_arg()?;
+        func.push(Return.get_builder(vec![x], LocationInfo::Unknown)?)?;
+        module.push(func)?;
+    }
+    pm.prewalk(module.finish()?)?;
+
+    // Passes and nested pass managers run in the order they are given.
+    assert_eq!(
+        *order.0.lock().unwrap(),
+        vec!["cse", "cse", "populate-symbol-table", "dce", "dce"]
+    );
+    Ok(())
+}
+
+#[test]
+fn pipeline_errors_0() -> Result<(), Report> {
+    register();
+    for src in [
+        "builtin.module(cse",
+        "builtin.module(unknown-pass)",
+        "test.unknown(cse)",
+        "builtin.func(canonicalize{max-iterations=many})",
+        "builtin.func(canonicalize{unknown=1})",
+        "builtin.module(cse) dce",
+    ] {
+        let res = parse_pass_pipeline(src);
+        assert!(res.is_err());
+        println!("{}", res.err().unwrap());
+    }
+    let info = get_pass_info("canonicalize").unwrap();
+    assert_eq!(info.get_options().len(), 3);
+    assert!(get_registered_passes()
+        .iter()
+        .any(|p| p.get_argument() == "populate-symbol-table"));
+    Ok(())
+}
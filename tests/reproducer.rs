@@ -0,0 +1,175 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+use std::sync::{Arc, RwLock};
+
+fn module() -> Result<Operation, Report> {
+    let mut module = Module.get_builder("foo", LocationInfo::Unknown)?;
+    for name in ["new_func1", "new_func2"] {
+        let mut func = Func.get_builder(name, LocationInfo::Unknown)?;
+        let x = func.push_arg()?;
+        let a = func.push(Addi.get_builder(vec![x, x], LocationInfo::Unknown)?)?;
+        func.push(Return.get_builder(vec![a], LocationInfo::Unknown)?)?;
+        module.push(func)?;
+    }
+    module.finish()
+}
+
+fn register() {
+    register_builtin_passes();
+    register_builtin_dialect();
+    register_base_dialect();
+    register_arith_dialect();
+}
+
+// `populate-symbol-table` fails on `builtin.func`, which has no symbol table.
+fn pipeline() -> Result<Box<dyn PassManager>, Report> {
+    register();
+    parse_pass_pipeline("builtin.module(builtin.func(cse, populate-symbol-table))")
+}
+
+#[test]
+fn reproducer_full_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    let path = std::env::temp_dir().join("abstraps_reproducer_full_0.mlir");
+    register();
+    let mut pm = OperationPassManager::new(Module);
+    parse_pass_pipeline_into(&mut pm, "builtin.func(cse, populate-symbol-table)")?;
+    pm.enable_crash_reproducer(&path, false);
+    let res = pm.prewalk(module()?);
+    assert!(res.is_err());
+    let msgs = res
+        .err()
+        .unwrap()
+        .chain()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>();
+    assert!(msgs[0].contains("Crash reproducer written"));
+
+    let contents = std::fs::read_to_string(&path)?;
+    println!("{}", contents);
+    assert!(!contents.contains('\x1b'));
+    assert!(contents.contains("new_func1") && contents.contains("new_func2"));
+
+    // The pipeline can be replayed on the input read back from the reproducer.
+    let spec = read_reproducer_pipeline(&path)?;
+    assert_eq!(spec, format!("{}", pipeline()?));
+    let input = read_reproducer_input(&path)?;
+    assert_eq!(print_generic(&input)?, print_generic(&module()?)?);
+    assert!(parse_pass_pipeline(&spec)?.prewalk(input).is_err());
+    Ok(())
+}
+
+#[test]
+fn reproducer_local_0() -> Result<(), Report> {
+    let path = std::env::temp_dir().join("abstraps_reproducer_local_0.mlir");
+    register();
+    let mut pm = OperationPassManager::new(Module);
+    parse_pass_pipeline_into(&mut pm, "builtin.func(cse, populate-symbol-table)")?;
+    pm.set_num_threads(1);
+    pm.enable_crash_reproducer(&path, true);
+    assert!(pm.prewalk(module()?).is_err());
+
+    // Narrowed to the failing pass and function.
+    let contents = std::fs::read_to_string(&path)?;
+    println!("{}", contents);
+    assert_eq!(
+        read_reproducer_pipeline(&path)?,
+        "builtin.func(populate-symbol-table)"
+    );
+    assert!(contents.contains("new_func1"));
+    assert!(!contents.contains("new_func2"));
+    let input = read_reproducer_input(&path)?;
+    let spec = read_reproducer_pipeline(&path)?;
+    assert!(parse_pass_pipeline(&spec)?.prewalk(input).is_err());
+    Ok(())
+}
+
+#[derive(Debug)]
+struct PanickingPass;
+
+impl OperationPass for PanickingPass {
+    fn reset(&self) -> Box<dyn OperationPass> {
+        Box::new(PanickingPass)
+    }
+
+    fn check(&self, _op: &RwLock<Operation>) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn apply(
+        &self,
+        _op_lock: &RwLock<Operation>,
+        _analysis_lock: &RwLock<AnalysisManager>,
+    ) -> Result<(), Report> {
+        panic!("Panicking pass.")
+    }
+}
+
+#[test]
+fn reproducer_panic_0() -> Result<(), Report> {
+    let path = std::env::temp_dir().join("abstraps_reproducer_panic_0.mlir");
+    let _ = std::fs::remove_file(&path);
+    let mut pm = OperationPassManager::new(Module);
+    let mut pm2 = OperationPassManager::new(Func);
+    pm2.push(Box::new(PanickingPass))?;
+    pm.nest(Box::new(pm2))?;
+    pm.enable_crash_reproducer(&path, false);
+    let op = module()?;
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| pm.prewalk(op)));
+    assert!(res.is_err());
+    let contents = std::fs::read_to_string(&path)?;
+    println!("{}", contents);
+    assert!(contents.contains("PanickingPass panicked: Panicking pass."));
+    Ok(())
+}
+
+// Removes the terminator of a function, which fails verification.
+#[derive(Debug)]
+struct RemoveTerminator;
+
+impl OperationPass for RemoveTerminator {
+    fn reset(&self) -> Box<dyn OperationPass> {
+        Box::new(RemoveTerminator)
+    }
+
+    fn check(&self, _op: &RwLock<Operation>) -> Result<(), Report> {
+        Ok(())
+    }
+
+    fn apply(
+        &self,
+        op_lock: &RwLock<Operation>,
+        _analysis_lock: &RwLock<AnalysisManager>,
+    ) -> Result<(), Report> {
+        let mut op = op_lock.write().unwrap();
+        let r = &mut op.get_regions_mut()[0];
+        let (v, _) = r.get_block_iter(0).last().unwrap();
+        r.remove_op(v);
+        Ok(())
+    }
+}
+
+#[test]
+fn reproducer_verify_0() -> Result<(), Report> {
+    let path = std::env::temp_dir().join("abstraps_reproducer_verify_0.mlir");
+    let _ = std::fs::remove_file(&path);
+    let mut pm = OperationPassManager::new(Module);
+    let mut pm2 = OperationPassManager::new(Func);
+    pm2.push(Box::new(RemoveTerminator))?;
+    pm.nest(Box::new(pm2))?;
+    pm.set_num_threads(1);
+
+    // The verifier runs after the reproducer's hooks.
+    pm.enable_crash_reproducer(&path, true);
+    pm.add_instrumentation(Arc::new(VerifyEach));
+    let err = pm.prewalk(module()?).unwrap_err();
+    assert!(err.to_string().contains("Crash reproducer written"));
+    let contents = std::fs::read_to_string(&path)?;
+    println!("{}", contents);
+    assert!(contents.contains("Verification failed after RemoveTerminator"));
+    assert!(contents.contains("new_func1") && contents.contains("new_func2"));
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn build_func() -> Result<(Operation, Vec<Var>), Report> {
+    let mut func1 = Func.get_builder("new_func1", LocationInfo::Unknown)?;
+    let (a, b) = (func1.push_arg()?, func1.push_arg()?);
+    let add1 = Addi.get_builder(vec![a, b], LocationInfo::Unknown)?;
+    let ret = func1.push(add1)?;
+    let add2 = Addi.get_builder(vec![ret, ret], LocationInfo::Unknown)?;
+    let v = func1.push(add2)?;
+    let r = func1.push(Return.get_builder(vec![v], LocationInfo::Unknown)?)?;
+    Ok((func1.finish()?, vec![a, b, ret, v, r]))
+}
+
+#[derive(Default)]
+struct Counter {
+    inserted: usize,
+    erased: usize,
+    modified: usize,
+}
+
+struct CountingListener(Rc<RefCell<Counter>>);
+
+impl RewriteListener for CountingListener {
+    fn notify_inserted(&mut self, _v: Var, _op: &Operation) {
+        self.0.borrow_mut().inserted += 1;
+    }
+
+    fn notify_erased(&mut self, _v: Var, _op: &Operation) {
+        self.0.borrow_mut().erased += 1;
+    }
+
+    fn notify_modified(&mut self, _v: Var, _op: &Operation) {
+        self.0.borrow_mut().modified += 1;
+    }
+}
+
+#[test]
+fn rewriter_insertion_points_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    let (mut op, vs) = build_func()?;
+    let (a, b, x, y, r) = (vs[0], vs[1], vs[2], vs[3], vs[4]);
+    let mut rewriter = IrRewriter::new(&mut op);
+
+    // Inserting without an insertion point is an error.
+    let add = Addi.get_builder(vec![a, b], LocationInfo::Unknown)?;
+    assert!(rewriter.create(add).is_err());
+
+    rewriter.set_insertion_point_to_start(0);
+    let first = rewriter.create(Addi.get_builder(vec![a, a], LocationInfo::Unknown)?)?;
+    let second = rewriter.create(Addi.get_builder(vec![b, b], LocationInfo::Unknown)?)?;
+    rewriter.set_insertion_point_before(r);
+    let third = rewriter.create(Addi.get_builder(vec![y, y], LocationInfo::Unknown)?)?;
+    rewriter.set_insertion_point_after(x);
+    let fourth = rewriter.create(Addi.get_builder(vec![x, b], LocationInfo::Unknown)?)?;
+    let order = rewriter
+        .get_region()
+        .get_block_iter(0)
+        .map(|(v, _)| v)
+        .collect::<Vec<_>>();
+    assert_eq!(order, vec![first, second, x, fourth, y, third, r]);
+    println!("{}", rewriter.get_operation());
+    Ok(())
+}
+
+#[test]
+fn rewriter_replace_and_erase_0() -> Result<(), Report> {
+    let (mut op, vs) = build_func()?;
+    let (a, b, x, y, r) = (vs[0], vs[1], vs[2], vs[3], vs[4]);
+    let counter = Rc::new(RefCell::new(Counter::default()));
+    let mut rewriter = IrRewriter::new(&mut op);
+    rewriter.add_listener(Box::new(CountingListener(counter.clone())));
+
+    // `x` is still used by `y`.
+    assert!(rewriter.erase_op(x).is_err());
+
+    let z =
+        rewriter.replace_op_with_new(y, Addi.get_builder(vec![a, b], LocationInfo::Unknown)?)?;
+    assert!(rewriter.get_op(y).is_none());
+    assert_eq!(rewriter.get_op(r).unwrap().get_operands(), vec![z]);
+    rewriter.erase_op(x)?;
+    rewriter.set_insertion_point_to_start(0);
+    rewriter.move_op(z)?;
+    let order = rewriter
+        .get_region()
+        .get_block_iter(0)
+        .map(|(v, _)| v)
+        .collect::<Vec<_>>();
+    assert_eq!(order, vec![z, r]);
+    let counter = counter.borrow();
+    assert_eq!(counter.inserted, 1);
+    assert_eq!(counter.erased, 2);
+    assert_eq!(counter.modified, 2);
+    drop(rewriter);
+    let intr = op.get_intrinsic();
+    intr.verify(intr, &op)?;
+    Ok(())
+}
+
+#[test]
+fn rewriter_move_op_failure_0() -> Result<(), Report> {
+    let (mut op, vs) = build_func()?;
+    let (a, b, x, y, r) = (vs[0], vs[1], vs[2], vs[3], vs[4]);
+    let mut rewriter = IrRewriter::new(&mut op);
+    rewriter.set_insertion_point_to_start(0);
+    let dead = rewriter.create(Addi.get_builder(vec![a, b], LocationInfo::Unknown)?)?;
+    rewriter.erase_op(dead)?;
+
+    // The insertion point can't be resolved, so `x` stays in place.
+    rewriter.set_insertion_point_before(dead);
+    assert!(rewriter.move_op(x).is_err());
+    assert!(rewriter.get_op(x).is_some());
+    let order = rewriter
+        .get_region()
+        .get_block_iter(0)
+        .map(|(v, _)| v)
+        .collect::<Vec<_>>();
+    assert_eq!(order, vec![x, y, r]);
+
+    // Moving later in the same block accounts for the removed operation.
+    rewriter.set_insertion_point_before(r);
+    rewriter.move_op(x)?;
+    let order = rewriter
+        .get_region()
+        .get_block_iter(0)
+        .map(|(v, _)| v)
+        .collect::<Vec<_>>();
+    assert_eq!(order, vec![y, x, r]);
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+use std::sync::RwLock;
+
+fn apply(pass: &dyn OperationPass, op: Operation) -> Result<Operation, Report> {
+    let lock = RwLock::new(op);
+    pass.apply(&lock, &RwLock::new(AnalysisManager::default()))?;
+    Ok(lock.into_inner().unwrap())
+}
+
+fn constant(c: i64) -> Result<OperationBuilder, Report> {
+    Constant.get_builder(ConstantAttr::Integer(c, 64), LocationInfo::Unknown)
+}
+
+fn get_constant(region: &Region, v: Var) -> Option<&ConstantAttr> {
+    let (_, op) = region.get_op(v)?;
+    let intr = op.get_intrinsic();
+    intr.query_ref::<dyn ProvidesConstantAttr>()
+        .map(|trt| trt.get_value(op))
+}
+
+#[test]
+fn sccp_branches_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    let mut func = Func.get_builder("branches", LocationInfo::Unknown)?;
+    let x = func.push_arg()?;
+    let c1 = func.push(constant(1)?)?;
+    let c2 = func.push(constant(2)?)?;
+    let exit = func.new_block(BasicBlock::default())?;
+    let e = func.push_arg_to(exit)?;
+    let left = func.new_block(BasicBlock::default())?;
+    let a = func.push_arg_to(left)?;
+    let right = func.new_block(BasicBlock::default())?;
+    func.push_arg_to(right)?;
+    func.with_block(left, |b| {
+        let v = b.push(Addi.get_builder(vec![a, c1], LocationInfo::Unknown)?)?;
+        b.push(Branch.get_builder(vec![v], vec![exit.get_block()], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.with_block(right, |b| {
+        b.push(Branch.get_builder(vec![x], vec![exit.get_block()], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.with_block(exit, |b| {
+        b.push(Return.get_builder(vec![e], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    let s = func.push(Addi.get_builder(vec![c1, c2], LocationInfo::Unknown)?)?;
+    let cond =
+        func.push(Cmpi.get_builder(CmpiPredicate::Slt, vec![c1, c2], LocationInfo::Unknown)?)?;
+    func.push(ConditionalBranch.get_builder(
+        vec![cond, s, x],
+        vec![left.get_block(), right.get_block()],
+        LocationInfo::Unknown,
+    )?)?;
+    let sccp = SCCPPass::default();
+    let op = apply(&sccp, func.finish()?)?;
+    println!("{}", op);
+    let intr = op.get_intrinsic();
+    intr.verify(intr, &op)?;
+
+    // Only the left branch is taken.
+    let region = &op.get_regions()[0];
+    let (_, term) = region.get_block_iter(0).last().unwrap();
+    assert!(term.get_intrinsic().is::<Branch>());
+    assert_eq!(term.get_successors(), &[left.get_block()]);
+    let stats = sccp
+        .get_statistics()
+        .iter()
+        .map(|s| (s.get_name(), s.get()))
+        .collect::<Vec<_>>();
+    println!("{:?}", stats);
+    assert_eq!(
+        stats,
+        vec![("constants-propagated", 5), ("branches-folded", 1)]
+    );
+    let (_, ret) = region.get_block_iter(exit.get_block()).last().unwrap();
+    assert_eq!(
+        get_constant(region, ret.get_operands()[0]),
+        Some(&ConstantAttr::Integer(4, 64))
+    );
+
+    // The right branch is now unreachable.
+    let op = apply(&DCEPass::default(), op)?;
+    assert_eq!(op.get_regions()[0].get_blocks().len(), 3);
+    Ok(())
+}
+
+#[test]
+fn sccp_loop_0() -> Result<(), Report> {
+    let mut func = Func.get_builder("loop", LocationInfo::Unknown)?;
+    let c0 = func.push(constant(0)?)?;
+    let c10 = func.push(constant(10)?)?;
+    let header = func.new_block(BasicBlock::default())?;
+    let i = func.push_arg_to(header)?;
+    let exit = func.build_block(|b| {
+        b.push(Return.get_builder(vec![c10], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    let mut cond = None;
+    func.with_block(header, |b| {
+        let c1 = b.push(constant(1)?)?;
+        let n = b.push(Addi.get_builder(vec![i, c1], LocationInfo::Unknown)?)?;
+        let c =
+            b.push(Cmpi.get_builder(CmpiPredicate::Slt, vec![n, c10], LocationInfo::Unknown)?)?;
+        b.push(ConditionalBranch.get_builder(
+            vec![c, n],
+            vec![header.get_block(), exit.get_block()],
+            LocationInfo::Unknown,
+        )?)?;
+        cond = Some(c);
+        Ok(())
+    })?;
+    func.push(Branch.get_builder(vec![c0], vec![header.get_block()], LocationInfo::Unknown)?)?;
+    let op = apply(&SCCPPass::default(), func.finish()?)?;
+    println!("{}", op);
+
+    // The induction variable is not a constant.
+    let region = &op.get_regions()[0];
+    assert_eq!(region.get_uses(i).len(), 1);
+    assert!(region.get_op(cond.unwrap()).is_some());
+    let (_, term) = region.get_block_iter(header.get_block()).last().unwrap();
+    assert!(term.get_intrinsic().is::<ConditionalBranch>());
+    Ok(())
+}
+
+#[test]
+fn sccp_calls_0() -> Result<(), Report> {
+    let mut func = Func.get_builder("calls", LocationInfo::Unknown)?;
+    let c1 = func.push(constant(1)?)?;
+    let r = func.push(Call.get_builder("other", vec![c1], LocationInfo::Unknown)?)?;
+    let a = func.push(Addi.get_builder(vec![r, c1], LocationInfo::Unknown)?)?;
+    let b = func.push(Addi.get_builder(vec![c1, c1], LocationInfo::Unknown)?)?;
+    func.push(Return.get_builder(vec![a, b], LocationInfo::Unknown)?)?;
+    let op = apply(&SCCPPass::default(), func.finish()?)?;
+    println!("{}", op);
+
+    // The result of the call is unknown.
+    let region = &op.get_regions()[0];
+    assert!(region.get_op(r).is_some());
+    assert!(region.get_op(a).is_some());
+    assert!(region.get_op(b).is_none());
+    Ok(())
+}
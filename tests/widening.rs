@@ -0,0 +1,194 @@
+use abstraps::core::*;
+use abstraps::dialects::arith::*;
+use abstraps::dialects::base::*;
+use abstraps::dialects::builtin::*;
+use abstraps::*;
+
+// Integer intervals, where `i64::MIN` and `i64::MAX` are infinite bounds.
+#[derive(Debug, Clone, PartialEq)]
+enum Interval {
+    Bottom,
+    Range(i64, i64),
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let bound = |b: i64| match b {
+            i64::MIN => "-inf".to_string(),
+            i64::MAX => "+inf".to_string(),
+            b => b.to_string(),
+        };
+        match self {
+            Interval::Bottom => write!(f, "{{}}"),
+            Interval::Range(lo, hi) => write!(f, "[{}, {}]", bound(*lo), bound(*hi)),
+        }
+    }
+}
+
+impl LatticeJoin for Interval {
+    fn join(&self, other: &Interval) -> Interval {
+        match (self, other) {
+            (Interval::Bottom, v) | (v, Interval::Bottom) => v.clone(),
+            (Interval::Range(a, b), Interval::Range(c, d)) => Interval::Range(*a.min(c), *b.max(d)),
+        }
+    }
+}
+
+impl LatticeWiden for Interval {
+    fn widen(&self, other: &Interval) -> Interval {
+        match (self, other) {
+            (Interval::Bottom, v) | (v, Interval::Bottom) => v.clone(),
+            (Interval::Range(a, b), Interval::Range(c, d)) => Interval::Range(
+                if c < a { i64::MIN } else { *a },
+                if d > b { i64::MAX } else { *b },
+            ),
+        }
+    }
+}
+
+impl LatticeNarrow for Interval {
+    fn narrow(&self, other: &Interval) -> Interval {
+        match (self, other) {
+            (Interval::Bottom, _) | (_, Interval::Bottom) => Interval::Bottom,
+            (Interval::Range(a, b), Interval::Range(c, d)) => Interval::Range(
+                if *a == i64::MIN { *c } else { *a },
+                if *b == i64::MAX { *d } else { *b },
+            ),
+        }
+    }
+}
+
+fn add_bound(a: i64, b: i64) -> i64 {
+    match (a, b) {
+        (i64::MIN, _) | (_, i64::MIN) => i64::MIN,
+        (i64::MAX, _) | (_, i64::MAX) => i64::MAX,
+        (a, b) => a.saturating_add(b),
+    }
+}
+
+impl LatticeSemantics<Interval> for Constant {
+    fn propagate(&self, op: &Operation, _vtypes: Vec<&Interval>) -> Result<Interval, Report> {
+        match self.get_value(op) {
+            ConstantAttr::Integer(v, _) => Ok(Interval::Range(*v, *v)),
+            _ => bail!("Expected an integer constant."),
+        }
+    }
+}
+
+impl LatticeSemantics<Interval> for Addi {
+    fn propagate(&self, _op: &Operation, vtypes: Vec<&Interval>) -> Result<Interval, Report> {
+        match (vtypes[0], vtypes[1]) {
+            (Interval::Range(a, b), Interval::Range(c, d)) => {
+                Ok(Interval::Range(add_bound(*a, *c), add_bound(*b, *d)))
+            }
+            _ => Ok(Interval::Bottom),
+        }
+    }
+}
+
+// Masking with a non-negative constant bounds the result.
+impl LatticeSemantics<Interval> for Andi {
+    fn propagate(&self, _op: &Operation, vtypes: Vec<&Interval>) -> Result<Interval, Report> {
+        match vtypes[1] {
+            Interval::Range(m, n) if m == n && *m >= 0 => Ok(Interval::Range(0, *m)),
+            _ => Ok(Interval::Range(i64::MIN, i64::MAX)),
+        }
+    }
+}
+
+impl LatticeSemantics<Interval> for Cmpi {
+    fn propagate(&self, _op: &Operation, _vtypes: Vec<&Interval>) -> Result<Interval, Report> {
+        Ok(Interval::Range(0, 1))
+    }
+}
+
+impl LatticeSemantics<Interval> for Return {
+    fn propagate(&self, _op: &Operation, vtypes: Vec<&Interval>) -> Result<Interval, Report> {
+        Ok(vtypes[0].clone())
+    }
+}
+
+fn register() {
+    dynamic_interfaces! {
+        Constant: dyn LatticeSemantics<Interval>;
+        Addi: dyn LatticeSemantics<Interval>;
+        Andi: dyn LatticeSemantics<Interval>;
+        Cmpi: dyn LatticeSemantics<Interval>;
+        Return: dyn LatticeSemantics<Interval>;
+    }
+}
+
+fn constant(c: i64) -> Result<OperationBuilder, Report> {
+    Constant.get_builder(ConstantAttr::Integer(c, 64), LocationInfo::Unknown)
+}
+
+// A loop which increments `i` (masked with `15` if `mask`) while `i < 10`, and returns `i`.
+fn counter(mask: bool) -> Result<(Operation, Var), Report> {
+    let mut func = Func.get_builder("counter", LocationInfo::Unknown)?;
+    let c0 = func.push(constant(0)?)?;
+    let header = func.new_block(BasicBlock::default())?;
+    let i = func.push_arg_to(header)?;
+    let exit = func.new_block(BasicBlock::default())?;
+    let r = func.push_arg_to(exit)?;
+    func.with_block(header, |b| {
+        let c1 = b.push(constant(1)?)?;
+        let mut n = b.push(Addi.get_builder(vec![i, c1], LocationInfo::Unknown)?)?;
+        if mask {
+            let c15 = b.push(constant(15)?)?;
+            n = b.push(Andi.get_builder(vec![n, c15], LocationInfo::Unknown)?)?;
+        }
+        let c10 = b.push(constant(10)?)?;
+        let c =
+            b.push(Cmpi.get_builder(CmpiPredicate::Slt, vec![n, c10], LocationInfo::Unknown)?)?;
+        b.push(ConditionalBranch.get_builder(
+            vec![c, n, i],
+            vec![header.get_block(), exit.get_block()],
+            LocationInfo::Unknown,
+        )?)?;
+        Ok(())
+    })?;
+    func.with_block(exit, |b| {
+        b.push(Return.get_builder(vec![r], LocationInfo::Unknown)?)?;
+        Ok(())
+    })?;
+    func.push(Branch.get_builder(vec![c0], vec![header.get_block()], LocationInfo::Unknown)?)?;
+    Ok((func.finish()?, i))
+}
+
+#[test]
+fn widening_0() -> Result<(), Report> {
+    let _ = diagnostics_setup();
+    register();
+    let (op, i) = counter(false)?;
+    let mut interp = Interpreter::<Interval>::new(&op, Vec::new()).with_widening(3);
+    interp.run(&op)?;
+    let frame = interp.clone_frame()?;
+    println!("{}", op);
+    println!("{}", frame);
+
+    // The increasing chain `[0, 0], [0, 1], ...` is widened.
+    assert_eq!(frame.get(i), Some(&Interval::Range(0, i64::MAX)));
+    assert_eq!(frame.get_ret(), Some(Interval::Range(0, i64::MAX)));
+    Ok(())
+}
+
+#[test]
+fn narrowing_0() -> Result<(), Report> {
+    register();
+    let (op, i) = counter(true)?;
+    let mut interp = Interpreter::<Interval>::new(&op, Vec::new()).with_widening(0);
+    interp.run(&op)?;
+    let frame = interp.clone_frame()?;
+    assert_eq!(frame.get(i), Some(&Interval::Range(0, i64::MAX)));
+
+    // Narrowing recovers the bound from the mask.
+    let mut interp = Interpreter::<Interval>::new(&op, Vec::new())
+        .with_widening(0)
+        .with_narrowing(2);
+    interp.run(&op)?;
+    let frame = interp.clone_frame()?;
+    println!("{}", frame);
+    assert_eq!(frame.get(i), Some(&Interval::Range(0, 15)));
+    assert_eq!(frame.get_ret(), Some(Interval::Range(0, 15)));
+    Ok(())
+}